@@ -18,6 +18,7 @@ const cachePomPath = `${cacheProjectPath}/pom.xml`;
 const cacheJarOutputDir = `${cacheProjectPath}/target`;
 const osrsCacheDirectory = './cache/cache';
 const siteItemDataPath = '../site/public/data/item_data.json';
+const serverItemDataPath = '../server/item_data.json';
 const siteMapIconMetaPath = "../site/public/data/map_icons.json";
 const siteMapLabelMetaPath = "../site/public/data/map_labels.json";
 const siteItemImagesPath = '../site/public/icons/items';
@@ -381,6 +382,8 @@ async function moveFiles(globSource, destination) {
 async function moveResults() {
   console.log('\nStep: Moving results to site');
   await retry(() => fs.renameSync('./item_data.json', siteItemDataPath), true);
+  // The server values drops of untradeable items from their alch value
+  await retry(() => fs.copyFileSync(siteItemDataPath, serverItemDataPath), true);
 
   await moveFiles('./item-images/*.webp', siteItemImagesPath);
   await moveFiles("./map-data/tiles/*.webp", siteMapImagesPath);
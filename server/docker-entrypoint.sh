@@ -17,6 +17,12 @@ echo "port = $PG_PORT" >> $CONFIG_FILE
 echo "dbname = \"$PG_DB\"" >> $CONFIG_FILE
 echo "pool.max_size = 16" >> $CONFIG_FILE
 
+if [ -n "$ATTACHMENTS_PATH" ]
+then
+  echo "[attachments]" >> $CONFIG_FILE
+  echo "local_path = \"$ATTACHMENTS_PATH\"" >> $CONFIG_FILE
+fi
+
 SECRET_FILE=secret
 
 echo "[entrypoint] Creating $SECRET_FILE"
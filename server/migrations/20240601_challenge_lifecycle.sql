@@ -0,0 +1,31 @@
+-- Add explicit lifecycle states to group challenges
+-- draft -> scheduled -> active -> ended -> completed / failed
+ALTER TABLE group_challenges ADD COLUMN IF NOT EXISTS status VARCHAR(20) NOT NULL DEFAULT 'draft';
+ALTER TABLE group_challenges ADD COLUMN IF NOT EXISTS activated_at TIMESTAMPTZ;
+ALTER TABLE group_challenges ADD COLUMN IF NOT EXISTS ended_at TIMESTAMPTZ;
+
+-- Existing challenges were live as soon as they were created
+UPDATE group_challenges SET status = 'completed', ended_at = completed_at WHERE completed = TRUE;
+UPDATE group_challenges SET status = 'active', activated_at = COALESCE(start_date, created_at) WHERE completed = FALSE;
+
+ALTER TABLE group_challenges DROP CONSTRAINT IF EXISTS chk_group_challenges_status;
+ALTER TABLE group_challenges ADD CONSTRAINT chk_group_challenges_status
+    CHECK (status IN ('draft', 'scheduled', 'active', 'ended', 'completed', 'failed'));
+
+-- Per-member snapshots taken when a challenge becomes active and when it stops being active
+CREATE TABLE IF NOT EXISTS challenge_baselines (
+    challenge_id BIGINT NOT NULL REFERENCES group_challenges(id) ON DELETE CASCADE,
+    member_id BIGINT NOT NULL REFERENCES groupironman.members(member_id) ON DELETE CASCADE,
+    skills INTEGER[],
+    points BIGINT NOT NULL DEFAULT 0,
+    captured_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Filled in when the challenge leaves the active state
+    final_skills INTEGER[],
+    final_points BIGINT,
+    finalized_at TIMESTAMPTZ,
+
+    PRIMARY KEY (challenge_id, member_id)
+);
+
+-- Index used by the scheduler to find due transitions
+CREATE INDEX IF NOT EXISTS idx_group_challenges_status ON group_challenges(status, start_date, end_date);
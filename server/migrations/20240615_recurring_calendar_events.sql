@@ -0,0 +1,35 @@
+-- Recurrence rules for calendar events. An event with a frequency is a series whose
+-- occurrences repeat from start_time, keeping the event's duration.
+ALTER TABLE calendar_events ADD COLUMN IF NOT EXISTS recurrence_frequency VARCHAR(10);
+ALTER TABLE calendar_events ADD COLUMN IF NOT EXISTS recurrence_interval INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE calendar_events ADD COLUMN IF NOT EXISTS recurrence_until TIMESTAMPTZ;
+ALTER TABLE calendar_events ADD COLUMN IF NOT EXISTS recurrence_count INTEGER;
+-- Start times of occurrences that were removed from the series
+ALTER TABLE calendar_events ADD COLUMN IF NOT EXISTS recurrence_exceptions TIMESTAMPTZ[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE calendar_events DROP CONSTRAINT IF EXISTS chk_calendar_events_recurrence;
+ALTER TABLE calendar_events ADD CONSTRAINT chk_calendar_events_recurrence CHECK (
+    recurrence_frequency IS NULL
+    OR (recurrence_frequency IN ('daily', 'weekly', 'monthly')
+        AND recurrence_interval > 0
+        AND (recurrence_until IS NULL OR recurrence_count IS NULL))
+);
+
+-- Changes to a single occurrence of a series. Unset columns keep the series' values.
+CREATE TABLE IF NOT EXISTS calendar_event_overrides (
+    event_id BIGINT NOT NULL REFERENCES calendar_events(id) ON DELETE CASCADE,
+    -- The occurrence's original start time, which identifies it within the series
+    occurrence_start TIMESTAMPTZ NOT NULL,
+    title VARCHAR(255),
+    description TEXT,
+    start_time TIMESTAMPTZ,
+    end_time TIMESTAMPTZ,
+    all_day BOOLEAN,
+    location TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (event_id, occurrence_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_calendar_events_recurring ON calendar_events(group_id)
+    WHERE recurrence_frequency IS NOT NULL;
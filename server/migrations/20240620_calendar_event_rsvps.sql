@@ -0,0 +1,31 @@
+-- Member responses to calendar events. Recurring events take RSVPs per occurrence,
+-- identified by the occurrence's original start time; it's NULL for one-off events.
+CREATE TABLE IF NOT EXISTS calendar_event_rsvps (
+    id BIGSERIAL PRIMARY KEY,
+    event_id BIGINT NOT NULL REFERENCES calendar_events(id) ON DELETE CASCADE,
+    occurrence_start TIMESTAMPTZ,
+    member_id BIGINT NOT NULL REFERENCES groupironman.members(member_id) ON DELETE CASCADE,
+    response VARCHAR(10) NOT NULL,
+    note TEXT,
+    responded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT chk_calendar_event_rsvps_response CHECK (response IN ('yes', 'no', 'maybe'))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_calendar_event_rsvps_member
+    ON calendar_event_rsvps (event_id, member_id, (COALESCE(occurrence_start, '-infinity'::TIMESTAMPTZ)));
+
+-- Members seen online while an event was in progress, recorded by the server while it runs
+CREATE TABLE IF NOT EXISTS calendar_event_attendance (
+    id BIGSERIAL PRIMARY KEY,
+    event_id BIGINT NOT NULL REFERENCES calendar_events(id) ON DELETE CASCADE,
+    occurrence_start TIMESTAMPTZ,
+    member_id BIGINT NOT NULL REFERENCES groupironman.members(member_id) ON DELETE CASCADE,
+    first_seen TIMESTAMPTZ NOT NULL,
+    last_seen TIMESTAMPTZ NOT NULL,
+    -- Worlds the member was seen on
+    worlds INTEGER[] NOT NULL DEFAULT '{}'
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_calendar_event_attendance_member
+    ON calendar_event_attendance (event_id, member_id, (COALESCE(occurrence_start, '-infinity'::TIMESTAMPTZ)));
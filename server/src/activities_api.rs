@@ -1,5 +1,5 @@
-use actix_web::{web, HttpResponse, Responder};
-use deadpool_postgres::{Client, Pool};
+use actix_web::{web, HttpResponse};
+use deadpool_postgres::Pool;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use std::error::Error;
@@ -141,7 +141,7 @@ pub async fn complete_activity(
     let (group_id, activity_id) = path.into_inner();
     let username = completed_by.get("username")
         .and_then(|v| v.as_str())
-        .ok_or_else(|| "Missing username")?;
+        .ok_or("Missing username")?;
     
     client.execute(
         "UPDATE activities SET completed = true, completed_at = NOW(), completed_by = $1 WHERE id = $2 AND group_id = $3",
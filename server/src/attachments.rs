@@ -0,0 +1,536 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::config::{AttachmentConfig, BlobStoreKind, Config};
+use crate::error::ApiError;
+use actix_web::{http::header, web, web::Bytes, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, Pool};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::{task, time};
+use tokio_postgres::Row;
+
+#[cfg(test)]
+mod attachment_tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const WEBP: &[u8] = b"RIFF\x24\0\0\0WEBPVP8 ";
+
+    fn config() -> AttachmentConfig {
+        AttachmentConfig {
+            store: BlobStoreKind::Local,
+            local_path: "attachments".to_string(),
+            max_bytes: 32,
+            content_types: vec!["image/png".to_string(), "image/webp".to_string()],
+        }
+    }
+
+    #[test]
+    fn content_type_is_read_from_file_contents() {
+        assert_eq!(sniff_content_type(PNG), Some("image/png"));
+        assert_eq!(sniff_content_type(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("image/jpeg"));
+        assert_eq!(sniff_content_type(b"GIF89a\x01\0"), Some("image/gif"));
+        assert_eq!(sniff_content_type(WEBP), Some("image/webp"));
+        assert_eq!(sniff_content_type(b"<html><script>"), None);
+        assert_eq!(sniff_content_type(b"RIFF\x24\0\0\0WAVEfmt "), None);
+    }
+
+    #[test]
+    fn uploads_are_checked_against_the_limits() {
+        let config = config();
+        assert_eq!(check_upload(PNG, Some("image/png"), &config), Ok("image/png"));
+        assert_eq!(check_upload(WEBP, None, &config), Ok("image/webp"));
+        assert_eq!(check_upload(PNG, Some("image/png; charset=binary"), &config), Ok("image/png"));
+        assert!(check_upload(b"", Some("image/png"), &config).is_err());
+        assert!(check_upload(&[PNG, &[0u8; 32][..]].concat(), Some("image/png"), &config).is_err());
+        // A real file of a type the group hasn't allowed
+        assert!(check_upload(b"GIF89a\x01\0", Some("image/gif"), &config).is_err());
+        // Declared as one type but containing another
+        assert!(check_upload(PNG, Some("image/webp"), &config).is_err());
+    }
+}
+
+/// Attachments allowed on a single drop, activity or strategy
+const MAX_ATTACHMENTS_PER_PARENT: i64 = 10;
+
+/// Queued blob deletions handled per run of the cleanup job
+const BLOB_DELETION_BATCH: i64 = 100;
+
+/// Storage for attachment contents, keyed by the attachment's storage key
+pub trait BlobStore: Send + Sync {
+    fn put<'a>(&'a self, key: &'a str, data: Bytes) -> BoxFuture<'a, io::Result<()>>;
+
+    /// The stored contents, or `None` if nothing is stored under the key
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<Option<Bytes>>>;
+
+    /// Remove the contents. Removing a key that isn't stored is not an error.
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<()>>;
+}
+
+/// Blob store that keeps each attachment as a file under a directory
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path_for(&self, key: &str) -> io::Result<PathBuf> {
+        let valid = !key.is_empty()
+            && key
+                .split('/')
+                .all(|part| !part.is_empty() && part != "." && part != ".." && !part.contains('\\'));
+        if !valid {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid storage key {}", key)));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+async fn run_blocking<T: Send + 'static>(f: impl FnOnce() -> io::Result<T> + Send + 'static) -> io::Result<T> {
+    web::block(f)
+        .await
+        .map_err(|err| io::Error::other(err.to_string()))?
+}
+
+impl BlobStore for LocalBlobStore {
+    fn put<'a>(&'a self, key: &'a str, data: Bytes) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let path = self.path_for(key)?;
+            run_blocking(move || {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                // Write to a temporary file first so a failed write never leaves a partial file behind the key
+                let partial = path.with_extension("partial");
+                std::fs::write(&partial, &data)?;
+                std::fs::rename(&partial, &path)
+            })
+            .await
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<Option<Bytes>>> {
+        Box::pin(async move {
+            let path = self.path_for(key)?;
+            run_blocking(move || match std::fs::read(&path) {
+                Ok(data) => Ok(Some(Bytes::from(data))),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err),
+            })
+            .await
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let path = self.path_for(key)?;
+            run_blocking(move || match std::fs::remove_file(&path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            })
+            .await
+        })
+    }
+}
+
+/// Create the blob store chosen in the config
+pub fn blob_store_from_config(config: &AttachmentConfig) -> Arc<dyn BlobStore> {
+    match config.store {
+        BlobStoreKind::Local => Arc::new(LocalBlobStore::new(&config.local_path)),
+    }
+}
+
+/// What an attachment is attached to
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentParent {
+    ValuableDrop,
+    /// A completed activity, usually as proof
+    PlayerActivity,
+    BossStrategy,
+}
+
+impl AttachmentParent {
+    fn column(&self) -> &'static str {
+        match self {
+            AttachmentParent::ValuableDrop => "drop_id",
+            AttachmentParent::PlayerActivity => "player_activity_id",
+            AttachmentParent::BossStrategy => "boss_strategy_id",
+        }
+    }
+
+    /// Query returning a row if the parent ($1) exists and belongs to the group ($2)
+    fn ownership_query(&self) -> &'static str {
+        match self {
+            AttachmentParent::ValuableDrop => {
+                "SELECT 1 FROM groupironman.valuable_drops d
+                 JOIN groupironman.members m ON m.member_id = d.member_id
+                 WHERE d.drop_id = $1 AND m.group_id = $2"
+            }
+            AttachmentParent::PlayerActivity => {
+                "SELECT 1 FROM groupironman.player_activities pa
+                 JOIN groupironman.members m ON m.member_id = pa.member_id
+                 WHERE pa.player_activity_id = $1 AND m.group_id = $2"
+            }
+            AttachmentParent::BossStrategy => "SELECT 1 FROM boss_strategies WHERE id = $1 AND group_id = $2",
+        }
+    }
+}
+
+/// Query parameters naming an attachment's parent
+#[derive(Deserialize)]
+pub struct AttachmentParams {
+    pub parent_type: AttachmentParent,
+    pub parent_id: i64,
+}
+
+#[derive(Serialize)]
+pub struct Attachment {
+    pub attachment_id: i64,
+    pub parent_type: AttachmentParent,
+    pub parent_id: i64,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub created_at: DateTime<Utc>,
+
+    /// Where to download the file. Requests need the group's Authorization header.
+    pub url: String,
+}
+
+const ATTACHMENT_COLUMNS: &str =
+    "attachment_id, drop_id, player_activity_id, boss_strategy_id, content_type, size_bytes, created_at";
+
+fn attachment_from_row(row: &Row, group_name: &str) -> Result<Attachment, tokio_postgres::Error> {
+    let attachment_id: i64 = row.try_get(0)?;
+    let (parent_type, parent_id) = match (
+        row.try_get::<_, Option<i64>>(1)?,
+        row.try_get::<_, Option<i64>>(2)?,
+        row.try_get::<_, Option<i64>>(3)?,
+    ) {
+        (Some(drop_id), _, _) => (AttachmentParent::ValuableDrop, drop_id),
+        (_, Some(player_activity_id), _) => (AttachmentParent::PlayerActivity, player_activity_id),
+        (_, _, boss_strategy_id) => (AttachmentParent::BossStrategy, boss_strategy_id.unwrap_or_default()),
+    };
+
+    Ok(Attachment {
+        attachment_id,
+        parent_type,
+        parent_id,
+        content_type: row.try_get(4)?,
+        size_bytes: row.try_get(5)?,
+        created_at: row.try_get(6)?,
+        url: format!("/api/group/{}/attachments/{}", group_name, attachment_id),
+    })
+}
+
+/// The file type from the first bytes of a file, for the types attachments can be
+pub fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if data.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else {
+        None
+    }
+}
+
+/// Check an upload against the size and content type limits, returning its content type
+///
+/// The type comes from the file contents rather than the request, so a file can't be
+/// served back as something other than what it is.
+pub fn check_upload(data: &[u8], declared: Option<&str>, config: &AttachmentConfig) -> Result<&'static str, String> {
+    if data.is_empty() {
+        return Err("Attachment is empty".to_string());
+    }
+    if data.len() > config.max_bytes {
+        return Err(format!("Attachments can be at most {} bytes", config.max_bytes));
+    }
+
+    let content_type = match sniff_content_type(data) {
+        Some(content_type) if config.content_types.iter().any(|allowed| allowed == content_type) => content_type,
+        _ => {
+            return Err(format!(
+                "Attachments must be one of: {}",
+                config.content_types.join(", ")
+            ))
+        }
+    };
+
+    if let Some(declared) = declared {
+        let declared = declared.split(';').next().unwrap_or_default().trim();
+        if !declared.eq_ignore_ascii_case(content_type) {
+            return Err(format!("File is {} but was sent as {}", content_type, declared));
+        }
+    }
+
+    Ok(content_type)
+}
+
+fn file_extension(content_type: &str) -> &'static str {
+    match content_type {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "application/pdf" => "pdf",
+        _ => "bin",
+    }
+}
+
+fn attachment_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "status": "error",
+        "message": "Attachment not found"
+    }))
+}
+
+/// Upload a file and attach it to a drop, completed activity or boss strategy
+///
+/// The request body is the file itself. Its type is checked against the file
+/// contents and the configured content types.
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_attachment(
+    pool: web::Data<Pool>,
+    store: web::Data<dyn BlobStore>,
+    config: web::Data<Config>,
+    auth: AuthedGroupId,
+    path: web::Path<String>,
+    params: web::Query<AttachmentParams>,
+    req: HttpRequest,
+    body: Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let group_name = path.into_inner();
+    let declared = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    let content_type = match check_upload(&body, declared, &config.attachments) {
+        Ok(content_type) => content_type,
+        Err(message) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": message
+            })))
+        }
+    };
+
+    let client = pool.get().await?;
+    let parent = client
+        .query_opt(params.parent_type.ownership_query(), &[&params.parent_id, &auth.group_id])
+        .await?;
+    if parent.is_none() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "Nothing to attach to was found in your group"
+        })));
+    }
+
+    let column = params.parent_type.column();
+    let count: i64 = client
+        .query_one(
+            format!("SELECT COUNT(*) FROM groupironman.attachments WHERE {} = $1", column).as_str(),
+            &[&params.parent_id],
+        )
+        .await?
+        .try_get(0)?;
+    if count >= MAX_ATTACHMENTS_PER_PARENT {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": format!("At most {} files can be attached", MAX_ATTACHMENTS_PER_PARENT)
+        })));
+    }
+
+    let storage_key = format!(
+        "{}/{}.{}",
+        auth.group_id,
+        uuid::Uuid::new_v4().to_simple(),
+        file_extension(content_type)
+    );
+    let size_bytes = body.len() as i64;
+    store.put(&storage_key, body).await?;
+
+    let insert = client
+        .query_one(
+            format!(
+                "INSERT INTO groupironman.attachments (group_id, {}, storage_key, content_type, size_bytes)
+                 VALUES ($1, $2, $3, $4, $5)
+                 RETURNING {}",
+                column, ATTACHMENT_COLUMNS
+            )
+            .as_str(),
+            &[&auth.group_id, &params.parent_id, &storage_key, &content_type, &size_bytes],
+        )
+        .await;
+    let row = match insert {
+        Ok(row) => row,
+        Err(err) => {
+            if let Err(delete_err) = store.delete(&storage_key).await {
+                log::error!("Failed to remove unused attachment {}: {}", storage_key, delete_err);
+            }
+            return Err(err.into());
+        }
+    };
+
+    Ok(HttpResponse::Created().json(attachment_from_row(&row, &group_name)?))
+}
+
+/// List the files attached to a drop, completed activity or boss strategy
+pub async fn get_attachments(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<String>,
+    params: web::Query<AttachmentParams>,
+) -> Result<HttpResponse, ApiError> {
+    let group_name = path.into_inner();
+    let client = pool.get().await?;
+    let rows = client
+        .query(
+            format!(
+                "SELECT {} FROM groupironman.attachments
+                 WHERE group_id = $1 AND {} = $2
+                 ORDER BY created_at, attachment_id",
+                ATTACHMENT_COLUMNS,
+                params.parent_type.column()
+            )
+            .as_str(),
+            &[&auth.group_id, &params.parent_id],
+        )
+        .await?;
+
+    let attachments = rows
+        .iter()
+        .map(|row| attachment_from_row(row, &group_name))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(HttpResponse::Ok().json(attachments))
+}
+
+/// Download an attachment's file
+pub async fn get_attachment_content(
+    pool: web::Data<Pool>,
+    store: web::Data<dyn BlobStore>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, attachment_id) = path.into_inner();
+    let client = pool.get().await?;
+    let stmt = client
+        .prepare_cached(
+            "SELECT storage_key, content_type FROM groupironman.attachments
+             WHERE attachment_id = $1 AND group_id = $2"
+        )
+        .await?;
+    let row = match client.query_opt(&stmt, &[&attachment_id, &auth.group_id]).await? {
+        Some(row) => row,
+        None => return Ok(attachment_not_found()),
+    };
+    let storage_key: String = row.try_get(0)?;
+    let content_type: String = row.try_get(1)?;
+
+    let data = match store.get(&storage_key).await? {
+        Some(data) => data,
+        None => {
+            log::warn!("Attachment {} is missing from the blob store", storage_key);
+            return Ok(attachment_not_found());
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type.as_str())
+        .append_header(("X-Content-Type-Options", "nosniff"))
+        .append_header(("Cache-Control", "private, max-age=86400"))
+        .append_header((
+            "Content-Disposition",
+            format!("inline; filename=\"attachment-{}.{}\"", attachment_id, file_extension(&content_type)),
+        ))
+        .body(data))
+}
+
+/// Delete an attachment. The file is removed from the blob store by the cleanup job.
+pub async fn delete_attachment(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, attachment_id) = path.into_inner();
+    let client = pool.get().await?;
+    let stmt = client
+        .prepare_cached(
+            "DELETE FROM groupironman.attachments
+             WHERE attachment_id = $1 AND group_id = $2
+             RETURNING attachment_id"
+        )
+        .await?;
+
+    if client.query_opt(&stmt, &[&attachment_id, &auth.group_id]).await?.is_none() {
+        return Ok(attachment_not_found());
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Remove the files of deleted attachments from the blob store
+///
+/// Deleting an attachment row, directly or along with its parent, queues its
+/// file for removal. Files that fail to delete stay queued for the next run.
+async fn remove_queued_blobs(client: &Client, store: &dyn BlobStore) -> Result<(), ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "SELECT deletion_id, storage_key FROM groupironman.attachment_blob_deletions
+             ORDER BY deletion_id
+             LIMIT $1"
+        )
+        .await?;
+    let rows = client.query(&stmt, &[&BLOB_DELETION_BATCH]).await?;
+
+    let mut removed: Vec<i64> = Vec::new();
+    for row in &rows {
+        let deletion_id: i64 = row.try_get(0)?;
+        let storage_key: String = row.try_get(1)?;
+        match store.delete(&storage_key).await {
+            Ok(()) => removed.push(deletion_id),
+            Err(err) => log::error!("Failed to delete attachment {}: {}", storage_key, err),
+        }
+    }
+
+    if !removed.is_empty() {
+        let delete_stmt = client
+            .prepare_cached("DELETE FROM groupironman.attachment_blob_deletions WHERE deletion_id = ANY($1)")
+            .await?;
+        client.execute(&delete_stmt, &[&removed]).await?;
+    }
+
+    Ok(())
+}
+
+pub fn start_attachment_cleanup(db_pool: Pool, store: Arc<dyn BlobStore>) {
+    task::spawn(async move {
+        let mut interval = time::interval(std::time::Duration::from_secs(60));
+
+        loop {
+            interval.tick().await;
+
+            match db_pool.get().await {
+                Ok(client) => {
+                    if let Err(err) = remove_queued_blobs(&client, store.as_ref()).await {
+                        log::error!("Failed to remove deleted attachments: {}", err);
+                    }
+                }
+                Err(err) => {
+                    log::error!("Failed to get db client: {}", err);
+                }
+            }
+        }
+    });
+}
@@ -41,6 +41,7 @@ where
 
 pub struct AuthenticationResult {
     pub group_id: i64,
+    #[allow(dead_code)]
     pub version: i32,
 }
 type AuthenticationInfo = Rc<AuthenticationResult>;
@@ -175,11 +175,6 @@ pub async fn get_skill_data(
     Ok(web::Json(group_skill_data))
 }
 
-#[derive(Deserialize)]
-#[serde(deny_unknown_fields)]
-pub struct CollectionLogQuery {
-    pub member_name: String
-}
 #[get("/collection-log")]
 pub async fn get_collection_log(
     auth: Authenticated,
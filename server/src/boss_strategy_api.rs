@@ -1,5 +1,5 @@
-use actix_web::{web, HttpResponse, Responder};
-use deadpool_postgres::{Client, Pool};
+use actix_web::{web, HttpResponse};
+use deadpool_postgres::Pool;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 
@@ -119,8 +119,12 @@ pub async fn delete_strategy(
     let client = pool.get().await?;
     let (group_id, boss_name) = path.into_inner();
     
+    // Attachments aren't tied to boss_strategies by a foreign key, so they're deleted alongside it
     client.execute(
-        "DELETE FROM boss_strategies WHERE group_id = $1 AND LOWER(boss_name) = LOWER($2)",
+        "WITH deleted AS (
+            DELETE FROM boss_strategies WHERE group_id = $1 AND LOWER(boss_name) = LOWER($2) RETURNING id
+        )
+        DELETE FROM groupironman.attachments WHERE boss_strategy_id IN (SELECT id FROM deleted)",
         &[&group_id, &boss_name]
     ).await?;
     
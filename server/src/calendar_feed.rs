@@ -0,0 +1,538 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::crypto::token_hash;
+use crate::error::ApiError;
+use crate::shared_calendar_api::{event_from_row, load_overrides, EVENT_COLUMNS};
+use crate::time_zones::{group_time_zone, local_date};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use deadpool_postgres::Pool;
+use serde::Serialize;
+
+#[cfg(test)]
+mod calendar_feed_tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn text_is_escaped() {
+        assert_eq!(escape_text("Raids; CoX, ToB\nBring food\\pots"), "Raids\\; CoX\\, ToB\\nBring food\\\\pots");
+        assert_eq!(escape_text("a\r\nb"), "a\\nb");
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let line = format!("DESCRIPTION:{}", "é".repeat(60));
+        let folded = fold_line(&line);
+        let parts: Vec<&str> = folded.split("\r\n").collect();
+        assert!(parts.len() > 1);
+        assert!(parts.iter().all(|part| part.len() <= 75));
+        assert!(parts[1..].iter().all(|part| part.starts_with(' ')));
+        let unfolded: String = parts.iter().enumerate().map(|(i, part)| if i == 0 { *part } else { &part[1..] }).collect();
+        assert_eq!(unfolded, line);
+        assert_eq!(fold_line("SUMMARY:Short"), "SUMMARY:Short");
+    }
+
+    #[test]
+    fn all_day_events_end_the_day_after() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 4, 18, 0, 0).unwrap();
+        let (from, to) = event_times(start, None, true, Tz::UTC);
+        assert_eq!(from, FeedTime::Date(NaiveDate::from_ymd_opt(2024, 5, 4).unwrap()));
+        assert_eq!(to, Some(FeedTime::Date(NaiveDate::from_ymd_opt(2024, 5, 5).unwrap())));
+
+        let end = start + Duration::days(2);
+        assert_eq!(event_times(start, Some(end), true, Tz::UTC).1, Some(FeedTime::Date(NaiveDate::from_ymd_opt(2024, 5, 7).unwrap())));
+        // An end before the start is ignored
+        assert_eq!(event_times(start, Some(start - Duration::hours(1)), false, Tz::UTC).1, None);
+
+        // The day is the group's local day
+        let (from, _) = event_times(start, None, true, chrono_tz::Asia::Tokyo);
+        assert_eq!(from, FeedTime::Date(NaiveDate::from_ymd_opt(2024, 5, 5).unwrap()));
+    }
+
+    #[test]
+    fn calendar_is_rendered() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 5, 4, 18, 0, 0).unwrap();
+        let (start, end) = event_times(start, Some(start + Duration::hours(2)), false, Tz::UTC);
+        let events = vec![FeedEvent {
+            uid: "calendar-event-7@group-ironmen".to_string(),
+            summary: "Raids night".to_string(),
+            description: Some("Bring, food".to_string()),
+            location: None,
+            category: Some("raid".to_string()),
+            start,
+            end,
+            last_modified: None,
+            recurrence_rule: None,
+            exceptions: Vec::new(),
+            recurrence_id: None,
+        }];
+
+        let ics = render_calendar("Iron Squad", Tz::UTC, &events, now);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.contains("X-WR-CALNAME:Iron Squad\r\n"));
+        assert!(ics.contains("DTSTAMP:20240501T120000Z\r\n"));
+        assert!(ics.contains("DTSTART:20240504T180000Z\r\nDTEND:20240504T200000Z\r\n"));
+        assert!(ics.contains("DESCRIPTION:Bring\\, food\r\n"));
+        assert!(!ics.contains("LOCATION"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn recurring_events_are_rendered() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 5, 4, 18, 0, 0).unwrap();
+        let series = FeedEvent {
+            uid: "calendar-event-7@group-ironmen".to_string(),
+            summary: "Raids night".to_string(),
+            description: None,
+            location: None,
+            category: None,
+            start: FeedTime::DateTime(start),
+            end: None,
+            last_modified: None,
+            recurrence_rule: Some("FREQ=WEEKLY;INTERVAL=1".to_string()),
+            exceptions: vec![FeedTime::DateTime(start + Duration::weeks(1))],
+            recurrence_id: None,
+        };
+        let moved = FeedEvent {
+            start: FeedTime::DateTime(start + Duration::weeks(2) + Duration::hours(1)),
+            recurrence_rule: None,
+            exceptions: Vec::new(),
+            recurrence_id: Some(FeedTime::DateTime(start + Duration::weeks(2))),
+            ..series.clone()
+        };
+
+        let ics = render_calendar("Iron Squad", Tz::UTC, &[series, moved], now);
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;INTERVAL=1\r\nEXDATE:20240511T180000Z\r\n"));
+        assert!(ics.contains("RECURRENCE-ID:20240518T180000Z\r\nDTSTART:20240518T190000Z\r\n"));
+        assert_eq!(ics.matches("UID:calendar-event-7@group-ironmen").count(), 2);
+    }
+
+    #[test]
+    fn recurring_times_are_local_to_the_group() {
+        let london = chrono_tz::Europe::London;
+        let start = Utc.with_ymd_and_hms(2024, 5, 4, 18, 0, 0).unwrap();
+        assert_eq!(
+            FeedTime::DateTime(start).in_time_zone(london).property("DTSTART"),
+            "DTSTART;TZID=Europe/London:20240504T190000"
+        );
+        assert_eq!(FeedTime::DateTime(start).in_time_zone(Tz::UTC), FeedTime::DateTime(start));
+
+        let ics = render_calendar("Iron Squad", london, &[], start);
+        assert!(ics.contains("X-WR-TIMEZONE:Europe/London\r\n"));
+    }
+}
+
+/// Calendar events further in the past than this are left out of the feed
+const FEED_HISTORY_DAYS: i64 = 180;
+
+/// When an event in the feed happens
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeedTime {
+    /// A whole day
+    Date(NaiveDate),
+    DateTime(DateTime<Utc>),
+    /// A wall-clock time in a time zone, so recurrences keep their local time across DST changes
+    Local(NaiveDateTime, Tz),
+}
+
+impl FeedTime {
+    fn property(&self, name: &str) -> String {
+        match self {
+            FeedTime::Date(date) => format!("{};VALUE=DATE:{}", name, date.format("%Y%m%d")),
+            FeedTime::DateTime(time) => format!("{}:{}", name, format_utc(*time)),
+            FeedTime::Local(time, tz) => format!("{};TZID={}:{}", name, tz.name(), time.format("%Y%m%dT%H%M%S")),
+        }
+    }
+
+    /// The same time as local time in the time zone. UTC times and dates are unchanged.
+    pub fn in_time_zone(self, tz: Tz) -> FeedTime {
+        match self {
+            FeedTime::DateTime(time) if tz != Tz::UTC => FeedTime::Local(time.with_timezone(&tz).naive_local(), tz),
+            other => other,
+        }
+    }
+}
+
+/// An event as it appears in the iCalendar feed
+#[derive(Clone)]
+pub struct FeedEvent {
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub category: Option<String>,
+    pub start: FeedTime,
+    pub end: Option<FeedTime>,
+    pub last_modified: Option<DateTime<Utc>>,
+    /// RRULE value for a recurring series
+    pub recurrence_rule: Option<String>,
+    /// Occurrences removed from a recurring series
+    pub exceptions: Vec<FeedTime>,
+    /// Set when this is a single edited occurrence of a series with the same UID
+    pub recurrence_id: Option<FeedTime>,
+}
+
+fn format_utc(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape text for an iCalendar property value
+pub fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+/// Split a content line into lines of at most 75 bytes, as iCalendar requires.
+/// Continuation lines start with a space, and characters are never split.
+pub fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 74 * 3);
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        folded.push(c);
+        line_length += c.len_utf8();
+    }
+    folded
+}
+
+/// Start and end of a calendar event in the feed. All-day events cover whole days in
+/// the group's time zone and end on the day after their last day, which is what calendar apps expect.
+pub fn event_times(start: DateTime<Utc>, end: Option<DateTime<Utc>>, all_day: bool, tz: Tz) -> (FeedTime, Option<FeedTime>) {
+    let end = end.filter(|end| *end >= start);
+    if all_day {
+        let first_day = local_date(tz, start);
+        let last_day = end.map_or(first_day, |end| local_date(tz, end));
+        (FeedTime::Date(first_day), Some(FeedTime::Date(last_day + Duration::days(1))))
+    } else {
+        (FeedTime::DateTime(start), end.filter(|end| *end > start).map(FeedTime::DateTime))
+    }
+}
+
+/// Render a complete iCalendar document
+///
+/// Local times refer to the time zone by its IANA name without a VTIMEZONE
+/// definition, which the common calendar apps accept.
+pub fn render_calendar(name: &str, tz: Tz, events: &[FeedEvent], now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Group Ironmen//Shared Calendar//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+        format!("X-WR-TIMEZONE:{}", tz.name()),
+        "REFRESH-INTERVAL;VALUE=DURATION:PT1H".to_string(),
+        "X-PUBLISHED-TTL:PT1H".to_string(),
+    ];
+
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{}", format_utc(now)));
+        if let Some(recurrence_id) = &event.recurrence_id {
+            lines.push(recurrence_id.property("RECURRENCE-ID"));
+        }
+        lines.push(event.start.property("DTSTART"));
+        if let Some(end) = &event.end {
+            lines.push(end.property("DTEND"));
+        }
+        if let Some(rule) = &event.recurrence_rule {
+            lines.push(format!("RRULE:{}", rule));
+        }
+        for exception in &event.exceptions {
+            lines.push(exception.property("EXDATE"));
+        }
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        if let Some(description) = event.description.as_deref().filter(|text| !text.is_empty()) {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        if let Some(location) = event.location.as_deref().filter(|text| !text.is_empty()) {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+        if let Some(category) = event.category.as_deref().filter(|text| !text.is_empty()) {
+            lines.push(format!("CATEGORIES:{}", escape_text(category)));
+        }
+        if let Some(last_modified) = event.last_modified {
+            lines.push(format!("LAST-MODIFIED:{}", format_utc(last_modified)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_line(line) + "\r\n").collect()
+}
+
+/// Load everything that goes in a group's feed: calendar events plus challenge
+/// and milestone deadlines
+async fn load_feed_events(client: &deadpool_postgres::Client, group_id: i64, tz: Tz) -> Result<Vec<FeedEvent>, ApiError> {
+    let since = Utc::now() - Duration::days(FEED_HISTORY_DAYS);
+    let mut events = Vec::new();
+
+    // Recurring series are included whole while any of their occurrences are recent enough;
+    // calendar apps expand them from the RRULE
+    let event_rows = client
+        .query(
+            &format!(
+                "SELECT {} FROM calendar_events
+                 WHERE group_id = $1 AND (
+                    COALESCE(end_time, start_time) >= $2
+                    OR (recurrence_frequency IS NOT NULL AND (recurrence_until IS NULL OR recurrence_until >= $2))
+                 )
+                 ORDER BY start_time",
+                EVENT_COLUMNS
+            ),
+            &[&group_id, &since],
+        )
+        .await?;
+    let calendar_events: Vec<_> = event_rows.iter().map(event_from_row).collect();
+    let recurring_ids: Vec<i64> = calendar_events
+        .iter()
+        .filter(|event| event.recurrence.is_some())
+        .filter_map(|event| event.id)
+        .collect();
+    let mut overrides: Vec<_> = load_overrides(client, &recurring_ids).await?.into_iter().collect();
+    overrides.sort_by_key(|(key, _)| *key);
+
+    // Recurring series are written in local time so calendar apps repeat them at the
+    // same local time as the site does
+    let occurrence_time = |time: DateTime<Utc>, all_day: bool| {
+        if all_day {
+            FeedTime::Date(local_date(tz, time))
+        } else {
+            FeedTime::DateTime(time).in_time_zone(tz)
+        }
+    };
+    for event in &calendar_events {
+        let id = event.id.unwrap_or_default();
+        let (mut start, mut end) = event_times(event.start_time, event.end_time, event.all_day, tz);
+        if event.recurrence.is_some() {
+            start = start.in_time_zone(tz);
+            end = end.map(|end| end.in_time_zone(tz));
+        }
+        let series = FeedEvent {
+            uid: format!("calendar-event-{}@group-ironmen", id),
+            summary: event.title.clone(),
+            description: event.description.clone(),
+            category: Some(event.event_type.clone()),
+            start,
+            end,
+            location: event.location.clone(),
+            last_modified: Some(event.updated_at),
+            recurrence_rule: event.recurrence.as_ref().map(|rule| rule.to_rrule(event.all_day)),
+            exceptions: event.recurrence.as_ref().map_or_else(Vec::new, |rule| {
+                rule.exceptions.iter().map(|time| occurrence_time(*time, event.all_day)).collect()
+            }),
+            recurrence_id: None,
+        };
+
+        // Edited occurrences are separate VEVENTs sharing the series' UID
+        let duration = event.end_time.map(|end| end - event.start_time);
+        for ((_, occurrence_start), changes) in overrides.iter().filter(|((event_id, _), _)| *event_id == id) {
+            let mut occurrence = event.clone();
+            occurrence.start_time = *occurrence_start;
+            occurrence.end_time = duration.map(|duration| *occurrence_start + duration);
+            changes.apply(&mut occurrence);
+
+            let (start, end) = event_times(occurrence.start_time, occurrence.end_time, occurrence.all_day, tz);
+            events.push(FeedEvent {
+                summary: occurrence.title,
+                description: occurrence.description,
+                location: occurrence.location,
+                start,
+                end,
+                recurrence_rule: None,
+                exceptions: Vec::new(),
+                recurrence_id: Some(occurrence_time(*occurrence_start, event.all_day)),
+                ..series.clone()
+            });
+        }
+        events.push(series);
+    }
+
+    // Challenges appear once they're scheduled; drafts aren't shown to the group yet
+    let challenge_rows = client
+        .query(
+            "SELECT id, title, description, end_date
+             FROM group_challenges
+             WHERE group_id = $1 AND end_date IS NOT NULL AND end_date >= $2 AND status <> 'draft'
+             ORDER BY end_date",
+            &[&group_id, &since],
+        )
+        .await?;
+    for row in &challenge_rows {
+        let id: i64 = row.try_get(0)?;
+        let title: String = row.try_get(1)?;
+        events.push(FeedEvent {
+            uid: format!("challenge-{}@group-ironmen", id),
+            summary: format!("Challenge ends: {}", title),
+            description: row.try_get(2)?,
+            location: None,
+            category: Some("challenge".to_string()),
+            start: FeedTime::DateTime(row.try_get(3)?),
+            end: None,
+            last_modified: None,
+            recurrence_rule: None,
+            exceptions: Vec::new(),
+            recurrence_id: None,
+        });
+    }
+
+    let milestone_rows = client
+        .query(
+            "SELECT milestone_id, title, description, end_date
+             FROM groupironman.group_milestones
+             WHERE group_id = $1::BIGINT AND end_date IS NOT NULL AND end_date >= $2
+             ORDER BY end_date",
+            &[&group_id, &since],
+        )
+        .await?;
+    for row in &milestone_rows {
+        let id: i32 = row.try_get(0)?;
+        let title: String = row.try_get(1)?;
+        events.push(FeedEvent {
+            uid: format!("milestone-{}@group-ironmen", id),
+            summary: format!("Milestone deadline: {}", title),
+            description: row.try_get(2)?,
+            location: None,
+            category: Some("milestone".to_string()),
+            start: FeedTime::DateTime(row.try_get(3)?),
+            end: None,
+            last_modified: None,
+            recurrence_rule: None,
+            exceptions: Vec::new(),
+            recurrence_id: None,
+        });
+    }
+
+    Ok(events)
+}
+
+/// The group's calendar as an iCalendar feed, for subscribing from calendar apps
+///
+/// Authorized by the feed token in the URL rather than the Authorization header.
+/// Unknown groups and wrong or revoked tokens are both a 404.
+pub async fn get_calendar_feed(
+    pool: web::Data<Pool>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (group_name, feed_token) = path.into_inner();
+    let client = pool.get().await?;
+
+    let stmt = client
+        .prepare_cached(
+            "UPDATE groupironman.calendar_feed_tokens t
+             SET last_used_at = NOW()
+             FROM groupironman.groups g
+             WHERE g.group_id = t.group_id AND g.group_name = $1 AND t.token_hash = $2
+             RETURNING g.group_id"
+        )
+        .await?;
+    let group_id: i64 = match client
+        .query_opt(&stmt, &[&group_name, &token_hash(&feed_token, &group_name)])
+        .await?
+    {
+        Some(row) => row.try_get(0)?,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+
+    let tz = group_time_zone(&client, group_id).await?;
+    let events = load_feed_events(&client, group_id, tz).await?;
+    let calendar = render_calendar(&group_name, tz, &events, Utc::now());
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .append_header(("Cache-Control", "private, max-age=300"))
+        .append_header(("Content-Disposition", "inline; filename=\"calendar.ics\""))
+        .body(calendar))
+}
+
+/// Whether the group has a calendar feed
+#[derive(Serialize)]
+pub struct FeedTokenStatus {
+    pub active: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Check whether the group has a calendar feed URL and when it was last used
+pub async fn get_feed_token(pool: web::Data<Pool>, auth: AuthedGroupId) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let stmt = client
+        .prepare_cached("SELECT created_at, last_used_at FROM groupironman.calendar_feed_tokens WHERE group_id = $1")
+        .await?;
+    let status = match client.query_opt(&stmt, &[&auth.group_id]).await? {
+        Some(row) => FeedTokenStatus {
+            active: true,
+            created_at: row.try_get(0)?,
+            last_used_at: row.try_get(1)?,
+        },
+        None => FeedTokenStatus {
+            active: false,
+            created_at: None,
+            last_used_at: None,
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// Create the group's calendar feed URL, revoking any previous one
+///
+/// The token is only returned here; only its hash is stored.
+pub async fn create_feed_token(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let group_name = path.into_inner();
+    let feed_token = uuid::Uuid::new_v4().to_simple().to_string();
+
+    let client = pool.get().await?;
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO groupironman.calendar_feed_tokens (group_id, token_hash)
+             VALUES ($1, $2)
+             ON CONFLICT (group_id) DO UPDATE SET token_hash = EXCLUDED.token_hash, created_at = NOW(), last_used_at = NULL"
+        )
+        .await?;
+    client
+        .execute(&stmt, &[&auth.group_id, &token_hash(&feed_token, &group_name)])
+        .await?;
+
+    let connection = req.connection_info();
+    let url = reqwest::Url::parse(&format!("{}://{}/", connection.scheme(), connection.host()))
+        .ok()
+        .and_then(|mut url| {
+            url.path_segments_mut()
+                .ok()?
+                .pop_if_empty()
+                .extend(["api", "calendar", group_name.as_str(), feed_token.as_str(), "feed.ics"]);
+            Some(url.to_string())
+        });
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "token": feed_token,
+        "url": url,
+    })))
+}
+
+/// Revoke the group's calendar feed URL
+pub async fn revoke_feed_token(pool: web::Data<Pool>, auth: AuthedGroupId) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let stmt = client
+        .prepare_cached("DELETE FROM groupironman.calendar_feed_tokens WHERE group_id = $1")
+        .await?;
+    client.execute(&stmt, &[&auth.group_id]).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
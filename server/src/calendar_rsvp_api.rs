@@ -0,0 +1,541 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::error::ApiError;
+use crate::models::SHARED_MEMBER;
+use crate::shared_calendar_api::{event_from_row, expand_event, load_overrides, CalendarEvent, EventOccurrence, EVENT_COLUMNS};
+use crate::time_zones::{group_time_zone, local_date, start_of_local_day};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use deadpool_postgres::{Client, Pool};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use tokio::{task, time};
+
+#[cfg(test)]
+mod rsvp_tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    fn event(start_time: DateTime<Utc>, end_time: Option<DateTime<Utc>>, all_day: bool) -> CalendarEvent {
+        CalendarEvent {
+            id: Some(1),
+            group_id: 1,
+            title: "Raids night".to_string(),
+            description: None,
+            event_type: "raid".to_string(),
+            start_time,
+            end_time,
+            all_day,
+            location: None,
+            created_by: "a".to_string(),
+            created_at: start_time,
+            updated_at: start_time,
+            recurrence: None,
+        }
+    }
+
+    #[test]
+    fn attendance_windows() {
+        assert_eq!(attendance_window(&event(at(4, 18), Some(at(4, 21)), false), Tz::UTC), (at(4, 18), at(4, 21)));
+        assert_eq!(attendance_window(&event(at(4, 18), None, false), Tz::UTC), (at(4, 18), at(4, 19)));
+        assert_eq!(attendance_window(&event(at(4, 18), None, true), Tz::UTC), (at(4, 0), at(5, 0)));
+        assert_eq!(attendance_window(&event(at(4, 18), Some(at(5, 12)), true), Tz::UTC), (at(4, 0), at(6, 0)));
+        // All-day events cover the group's local days; 18:00 UTC is already the 5th in Tokyo
+        assert_eq!(attendance_window(&event(at(4, 18), None, true), chrono_tz::Asia::Tokyo), (at(4, 15), at(5, 15)));
+    }
+
+    #[test]
+    fn only_running_occurrences_are_in_progress() {
+        let mut weekly = event(at(1, 18), None, false);
+        weekly.recurrence = Some(crate::shared_calendar_api::RecurrenceRule {
+            frequency: crate::shared_calendar_api::RecurrenceFrequency::Weekly,
+            interval: 1,
+            until: None,
+            count: None,
+            exceptions: Vec::new(),
+        });
+        let overrides = HashMap::new();
+
+        let running = occurrences_in_progress(&weekly, &overrides, at(8, 18) + Duration::minutes(30), Tz::UTC);
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].occurrence_start, Some(at(8, 18)));
+        assert!(occurrences_in_progress(&weekly, &overrides, at(8, 20), Tz::UTC).is_empty());
+        assert!(occurrences_in_progress(&event(at(8, 18), None, false), &overrides, at(8, 17), Tz::UTC).is_empty());
+    }
+
+    #[test]
+    fn attendees_combine_rsvps_and_attendance() {
+        let rsvps = vec![
+            MemberRsvp { member_name: "zed".to_string(), response: RsvpResponse::Yes, note: None, responded_at: at(1, 0) },
+            MemberRsvp { member_name: "amy".to_string(), response: RsvpResponse::No, note: Some("work".to_string()), responded_at: at(1, 0) },
+            MemberRsvp { member_name: "bob".to_string(), response: RsvpResponse::Yes, note: None, responded_at: at(1, 0) },
+        ];
+        let attendance = vec![
+            MemberAttendance { member_name: "zed".to_string(), first_seen: at(4, 18), last_seen: at(4, 20), worlds: vec![302] },
+            MemberAttendance { member_name: "cat".to_string(), first_seen: at(4, 19), last_seen: at(4, 19), worlds: vec![] },
+        ];
+
+        let attendees = build_attendees(rsvps, attendance);
+        let names: Vec<&str> = attendees.iter().map(|attendee| attendee.member_name.as_str()).collect();
+        assert_eq!(names, vec!["bob", "zed", "amy", "cat"]);
+        assert!(attendees[1].attended);
+        assert_eq!(attendees[1].worlds, vec![302]);
+        assert!(!attendees[0].attended);
+        assert_eq!(attendees[3].response, None);
+
+        assert_eq!(Headcount::from_attendees(&attendees), Headcount { yes: 2, no: 1, maybe: 0, attended: 2 });
+    }
+}
+
+/// How recently a member's stats must have been updated for them to count as online,
+/// matching when the site starts showing a member as inactive
+const ONLINE_WINDOW_MINUTES: i64 = 5;
+
+/// How long an event without an end time is treated as lasting when recording attendance
+const DEFAULT_ATTENDANCE_HOURS: i64 = 1;
+
+const MAX_NOTE_LENGTH: usize = 500;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RsvpResponse {
+    Yes,
+    No,
+    Maybe,
+}
+
+impl RsvpResponse {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RsvpResponse::Yes => "yes",
+            RsvpResponse::No => "no",
+            RsvpResponse::Maybe => "maybe",
+        }
+    }
+
+    fn sort_order(response: Option<RsvpResponse>) -> u8 {
+        match response {
+            Some(RsvpResponse::Yes) => 0,
+            Some(RsvpResponse::Maybe) => 1,
+            Some(RsvpResponse::No) => 2,
+            None => 3,
+        }
+    }
+}
+
+impl FromStr for RsvpResponse {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "yes" => Ok(RsvpResponse::Yes),
+            "no" => Ok(RsvpResponse::No),
+            "maybe" => Ok(RsvpResponse::Maybe),
+            _ => Err(format!("Unknown RSVP response '{}'", value)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RsvpRequest {
+    pub member_name: String,
+    pub response: RsvpResponse,
+    pub note: Option<String>,
+    /// Which occurrence of a recurring event; left out for one-off events
+    pub occurrence_start: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct OccurrenceQuery {
+    pub occurrence_start: Option<DateTime<Utc>>,
+}
+
+pub struct MemberRsvp {
+    pub member_name: String,
+    pub response: RsvpResponse,
+    pub note: Option<String>,
+    pub responded_at: DateTime<Utc>,
+}
+
+pub struct MemberAttendance {
+    pub member_name: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub worlds: Vec<i32>,
+}
+
+/// A member who responded to an event or was seen online during it
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Attendee {
+    pub member_name: String,
+    pub response: Option<RsvpResponse>,
+    pub note: Option<String>,
+    pub responded_at: Option<DateTime<Utc>>,
+    /// Whether the member was seen online while the event was in progress
+    pub attended: bool,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub worlds: Vec<i32>,
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct Headcount {
+    pub yes: i64,
+    pub no: i64,
+    pub maybe: i64,
+    /// Members seen online during the event
+    pub attended: i64,
+}
+
+impl Headcount {
+    pub fn from_attendees(attendees: &[Attendee]) -> Self {
+        let mut headcount = Headcount::default();
+        for attendee in attendees {
+            match attendee.response {
+                Some(RsvpResponse::Yes) => headcount.yes += 1,
+                Some(RsvpResponse::No) => headcount.no += 1,
+                Some(RsvpResponse::Maybe) => headcount.maybe += 1,
+                None => (),
+            }
+            if attendee.attended {
+                headcount.attended += 1;
+            }
+        }
+        headcount
+    }
+}
+
+#[derive(Serialize)]
+pub struct EventAttendees {
+    pub event_id: i64,
+    pub occurrence_start: Option<DateTime<Utc>>,
+    pub headcount: Headcount,
+    pub attendees: Vec<Attendee>,
+}
+
+/// Merge RSVPs with recorded attendance into one list per member: yes, maybe, no,
+/// then members who came without responding, each by name
+pub fn build_attendees(rsvps: Vec<MemberRsvp>, attendance: Vec<MemberAttendance>) -> Vec<Attendee> {
+    let mut attendees: BTreeMap<String, Attendee> = BTreeMap::new();
+    for rsvp in rsvps {
+        attendees.insert(rsvp.member_name.clone(), Attendee {
+            member_name: rsvp.member_name,
+            response: Some(rsvp.response),
+            note: rsvp.note,
+            responded_at: Some(rsvp.responded_at),
+            attended: false,
+            first_seen: None,
+            last_seen: None,
+            worlds: Vec::new(),
+        });
+    }
+    for seen in attendance {
+        let attendee = attendees.entry(seen.member_name.clone()).or_insert_with(|| Attendee {
+            member_name: seen.member_name,
+            response: None,
+            note: None,
+            responded_at: None,
+            attended: false,
+            first_seen: None,
+            last_seen: None,
+            worlds: Vec::new(),
+        });
+        attendee.attended = true;
+        attendee.first_seen = Some(seen.first_seen);
+        attendee.last_seen = Some(seen.last_seen);
+        attendee.worlds = seen.worlds;
+    }
+
+    let mut attendees: Vec<Attendee> = attendees.into_values().collect();
+    attendees.sort_by_key(|attendee| RsvpResponse::sort_order(attendee.response));
+    attendees
+}
+
+/// When members count as attending an occurrence: all-day events cover their whole days
+/// in the group's time zone, and events without an end time are treated as lasting an hour
+pub fn attendance_window(occurrence: &CalendarEvent, tz: Tz) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = occurrence.start_time;
+    let end = occurrence.end_time.filter(|end| *end > start);
+    if occurrence.all_day {
+        let first_day = local_date(tz, start);
+        let last_day = end.map_or(first_day, |end| local_date(tz, end));
+        (start_of_local_day(tz, first_day), start_of_local_day(tz, last_day + Duration::days(1)))
+    } else {
+        (start, end.unwrap_or(start + Duration::hours(DEFAULT_ATTENDANCE_HOURS)))
+    }
+}
+
+/// Occurrences of the event whose attendance window contains `now`
+pub fn occurrences_in_progress(
+    event: &CalendarEvent,
+    overrides: &HashMap<(i64, DateTime<Utc>), crate::shared_calendar_api::OccurrenceOverride>,
+    now: DateTime<Utc>,
+    tz: Tz,
+) -> Vec<EventOccurrence> {
+    // Look back far enough to catch all-day occurrences and ones without an end time
+    expand_event(event, overrides, now - Duration::days(2), now + Duration::seconds(1), tz)
+        .into_iter()
+        .filter(|occurrence| {
+            let (start, end) = attendance_window(&occurrence.event, tz);
+            start <= now && now < end
+        })
+        .collect()
+}
+
+/// RSVP and attendance counts for each occurrence of the given events, keyed by event
+/// and occurrence start (None for one-off events)
+pub async fn load_headcounts(
+    client: &Client,
+    event_ids: &[i64],
+) -> Result<HashMap<(i64, Option<DateTime<Utc>>), Headcount>, tokio_postgres::Error> {
+    let mut headcounts: HashMap<(i64, Option<DateTime<Utc>>), Headcount> = HashMap::new();
+
+    let rows = client.query(
+        "SELECT event_id, occurrence_start, response, COUNT(*) FROM calendar_event_rsvps
+         WHERE event_id = ANY($1) GROUP BY event_id, occurrence_start, response",
+        &[&event_ids]
+    ).await?;
+    for row in &rows {
+        let headcount = headcounts.entry((row.get(0), row.get(1))).or_default();
+        let count: i64 = row.get(3);
+        match RsvpResponse::from_str(row.get(2)) {
+            Ok(RsvpResponse::Yes) => headcount.yes = count,
+            Ok(RsvpResponse::No) => headcount.no = count,
+            Ok(RsvpResponse::Maybe) => headcount.maybe = count,
+            Err(_) => (),
+        }
+    }
+
+    let rows = client.query(
+        "SELECT event_id, occurrence_start, COUNT(*) FROM calendar_event_attendance
+         WHERE event_id = ANY($1) GROUP BY event_id, occurrence_start",
+        &[&event_ids]
+    ).await?;
+    for row in &rows {
+        headcounts.entry((row.get(0), row.get(1))).or_default().attended = row.get(2);
+    }
+
+    Ok(headcounts)
+}
+
+// Check the event exists and `occurrence_start` names one of its occurrences.
+// Recurring events need an occurrence; one-off events must not have one.
+async fn check_occurrence(
+    client: &Client,
+    group_id: i64,
+    event_id: i64,
+    occurrence_start: Option<DateTime<Utc>>,
+) -> Result<Option<HttpResponse>, tokio_postgres::Error> {
+    let row = client.query_opt(
+        &format!("SELECT {} FROM calendar_events WHERE id = $1 AND group_id = $2", EVENT_COLUMNS),
+        &[&event_id, &group_id]
+    ).await?;
+    let event = match row {
+        Some(row) => event_from_row(&row),
+        None => return Ok(Some(HttpResponse::NotFound().finish())),
+    };
+
+    match (&event.recurrence, occurrence_start) {
+        (None, None) => Ok(None),
+        (None, Some(_)) => Ok(Some(HttpResponse::BadRequest().body("occurrence_start is only used for recurring events"))),
+        (Some(_), None) => Ok(Some(HttpResponse::BadRequest().body("occurrence_start is required for recurring events"))),
+        (Some(rule), Some(occurrence_start)) => {
+            let tz = group_time_zone(client, group_id).await?;
+            if rule.has_occurrence(event.start_time, occurrence_start, tz) {
+                Ok(None)
+            } else {
+                Ok(Some(HttpResponse::NotFound().body("The event has no occurrence at that time")))
+            }
+        }
+    }
+}
+
+// RSVP to an event, replacing the member's earlier response
+pub async fn set_rsvp(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+    rsvp: web::Json<RsvpRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let (_group_name, event_id) = path.into_inner();
+
+    if rsvp.note.as_ref().is_some_and(|note| note.chars().count() > MAX_NOTE_LENGTH) {
+        return Ok(HttpResponse::BadRequest().body(format!("Notes can be at most {} characters", MAX_NOTE_LENGTH)));
+    }
+    if let Some(response) = check_occurrence(&client, auth.group_id, event_id, rsvp.occurrence_start).await? {
+        return Ok(response);
+    }
+
+    let member = client.query_opt(
+        "SELECT member_id FROM groupironman.members WHERE group_id = $1 AND member_name = $2 AND member_name <> $3",
+        &[&auth.group_id, &rsvp.member_name, &SHARED_MEMBER]
+    ).await?;
+    let member_id: i64 = match member {
+        Some(row) => row.get(0),
+        None => return Ok(HttpResponse::BadRequest().body("Member is not in the group")),
+    };
+
+    let note = rsvp.note.as_deref().map(str::trim).filter(|note| !note.is_empty());
+    client.execute(
+        "INSERT INTO calendar_event_rsvps (event_id, occurrence_start, member_id, response, note)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (event_id, member_id, (COALESCE(occurrence_start, '-infinity'::TIMESTAMPTZ)))
+         DO UPDATE SET response = EXCLUDED.response, note = EXCLUDED.note, responded_at = NOW()",
+        &[&event_id, &rsvp.occurrence_start, &member_id, &rsvp.response.as_str(), &note]
+    ).await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+// Withdraw a member's RSVP
+pub async fn delete_rsvp(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64, String)>,
+    query: web::Query<OccurrenceQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let (_group_name, event_id, member_name) = path.into_inner();
+
+    client.execute(
+        "DELETE FROM calendar_event_rsvps r
+         USING calendar_events e, groupironman.members m
+         WHERE r.event_id = e.id AND r.member_id = m.member_id
+         AND e.id = $1 AND e.group_id = $2 AND m.member_name = $3
+         AND r.occurrence_start IS NOT DISTINCT FROM $4",
+        &[&event_id, &auth.group_id, &member_name, &query.occurrence_start]
+    ).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Get who responded to an event or occurrence and who was seen online during it
+pub async fn get_attendees(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+    query: web::Query<OccurrenceQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let (_group_name, event_id) = path.into_inner();
+
+    if let Some(response) = check_occurrence(&client, auth.group_id, event_id, query.occurrence_start).await? {
+        return Ok(response);
+    }
+
+    let rsvp_rows = client.query(
+        "SELECT m.member_name, r.response, r.note, r.responded_at
+         FROM calendar_event_rsvps r
+         JOIN groupironman.members m ON m.member_id = r.member_id
+         WHERE r.event_id = $1 AND r.occurrence_start IS NOT DISTINCT FROM $2",
+        &[&event_id, &query.occurrence_start]
+    ).await?;
+    let rsvps: Vec<MemberRsvp> = rsvp_rows.iter().filter_map(|row| {
+        Some(MemberRsvp {
+            member_name: row.get(0),
+            response: RsvpResponse::from_str(row.get(1)).ok()?,
+            note: row.get(2),
+            responded_at: row.get(3),
+        })
+    }).collect();
+
+    let attendance_rows = client.query(
+        "SELECT m.member_name, a.first_seen, a.last_seen, a.worlds
+         FROM calendar_event_attendance a
+         JOIN groupironman.members m ON m.member_id = a.member_id
+         WHERE a.event_id = $1 AND a.occurrence_start IS NOT DISTINCT FROM $2",
+        &[&event_id, &query.occurrence_start]
+    ).await?;
+    let attendance: Vec<MemberAttendance> = attendance_rows.iter().map(|row| MemberAttendance {
+        member_name: row.get(0),
+        first_seen: row.get(1),
+        last_seen: row.get(2),
+        worlds: row.get(3),
+    }).collect();
+
+    let attendees = build_attendees(rsvps, attendance);
+    Ok(HttpResponse::Ok().json(EventAttendees {
+        event_id,
+        occurrence_start: query.occurrence_start,
+        headcount: Headcount::from_attendees(&attendees),
+        attendees,
+    }))
+}
+
+// Record which members are online for every event occurrence in progress.
+// A member is online if the plugin sent their stats recently; their world comes from the stats.
+async fn record_attendance(client: &Client) -> Result<(), tokio_postgres::Error> {
+    let now = Utc::now();
+    let rows = client.query(
+        &format!(
+            "SELECT {} FROM calendar_events
+             WHERE start_time <= $1 AND (
+                COALESCE(end_time, start_time) >= $2
+                OR (recurrence_frequency IS NOT NULL AND (recurrence_until IS NULL OR recurrence_until >= $2))
+             )",
+            EVENT_COLUMNS
+        ),
+        &[&now, &(now - Duration::days(2))]
+    ).await?;
+    let events: Vec<CalendarEvent> = rows.iter().map(event_from_row).collect();
+
+    let recurring_ids: Vec<i64> = events.iter().filter(|event| event.recurrence.is_some()).filter_map(|event| event.id).collect();
+    let overrides = load_overrides(client, &recurring_ids).await?;
+
+    let online_since = now - Duration::minutes(ONLINE_WINDOW_MINUTES);
+    let mut time_zones: HashMap<i64, Tz> = HashMap::new();
+    for event in &events {
+        let tz = match time_zones.get(&event.group_id) {
+            Some(tz) => *tz,
+            None => {
+                let tz = group_time_zone(client, event.group_id).await?;
+                time_zones.insert(event.group_id, tz);
+                tz
+            }
+        };
+        for occurrence in occurrences_in_progress(event, &overrides, now, tz) {
+            let (started, _) = attendance_window(&occurrence.event, tz);
+            client.execute(
+                "INSERT INTO calendar_event_attendance (event_id, occurrence_start, member_id, first_seen, last_seen, worlds)
+                 SELECT $1, $2, m.member_id, m.stats_last_update, m.stats_last_update, ARRAY_REMOVE(ARRAY[NULLIF(m.stats[7], 0)], NULL)
+                 FROM groupironman.members m
+                 WHERE m.group_id = $3 AND m.member_name <> $6 AND m.stats_last_update >= GREATEST($4, $5)
+                 ON CONFLICT (event_id, member_id, (COALESCE(occurrence_start, '-infinity'::TIMESTAMPTZ))) DO UPDATE SET
+                    last_seen = GREATEST(calendar_event_attendance.last_seen, EXCLUDED.last_seen),
+                    worlds = CASE WHEN EXCLUDED.worlds <@ calendar_event_attendance.worlds
+                        THEN calendar_event_attendance.worlds
+                        ELSE calendar_event_attendance.worlds || EXCLUDED.worlds END",
+                &[&event.id, &occurrence.occurrence_start, &event.group_id, &started, &online_since, &SHARED_MEMBER]
+            ).await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn start_attendance_tracker(db_pool: Pool) {
+    task::spawn(async move {
+        let mut interval = time::interval(std::time::Duration::from_secs(60));
+
+        loop {
+            interval.tick().await;
+
+            match db_pool.get().await {
+                Ok(client) => {
+                    if let Err(err) = record_attendance(&client).await {
+                        log::error!("Failed to record event attendance: {}", err);
+                    }
+                }
+                Err(err) => {
+                    log::error!("Failed to get db client: {}", err);
+                }
+            }
+        }
+    });
+}
@@ -2,10 +2,11 @@ use serde::{Deserialize, Serialize};
 use lazy_static::lazy_static;
 use std::collections::{HashMap, HashSet};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct CollectionLog {
     #[serde(skip_serializing)]
+    #[allow(dead_code)]
     pub tab: i16,
     pub page_name: String,
     pub completion_counts: Vec<i32>,
@@ -36,6 +37,7 @@ pub struct CollectionLogItemInfo {
 #[derive(Deserialize)]
 pub struct CollectionLogPageInfo {
     pub name: String,
+    #[allow(dead_code)]
     pub completion_labels: Vec<String>,
     pub items: Vec<CollectionLogItemInfo>
 }
@@ -67,14 +69,12 @@ impl CollectionLogInfo {
                 for item in page.items.iter() {
                     item_name_to_id_lookup.insert(item.name.clone(), item.id);
 
-                    match page_id_item_set_lookup.get_mut(&page_id) {
+                    match page_id_item_set_lookup.get_mut(page_id) {
                         Some (x) => x.insert(item.id),
                         None => true
                     };
 
-                    if !item_id_to_page_id_lookup.contains_key(&item.id) {
-                        item_id_to_page_id_lookup.insert(item.id, HashSet::new());
-                    }
+                    item_id_to_page_id_lookup.entry(item.id).or_insert_with(HashSet::new);
 
                     match item_id_to_page_id_lookup.get_mut(&item.id) {
                         Some (x) => x.insert(*page_id),
@@ -165,7 +165,7 @@ lazy_static! {
 
     pub static ref COLLECTION_LOG_DATA: String = {
         let path = concat!(env!("CARGO_MANIFEST_DIR"), "/collection_log_info.json");
-        std::fs::read_to_string(path).expect(&format!("Could not read collection log info file at {}", path))
+        std::fs::read_to_string(path).unwrap_or_else(|_| panic!("Could not read collection log info file at {}", path))
     };
 
     pub static ref COLLECTION_LOG_INFO: Vec<CollectionLogTabInfo> = {
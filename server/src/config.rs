@@ -28,12 +28,31 @@ pub struct CaptchaConfig {
     pub secret: String,
 }
 #[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum BlobStoreKind {
+    Local,
+}
+#[derive(Deserialize, Clone)]
+pub struct AttachmentConfig {
+    #[serde(default = "default_blob_store")]
+    pub store: BlobStoreKind,
+    /// Directory the local blob store writes files to
+    #[serde(default = "default_attachment_path")]
+    pub local_path: String,
+    #[serde(default = "default_attachment_max_bytes")]
+    pub max_bytes: usize,
+    #[serde(default = "default_attachment_content_types")]
+    pub content_types: Vec<String>,
+}
+#[derive(Deserialize, Clone)]
 pub struct Config {
     pub pg: deadpool_postgres::Config,
     #[serde(default = "default_logger_config")]
     pub logger: LoggerConfig,
     #[serde(default = "default_captcha_config")]
     pub hcaptcha: CaptchaConfig,
+    #[serde(default = "default_attachment_config")]
+    pub attachments: AttachmentConfig,
 }
 fn default_logger_config() -> LoggerConfig {
     LoggerConfig {
@@ -47,6 +66,31 @@ fn default_captcha_config() -> CaptchaConfig {
         secret: "".to_string(),
     }
 }
+fn default_blob_store() -> BlobStoreKind {
+    BlobStoreKind::Local
+}
+fn default_attachment_path() -> String {
+    "attachments".to_string()
+}
+fn default_attachment_max_bytes() -> usize {
+    5_000_000
+}
+fn default_attachment_content_types() -> Vec<String> {
+    vec![
+        "image/png".to_string(),
+        "image/jpeg".to_string(),
+        "image/gif".to_string(),
+        "image/webp".to_string(),
+    ]
+}
+fn default_attachment_config() -> AttachmentConfig {
+    AttachmentConfig {
+        store: default_blob_store(),
+        local_path: default_attachment_path(),
+        max_bytes: default_attachment_max_bytes(),
+        content_types: default_attachment_content_types(),
+    }
+}
 impl Config {
     pub fn from_env() -> Result<Self, ConfigError> {
         let cfg = ::config::Config::builder()
@@ -7,7 +7,7 @@ lazy_static! {
     static ref SECRET: String = {
         let path = concat!(env!("CARGO_MANIFEST_DIR"), "/secret");
         fs::read_to_string(path)
-            .expect(&format!("Could not find secret file at {}", path))
+            .unwrap_or_else(|_| panic!("Could not find secret file at {}", path))
     };
 }
 
@@ -18,7 +18,7 @@ pub fn hash(value: &str, salt: &str, iterations: u32) -> std::vec::Vec<u8> {
         hasher.update(v);
     }
     hasher.update(salt);
-    hasher.update(&SECRET.as_str());
+    hasher.update(SECRET.as_str());
     hasher.finalize().to_vec()
 }
 
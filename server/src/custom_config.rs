@@ -1,20 +1,10 @@
 use std::env;
 
-/// Configuration for custom features of the Group Ironmen site
+/// Configuration for custom features of the Group Ironmen site.
+/// Point values live in each group's scoring rules (see `scoring_rules`).
+#[derive(Clone)]
+#[allow(dead_code)]
 pub struct CustomConfig {
-    /// Number of points awarded for boss kills based on difficulty
-    pub boss_points_easy: i32,
-    pub boss_points_medium: i32,
-    pub boss_points_hard: i32,
-    
-    /// Number of points awarded for skill milestones
-    pub skill_level_92_points: i32,
-    pub skill_level_99_points: i32,
-    
-    /// Number of points awarded for collection log completions
-    pub collection_points_per_item: i32,
-    pub collection_set_completion_bonus: i32,
-    
     /// Number of days to keep player activity history
     pub activity_history_days: i32,
     
@@ -30,16 +20,6 @@ pub struct CustomConfig {
 impl Default for CustomConfig {
     fn default() -> Self {
         Self {
-            boss_points_easy: 10,
-            boss_points_medium: 25,
-            boss_points_hard: 50,
-            
-            skill_level_92_points: 25,
-            skill_level_99_points: 50,
-            
-            collection_points_per_item: 1,
-            collection_set_completion_bonus: 25,
-            
             activity_history_days: 30,
             
             enable_group_challenges: true,
@@ -55,16 +35,6 @@ impl CustomConfig {
     /// Load configuration from environment variables, falling back to defaults
     pub fn from_env() -> Self {
         Self {
-            boss_points_easy: get_env_int("CUSTOM_BOSS_POINTS_EASY", 10),
-            boss_points_medium: get_env_int("CUSTOM_BOSS_POINTS_MEDIUM", 25),
-            boss_points_hard: get_env_int("CUSTOM_BOSS_POINTS_HARD", 50),
-            
-            skill_level_92_points: get_env_int("CUSTOM_SKILL_LEVEL_92_POINTS", 25),
-            skill_level_99_points: get_env_int("CUSTOM_SKILL_LEVEL_99_POINTS", 50),
-            
-            collection_points_per_item: get_env_int("CUSTOM_COLLECTION_POINTS_PER_ITEM", 1),
-            collection_set_completion_bonus: get_env_int("CUSTOM_COLLECTION_SET_COMPLETION_BONUS", 25),
-            
             activity_history_days: get_env_int("CUSTOM_ACTIVITY_HISTORY_DAYS", 30),
             
             enable_group_challenges: get_env_bool("CUSTOM_ENABLE_GROUP_CHALLENGES", true),
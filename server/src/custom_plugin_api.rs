@@ -1,15 +1,34 @@
 use crate::auth_middleware::AuthedGroupId;
 use crate::db::{get_member_id};
+use crate::drop_duplicates::{find_likely_duplicate, merge_drops, DropEntrySource};
 use crate::error::ApiError;
-use actix_web::{web, HttpResponse};
-use deadpool_postgres::Pool;
+use crate::item_values::value_item;
+use actix_web::{web, HttpRequest, HttpResponse};
+use deadpool_postgres::{Pool, Transaction};
 use serde::{Deserialize, Serialize};
-use crate::custom_config::CustomConfig;
+use crate::custom_points::{get_member_total_points, record_point_event, record_point_event_once};
+use crate::scoring_rules::{get_group_rules, SKILL_NAMES};
+
+#[cfg(test)]
+mod plugin_tests {
+    use super::*;
+
+    #[test]
+    fn names_are_escaped_for_regex_matching() {
+        assert_eq!(escape_regex("Kree'arra"), "Kree'arra");
+        assert_eq!(escape_regex("Barrows (Chests)"), r"Barrows \(Chests\)");
+        assert_eq!(escape_regex("a.b*c+d?"), r"a\.b\*c\+d\?");
+        assert_eq!(escape_regex(r"\d"), r"\\d");
+    }
+}
 
 /// Custom plugin data submission request
 #[derive(Deserialize)]
 pub struct CustomPluginData {
     member_name: String,
+    // Unique per submission; a retry with the same key is not processed again.
+    // Can also be sent in the Idempotency-Key header.
+    idempotency_key: Option<String>,
     // Custom data types from the plugin
     boss_kills: Option<Vec<BossKill>>,
     skill_milestones: Option<Vec<SkillMilestone>>,
@@ -20,6 +39,7 @@ pub struct CustomPluginData {
 
 /// Boss kill data
 #[derive(Deserialize)]
+#[allow(dead_code)]
 pub struct BossKill {
     boss_name: String,
     kill_count: i32,
@@ -31,9 +51,12 @@ pub struct BossKill {
 
 /// Skill milestone data
 #[derive(Deserialize)]
+#[allow(dead_code)]
 pub struct SkillMilestone {
     skill_id: i32,
     level: i32,
+    // Level before the milestone, when it was a jump of several levels
+    previous_level: Option<i32>,
     xp: i64,
     timestamp: chrono::DateTime<chrono::Utc>,
 }
@@ -61,7 +84,8 @@ pub struct ValuableDrop {
     item_id: i32,
     item_name: String,
     item_quantity: i32,
-    item_value: i64,
+    /// Value per item as the client saw it; the server values drops itself
+    item_value: Option<i64>,
     source_name: String,
     x_coord: Option<i32>,
     y_coord: Option<i32>,
@@ -70,11 +94,14 @@ pub struct ValuableDrop {
 }
 
 /// Response for plugin data submission
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct PluginUpdateResponse {
     status: String,
     points_awarded: i32,
     activities_completed: Vec<String>,
+    /// Completed activities that need proof, awarded once a reviewer approves them
+    #[serde(default)]
+    activities_pending_review: Vec<String>,
     new_total_points: Option<i32>,
 }
 
@@ -85,137 +112,331 @@ pub struct PluginUpdateResponse {
 /// - Mark activities as completed
 /// - Track collection log progress
 /// - Record valuable drops
+///
+/// Level, collection item and collection page awards are only made once per member,
+/// so resubmitting them is harmless.
+///
+/// Point values come from the group's scoring rules.
+///
+/// Submissions with an idempotency key are processed once; retries get the
+/// original response back and award nothing. Without a key, boss kills and
+/// drops that were already recorded are skipped.
 pub async fn process_custom_plugin_data(
     pool: web::Data<Pool>,
-    config: web::Data<CustomConfig>,
     auth: AuthedGroupId,
+    req: HttpRequest,
     data: web::Json<CustomPluginData>,
 ) -> Result<HttpResponse, ApiError> {
+    let idempotency_key = match data.idempotency_key.clone().or_else(|| {
+        req.headers()
+            .get("Idempotency-Key")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    }) {
+        Some(key) if key.is_empty() || key.len() > 100 => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": "Idempotency key must be between 1 and 100 characters"
+            })));
+        }
+        key => key,
+    };
+    
     let mut client = pool.get().await?;
     let transaction = client.transaction().await?;
     
     // Get member ID
-    let member_id = get_member_id(&transaction, auth.0, &data.member_name).await?;
+    let member_id = get_member_id(&transaction, auth.group_id, &data.member_name).await?;
+    
+    // Process one submission per member at a time, so a retry sent without an
+    // idempotency key sees the kills and drops the first attempt recorded
+    let lock_stmt = transaction
+        .prepare_cached("SELECT member_id FROM groupironman.members WHERE member_id = $1 FOR UPDATE")
+        .await?;
+    transaction.execute(&lock_stmt, &[&member_id]).await?;
+    
+    // Claim the idempotency key. A concurrent submission with the same key waits
+    // here until the first one commits or rolls back.
+    if let Some(key) = &idempotency_key {
+        let claim_stmt = transaction
+            .prepare_cached(
+                "INSERT INTO groupironman.plugin_submissions (group_id, idempotency_key, member_id) 
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (group_id, idempotency_key) DO NOTHING"
+            )
+            .await?;
+        
+        let claimed = transaction
+            .execute(&claim_stmt, &[&auth.group_id, key, &member_id])
+            .await?;
+        
+        if claimed == 0 {
+            let existing_stmt = transaction
+                .prepare_cached(
+                    "SELECT member_id, response FROM groupironman.plugin_submissions 
+                     WHERE group_id = $1 AND idempotency_key = $2"
+                )
+                .await?;
+            
+            let row = transaction
+                .query_one(&existing_stmt, &[&auth.group_id, key])
+                .await?;
+            let original_member_id: i64 = row.try_get(0)?;
+            let response: Option<serde_json::Value> = row.try_get(1)?;
+            
+            if original_member_id != member_id {
+                return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                    "status": "error",
+                    "message": "Idempotency key was already used for another member"
+                })));
+            }
+            
+            let response: PluginUpdateResponse = match response {
+                Some(response) => serde_json::from_value(response)?,
+                None => {
+                    return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                        "status": "error",
+                        "message": "Submission is still being processed"
+                    })));
+                }
+            };
+            
+            return Ok(HttpResponse::Ok()
+                .insert_header(("Idempotent-Replayed", "true"))
+                .json(response));
+        }
+    }
+    
+    // Get the scoring rules for this group
+    let rules = get_group_rules(&transaction, auth.group_id).await?;
     
     // Track total points awarded in this update
     let mut total_points_awarded = 0;
     let mut completed_activities = Vec::new();
+    let mut pending_activities = Vec::new();
     
     // Process boss kills
     if let Some(boss_kills) = &data.boss_kills {
         for kill in boss_kills {
-            // Determine points based on boss difficulty
-            let points = match kill.difficulty.as_deref() {
-                Some("easy") | None => config.boss_points_easy,
-                Some("medium") => config.boss_points_medium,
-                Some("hard") => config.boss_points_hard,
-                _ => config.boss_points_easy, // Default to easy if unknown difficulty
-            };
+            // A kill is identified by the boss and kill count; skip kills a retry resends
+            let kill_id = format!("{}:{}", kill.boss_name, kill.kill_count);
+            if point_event_recorded(&transaction, member_id, "boss_kill", &kill_id).await? {
+                continue;
+            }
             
-            // Check if there's an activity for this boss
-            let find_boss_activity_stmt = transaction
-                .prepare_cached(
-                    "SELECT activity_id, activity_name, point_value 
-                     FROM groupironman.custom_activities 
-                     WHERE activity_name ILIKE $1 
-                     AND category = 'Bossing'"
+            // Award points for the kill itself
+            let points = rules.boss_kill_points(&kill.boss_name, kill.difficulty.as_deref());
+            if points > 0 {
+                record_point_event(
+                    &transaction,
+                    member_id,
+                    "bossing",
+                    points,
+                    &format!("{} kill ({} kc)", kill.boss_name, kill.kill_count),
+                    "boss_kill",
+                    Some(&kill_id),
                 )
                 .await?;
+                
+                total_points_awarded += points;
+            }
             
-            let boss_pattern = format!("%{}%", kill.boss_name);
-            let boss_activity_rows = transaction
-                .query(&find_boss_activity_stmt, &[&boss_pattern])
-                .await?;
+            // Complete any activities for this boss
+            let (completed, pending) = complete_matching_activities(
+                &transaction,
+                auth.group_id,
+                member_id,
+                Some("Bossing"),
+                &[escape_regex(&kill.boss_name)],
+                "bossing",
+                &format!("({} kc)", kill.kill_count),
+                kill.timestamp,
+                None,
+            )
+            .await?;
             
-            for row in boss_activity_rows {
-                let activity_id: i32 = row.try_get(0)?;
-                let activity_name: String = row.try_get(1)?;
-                let point_value: i32 = row.try_get(2)?;
-                
-                // Check if player has already completed this activity
-                let check_completed_stmt = transaction
-                    .prepare_cached(
-                        "SELECT player_activity_id FROM groupironman.player_activities 
-                         WHERE member_id = $1 AND activity_id = $2"
-                    )
-                    .await?;
-                
-                let completed = transaction
-                    .query_opt(&check_completed_stmt, &[&member_id, &activity_id])
-                    .await?;
-                
-                if completed.is_none() {
-                    // Mark activity as completed
-                    let mark_completed_stmt = transaction
-                        .prepare_cached(
-                            "INSERT INTO groupironman.player_activities 
-                             (member_id, activity_id, completion_date, verified) 
-                             VALUES ($1, $2, $3, true)"
-                        )
-                        .await?;
-                    
-                    transaction
-                        .execute(
-                            &mark_completed_stmt, 
-                            &[&member_id, &activity_id, &kill.timestamp]
-                        )
-                        .await?;
-                    
-                    // Award points
-                    let add_points_stmt = transaction
-                        .prepare_cached(
-                            "INSERT INTO groupironman.player_points (member_id, point_type, points) 
-                             VALUES ($1, $2, $3) 
-                             ON CONFLICT (member_id, point_type) 
-                             DO UPDATE SET points = groupironman.player_points.points + $3"
-                        )
-                        .await?;
-                    
-                    transaction
-                        .execute(
-                            &add_points_stmt, 
-                            &[&member_id, &"bossing".to_string(), &point_value]
-                        )
-                        .await?;
-                    
-                    total_points_awarded += point_value;
-                    completed_activities.push(activity_name);
-                }
+            for (activity_name, point_value) in completed {
+                total_points_awarded += point_value;
+                completed_activities.push(activity_name);
             }
+            pending_activities.extend(pending);
         }
     }
     
     // Process skill milestones
     if let Some(skill_milestones) = &data.skill_milestones {
         for milestone in skill_milestones {
-            // Get point value from config based on milestone level
-            let (milestone_type, points) = match milestone.level {
-                99 => ("Level 99", config.skill_level_99_points),
-                92 => ("Level 92", config.skill_level_92_points),
-                _ => continue, // Skip milestones that don't match predefined activities
+            let skill_name = match usize::try_from(milestone.skill_id).ok().and_then(|id| SKILL_NAMES.get(id)) {
+                Some(skill_name) => *skill_name,
+                None => continue, // Skip skills we don't know about
             };
             
-            // Add skill points and mark activities as completed
-            // Similar implementation to boss kills above
-            // Code omitted for brevity
+            // Award points from the scoring rules for every level passed, once per skill and level
+            let previous_level = milestone.previous_level.unwrap_or(milestone.level - 1);
+            for threshold in rules.skill_level_points(milestone.skill_id, previous_level, milestone.level) {
+                let awarded = record_point_event_once(
+                    &transaction,
+                    member_id,
+                    "skilling",
+                    threshold.points,
+                    &format!("Level {} {}", threshold.level, skill_name),
+                    "skill_level",
+                    &format!("{}:{}", skill_name, threshold.level),
+                )
+                .await?;
+                
+                if awarded.is_some() {
+                    total_points_awarded += threshold.points;
+                }
+            }
+            
+            // Complete level activities, e.g. "Reach 99 in a skill" or "99 Slayer".
+            // Levels must be whole words so 9 doesn't match "Reach 99 in a skill".
+            let level = milestone.level.to_string();
+            let skill = escape_regex(skill_name);
+            let (completed, pending) = complete_matching_activities(
+                &transaction,
+                auth.group_id,
+                member_id,
+                Some("Skilling"),
+                &[
+                    format!(r"\m{} in a skill\M", level),
+                    format!(r"\m{} {}\M", level, skill),
+                    format!(r"\m{} {}\M", skill, level),
+                ],
+                "skilling",
+                &format!("({})", skill_name),
+                milestone.timestamp,
+                None,
+            )
+            .await?;
+            
+            for (activity_name, point_value) in completed {
+                total_points_awarded += point_value;
+                completed_activities.push(activity_name);
+            }
+            pending_activities.extend(pending);
+        }
+    }
+    
+    // Process achievements. These are scored through the activities they complete.
+    if let Some(achievements) = &data.completed_achievements {
+        for achievement in achievements {
+            let (completed, pending) = complete_matching_activities(
+                &transaction,
+                auth.group_id,
+                member_id,
+                None,
+                &[format!("^{}$", escape_regex(&achievement.achievement_name))],
+                "activity",
+                "",
+                achievement.timestamp,
+                achievement.proof_data.as_ref(),
+            )
+            .await?;
+            
+            for (activity_name, point_value) in completed {
+                total_points_awarded += point_value;
+                completed_activities.push(activity_name);
+            }
+            pending_activities.extend(pending);
+        }
+    }
+    
+    // Process collection log completions
+    if let Some(collection_completions) = &data.collection_completions {
+        let item_points = rules.collection_item_points();
+        
+        for completion in collection_completions {
+            // Points for each newly collected item, once per item
+            if item_points > 0 {
+                for item_id in &completion.items_collected {
+                    let awarded = record_point_event_once(
+                        &transaction,
+                        member_id,
+                        "collection",
+                        item_points,
+                        &format!("Collected item {} ({})", item_id, completion.collection_name),
+                        "collection_item",
+                        &item_id.to_string(),
+                    )
+                    .await?;
+                    
+                    if awarded.is_some() {
+                        total_points_awarded += item_points;
+                    }
+                }
+            }
+            
+            if !completion.is_set_completion {
+                continue;
+            }
+            
+            // Bonus for completing the page, once per page
+            let bonus = rules.collection_page_bonus(&completion.collection_name);
+            if bonus > 0 {
+                let awarded = record_point_event_once(
+                    &transaction,
+                    member_id,
+                    "collection",
+                    bonus,
+                    &format!("Completed {}", completion.collection_name),
+                    "collection_set",
+                    &completion.collection_name,
+                )
+                .await?;
+                
+                if awarded.is_some() {
+                    total_points_awarded += bonus;
+                }
+            }
+            
+            // Complete the page's activities and the generic page activity
+            let (completed, pending) = complete_matching_activities(
+                &transaction,
+                auth.group_id,
+                member_id,
+                Some("Collection"),
+                &[
+                    escape_regex(&completion.collection_name),
+                    "^Complete Collection Log$".to_string(),
+                ],
+                "collection",
+                &format!("({})", completion.collection_name),
+                completion.timestamp,
+                None,
+            )
+            .await?;
+            
+            for (activity_name, point_value) in completed {
+                total_points_awarded += point_value;
+                completed_activities.push(activity_name);
+            }
+            pending_activities.extend(pending);
         }
     }
     
     // Process valuable drops
     if let Some(valuable_drops) = &data.valuable_drops {
         for drop in valuable_drops {
-            // Insert the valuable drop into the database
+            // Skip drops a retry resends
+            if plugin_drop_recorded(&transaction, member_id, drop).await? {
+                continue;
+            }
+            
+            // Insert the valuable drop into the database, valued from GE prices
+            let (item_value, value_source) = value_item(drop.item_id, drop.item_value);
             let insert_drop_stmt = transaction
                 .prepare_cached(
                     "INSERT INTO groupironman.valuable_drops 
                      (member_id, item_id, item_name, item_quantity, item_value, source_name, 
-                      x_coord, y_coord, z_coord, timestamp) 
-                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                      x_coord, y_coord, z_coord, timestamp, reported_value, value_source, valued_at, entry_source) 
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, NOW(), 'plugin')
                      RETURNING drop_id"
                 )
                 .await?;
             
-            let _drop_id = transaction
+            let drop_id = transaction
                 .query_one(
                     &insert_drop_stmt, 
                     &[
@@ -223,16 +444,50 @@ pub async fn process_custom_plugin_data(
                         &drop.item_id,
                         &drop.item_name,
                         &drop.item_quantity,
-                        &drop.item_value,
+                        &item_value,
                         &drop.source_name,
                         &drop.x_coord,
                         &drop.y_coord,
                         &drop.z_coord,
-                        &drop.timestamp
+                        &drop.timestamp,
+                        &drop.item_value,
+                        &value_source.as_str(),
                     ]
                 )
                 .await?
                 .try_get::<_, i64>(0)?;
+            
+            // Fold in the same drop if someone already entered it by hand
+            let manual_drop_id = find_likely_duplicate(
+                &transaction,
+                member_id,
+                drop.item_id,
+                drop.item_quantity,
+                &drop.source_name,
+                drop.timestamp,
+                DropEntrySource::Plugin,
+            )
+            .await?;
+            if let Some(manual_drop_id) = manual_drop_id {
+                merge_drops(&transaction, drop_id, manual_drop_id).await?;
+            }
+            
+            // Award points if the drop reaches one of the value tiers
+            let drop_value = item_value * drop.item_quantity as i64;
+            if let Some(points) = rules.drop_points(drop_value) {
+                record_point_event(
+                    &transaction,
+                    member_id,
+                    "drops",
+                    points,
+                    &format!("{} x{} from {}", drop.item_name, drop.item_quantity, drop.source_name),
+                    "valuable_drop",
+                    Some(&drop_id.to_string()),
+                )
+                .await?;
+                
+                total_points_awarded += points;
+            }
                 
             // Check if this drop contributes to any group challenges
             // Logic omitted for brevity
@@ -240,25 +495,184 @@ pub async fn process_custom_plugin_data(
     }
     
     // Get new total points
-    let get_total_points_stmt = transaction
+    let new_total_points = get_member_total_points(&transaction, member_id).await?;
+    
+    let response = PluginUpdateResponse {
+        status: "success".to_string(),
+        points_awarded: total_points_awarded,
+        activities_completed: completed_activities,
+        activities_pending_review: pending_activities,
+        new_total_points: Some(new_total_points as i32),
+    };
+    
+    // Store the response so retries of this submission get it back
+    if let Some(key) = &idempotency_key {
+        let store_stmt = transaction
+            .prepare_cached(
+                "UPDATE groupironman.plugin_submissions SET response = $3 
+                 WHERE group_id = $1 AND idempotency_key = $2"
+            )
+            .await?;
+        
+        let response_json = serde_json::to_value(&response)?;
+        transaction
+            .execute(&store_stmt, &[&auth.group_id, key, &response_json])
+            .await?;
+    }
+    
+    // Commit transaction
+    transaction.commit().await?;
+    
+    // Return response
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Whether the member already has a ledger entry for `source_id`
+async fn point_event_recorded(
+    transaction: &Transaction<'_>,
+    member_id: i64,
+    source_type: &str,
+    source_id: &str,
+) -> Result<bool, ApiError> {
+    let stmt = transaction
         .prepare_cached(
-            "SELECT SUM(points) FROM groupironman.player_points WHERE member_id = $1"
+            "SELECT EXISTS (
+                SELECT 1 FROM groupironman.point_events 
+                WHERE member_id = $1 AND source_type = $2 AND source_id = $3
+             )"
         )
         .await?;
     
-    let new_total_points = transaction
-        .query_one(&get_total_points_stmt, &[&member_id])
+    let recorded = transaction
+        .query_one(&stmt, &[&member_id, &source_type, &source_id])
         .await?
-        .try_get::<_, i64>(0)?;
+        .try_get(0)?;
     
-    // Commit transaction
-    transaction.commit().await?;
+    Ok(recorded)
+}
+
+/// Whether the plugin already reported this drop: the same item and quantity at the same time
+async fn plugin_drop_recorded(
+    transaction: &Transaction<'_>,
+    member_id: i64,
+    drop: &ValuableDrop,
+) -> Result<bool, ApiError> {
+    let stmt = transaction
+        .prepare_cached(
+            "SELECT EXISTS (
+                SELECT 1 FROM groupironman.valuable_drops 
+                WHERE member_id = $1 AND item_id = $2 AND timestamp = $3 
+                AND COALESCE(item_quantity, 1) = $4 AND entry_source = 'plugin'
+             )"
+        )
+        .await?;
     
-    // Return response
-    Ok(HttpResponse::Ok().json(PluginUpdateResponse {
-        status: "success".to_string(),
-        points_awarded: total_points_awarded,
-        activities_completed,
-        new_total_points: Some(new_total_points as i32),
-    }))
+    let recorded = transaction
+        .query_one(&stmt, &[&member_id, &drop.item_id, &drop.timestamp, &drop.item_quantity])
+        .await?
+        .try_get(0)?;
+    
+    Ok(recorded)
+}
+
+/// Complete every activity in the group's catalog (in `category`, if given) whose name matches one of the
+/// case-insensitive regular expressions in `name_patterns` and that the member hasn't completed yet, awarding each
+/// activity's points. Activities that require proof are left pending review and
+/// are awarded when approved, as with manual completions. Returns the names and
+/// points of the newly awarded activities and the names of the pending ones.
+#[allow(clippy::too_many_arguments)]
+async fn complete_matching_activities(
+    transaction: &Transaction<'_>,
+    group_id: i64,
+    member_id: i64,
+    category: Option<&str>,
+    name_patterns: &[String],
+    point_type: &str,
+    reason_suffix: &str,
+    completed_at: chrono::DateTime<chrono::Utc>,
+    proof_data: Option<&serde_json::Value>,
+) -> Result<(Vec<(String, i32)>, Vec<String>), ApiError> {
+    let find_stmt = transaction
+        .prepare_cached(
+            "SELECT a.activity_id, a.activity_name, COALESCE(a.point_value, 0), a.required_proof
+             FROM groupironman.group_activity_catalog($4) a
+             WHERE ($1::TEXT IS NULL OR a.category = $1)
+             AND a.activity_name ~* ANY($2)
+             AND NOT a.is_disabled
+             AND NOT EXISTS (
+                SELECT 1 FROM groupironman.player_activities pa
+                WHERE pa.member_id = $3 AND pa.activity_id = a.activity_id
+             )"
+        )
+        .await?;
+    
+    let rows = transaction
+        .query(&find_stmt, &[&category, &name_patterns, &member_id, &group_id])
+        .await?;
+    
+    let insert_stmt = transaction
+        .prepare_cached(
+            "INSERT INTO groupironman.player_activities 
+             (member_id, activity_id, completion_date, verified, proof_data, review_status) 
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (member_id, activity_id) DO NOTHING
+             RETURNING player_activity_id"
+        )
+        .await?;
+    
+    let mut completed = Vec::new();
+    let mut pending = Vec::new();
+    for row in rows {
+        let activity_id: i32 = row.try_get(0)?;
+        let activity_name: String = row.try_get(1)?;
+        let point_value: i32 = row.try_get(2)?;
+        let requires_proof: bool = row.try_get(3)?;
+        
+        let review_status = if requires_proof { "pending" } else { "approved" };
+        let verified = !requires_proof;
+        
+        let inserted = transaction
+            .query_opt(
+                &insert_stmt,
+                &[&member_id, &activity_id, &completed_at, &verified, &proof_data, &review_status],
+            )
+            .await?;
+        
+        let player_activity_id: i64 = match inserted {
+            Some(row) => row.try_get(0)?,
+            None => continue,
+        };
+        
+        if !verified {
+            pending.push(activity_name);
+            continue;
+        }
+        
+        record_point_event(
+            transaction,
+            member_id,
+            point_type,
+            point_value,
+            format!("{} {}", activity_name, reason_suffix).trim_end(),
+            "activity",
+            Some(&player_activity_id.to_string()),
+        )
+        .await?;
+        
+        completed.push((activity_name, point_value));
+    }
+    
+    Ok((completed, pending))
+}
+
+/// Escape regular expression metacharacters so a name only matches itself
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
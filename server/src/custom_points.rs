@@ -1,8 +1,9 @@
 use crate::auth_middleware::AuthedGroupId;
 use crate::db::{get_member_id};
 use crate::error::ApiError;
+use crate::seasons::find_season;
 use actix_web::{web, HttpResponse};
-use deadpool_postgres::Pool;
+use deadpool_postgres::{GenericClient, Pool};
 use serde::{Deserialize, Serialize};
 
 // Request types
@@ -11,21 +12,94 @@ pub struct AddPointsRequest {
     member_name: String,
     point_type: String,
     points: i32,
+    reason: Option<String>,
+    source_id: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct GetPointsRequest {
     member_name: Option<String>,
     point_type: Option<String>,
+    // Season id or "current"; lifetime totals when omitted
+    season: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GetPointEventsRequest {
+    member_name: Option<String>,
+    point_type: Option<String>,
+    source_type: Option<String>,
+    before_event_id: Option<i64>,
+    #[serde(default = "default_event_limit")]
+    limit: i64,
+}
+
+fn default_event_limit() -> i64 {
+    100
+}
+
+#[derive(Deserialize)]
+pub struct ReversePointEventRequest {
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GetActivitiesRequest {
+    #[serde(default)]
+    include_disabled: bool,
+    category: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateActivityRequest {
+    activity_name: String,
+    activity_description: Option<String>,
+    point_value: i32,
+    category: Option<String>,
+    icon_url: Option<String>,
+    #[serde(default)]
+    required_proof: bool,
+}
+
+// Fields left out keep their current value
+#[derive(Deserialize)]
+pub struct UpdateActivityRequest {
+    activity_name: Option<String>,
+    activity_description: Option<String>,
+    point_value: Option<i32>,
+    category: Option<String>,
+    icon_url: Option<String>,
+    required_proof: Option<bool>,
 }
 
 #[derive(Deserialize)]
 pub struct CompleteActivityRequest {
     member_name: String,
     activity_id: i32,
+    // {"screenshot": "..."} and/or {"text": "..."}
     proof_data: Option<serde_json::Value>,
 }
 
+#[derive(Deserialize)]
+pub struct PendingReviewsRequest {
+    // Leave out the reviewer's own submissions
+    reviewer_name: Option<String>,
+}
+
+#[derive(Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewDecision {
+    Approve,
+    Reject,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewActivityRequest {
+    reviewer_name: String,
+    decision: ReviewDecision,
+    note: Option<String>,
+}
+
 // Response types
 #[derive(Serialize)]
 pub struct MemberPoints {
@@ -33,6 +107,23 @@ pub struct MemberPoints {
     point_type: String,
     points: i32,
     last_updated: chrono::DateTime<chrono::Utc>,
+    // Only set for season-scoped queries, where points are the season's points
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lifetime_points: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct PointEvent {
+    event_id: i64,
+    member_name: String,
+    point_type: String,
+    points: i32,
+    reason: Option<String>,
+    source_type: String,
+    source_id: Option<String>,
+    reverses_event_id: Option<i64>,
+    reversed_by_event_id: Option<i64>,
+    created_at: chrono::DateTime<chrono::Utc>,
 }
 
 #[derive(Serialize)]
@@ -44,48 +135,163 @@ pub struct Activity {
     category: Option<String>,
     icon_url: Option<String>,
     required_proof: bool,
+    is_disabled: bool,
+    // Global default rather than one of the group's own activities
+    is_default: bool,
+}
+
+#[derive(Serialize)]
+pub struct ActivityCategory {
+    category: String,
+    activity_count: i64,
+    icon_url: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct PlayerActivity {
+    player_activity_id: i64,
     activity_id: i32,
     activity_name: String,
     completion_date: chrono::DateTime<chrono::Utc>,
     verified: bool,
+    // pending, approved or rejected
+    review_status: String,
 }
 
-// Add points to a player
+#[derive(Serialize)]
+pub struct PendingReview {
+    player_activity_id: i64,
+    member_name: String,
+    activity_id: i32,
+    activity_name: String,
+    point_value: i32,
+    proof_data: Option<serde_json::Value>,
+    completion_date: chrono::DateTime<chrono::Utc>,
+}
+
+// Record a single ledger entry. Positive points are awards, negative points are deductions.
+pub async fn record_point_event<C: GenericClient>(
+    client: &C,
+    member_id: i64,
+    point_type: &str,
+    points: i32,
+    reason: &str,
+    source_type: &str,
+    source_id: Option<&str>,
+) -> Result<i64, ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO groupironman.point_events 
+             (member_id, point_type, points, reason, source_type, source_id) 
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING event_id"
+        )
+        .await?;
+    
+    let event_id: i64 = client
+        .query_one(&stmt, &[&member_id, &point_type, &points, &reason, &source_type, &source_id])
+        .await?
+        .try_get(0)?;
+    
+    Ok(event_id)
+}
+
+// Record a ledger entry that a member can only earn once, such as reaching a level.
+// `source_type` must be one of the types covered by idx_point_events_once.
+// Returns None when the member already has the award.
+pub async fn record_point_event_once<C: GenericClient>(
+    client: &C,
+    member_id: i64,
+    point_type: &str,
+    points: i32,
+    reason: &str,
+    source_type: &str,
+    source_id: &str,
+) -> Result<Option<i64>, ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO groupironman.point_events 
+             (member_id, point_type, points, reason, source_type, source_id) 
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (member_id, source_type, source_id) 
+             WHERE source_type IN ('skill_level', 'collection_item', 'collection_set')
+             DO NOTHING
+             RETURNING event_id"
+        )
+        .await?;
+    
+    let event_id = client
+        .query_opt(&stmt, &[&member_id, &point_type, &points, &reason, &source_type, &source_id])
+        .await?
+        .map(|row| row.try_get(0))
+        .transpose()?;
+    
+    Ok(event_id)
+}
+
+// Total points across all point types, derived from the ledger
+pub async fn get_member_total_points<C: GenericClient>(
+    client: &C,
+    member_id: i64,
+) -> Result<i64, ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "SELECT COALESCE(SUM(points), 0)::BIGINT FROM groupironman.point_events WHERE member_id = $1"
+        )
+        .await?;
+    
+    let total: i64 = client.query_one(&stmt, &[&member_id]).await?.try_get(0)?;
+    
+    Ok(total)
+}
+
+// Add (or deduct) points for a player
 pub async fn add_points(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
     request: web::Json<AddPointsRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    let client = pool.get().await?;
-    let member_id = get_member_id(&client, auth.0, &request.member_name).await?;
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    let member_id = get_member_id(&transaction, auth.group_id, &request.member_name).await?;
     
-    let stmt = client
+    let default_reason = if request.points < 0 { "Manual deduction" } else { "Manual award" };
+    let reason = request.reason.as_deref().unwrap_or(default_reason);
+    
+    record_point_event(
+        &transaction,
+        member_id,
+        &request.point_type,
+        request.points,
+        reason,
+        "manual",
+        request.source_id.as_deref(),
+    )
+    .await?;
+    
+    let stmt = transaction
         .prepare_cached(
-            "INSERT INTO groupironman.player_points (member_id, point_type, points) 
-             VALUES ($1, $2, $3) 
-             ON CONFLICT (member_id, point_type) 
-             DO UPDATE SET points = groupironman.player_points.points + $3, 
-                          last_updated = CURRENT_TIMESTAMP
-             RETURNING points, last_updated"
+            "SELECT points, last_updated 
+             FROM groupironman.player_points 
+             WHERE member_id = $1 AND point_type = $2"
         )
         .await?;
     
-    let row = client
-        .query_one(&stmt, &[&member_id, &request.point_type, &request.points])
+    let row = transaction
+        .query_one(&stmt, &[&member_id, &request.point_type])
         .await?;
     
     let total_points: i32 = row.try_get(0)?;
     let last_updated: chrono::DateTime<chrono::Utc> = row.try_get(1)?;
     
+    transaction.commit().await?;
+    
     Ok(HttpResponse::Ok().json(MemberPoints {
         member_name: request.member_name.clone(),
         point_type: request.point_type.clone(),
         points: total_points,
         last_updated,
+        lifetime_points: None,
     }))
 }
 
@@ -97,11 +303,15 @@ pub async fn get_points(
 ) -> Result<HttpResponse, ApiError> {
     let client = pool.get().await?;
     
+    if let Some(season) = &query.season {
+        return get_season_points(&client, auth.group_id, season, &query).await;
+    }
+    
     let mut points = Vec::new();
     
     if let Some(member_name) = &query.member_name {
         // Get points for a specific player
-        let member_id = get_member_id(&client, auth.0, member_name).await?;
+        let member_id = get_member_id(&client, auth.group_id, member_name).await?;
         
         let stmt = client
             .prepare_cached(
@@ -134,6 +344,7 @@ pub async fn get_points(
                     point_type,
                     points: point_value,
                     last_updated,
+                    lifetime_points: None,
                 });
             }
         }
@@ -141,11 +352,11 @@ pub async fn get_points(
         // Get points for all players in the group
         let stmt = client
             .prepare_cached(
-                "SELECT m.name, pp.point_type, pp.points, pp.last_updated
+                "SELECT m.member_name, pp.point_type, pp.points, pp.last_updated
                  FROM groupironman.members m
-                 JOIN groupironman.player_points pp ON m.id = pp.member_id
+                 JOIN groupironman.player_points pp ON m.member_id = pp.member_id
                  WHERE m.group_id = $1
-                 ORDER BY m.name, pp.point_type"
+                 ORDER BY m.member_name, pp.point_type"
             )
             .await?;
         
@@ -156,7 +367,7 @@ pub async fn get_points(
         };
         
         let rows = client
-            .query(&stmt, &[&auth.0])
+            .query(&stmt, &[&auth.group_id])
             .await?;
         
         for row in rows {
@@ -172,6 +383,7 @@ pub async fn get_points(
                     point_type,
                     points: point_value,
                     last_updated,
+                    lifetime_points: None,
                 });
             }
         }
@@ -180,90 +392,690 @@ pub async fn get_points(
     Ok(HttpResponse::Ok().json(points))
 }
 
-// Get all available activities
-pub async fn get_activities(
+// Get points earned during a season, with lifetime totals alongside
+async fn get_season_points(
+    client: &deadpool_postgres::Client,
+    group_id: i64,
+    season: &str,
+    query: &GetPointsRequest,
+) -> Result<HttpResponse, ApiError> {
+    let season = match find_season(client, group_id, season).await? {
+        Some(season) => season,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "status": "error",
+                "message": "Season not found or not in your group"
+            })));
+        }
+    };
+    
+    let stmt = client
+        .prepare_cached(
+            "SELECT m.member_name, e.point_type,
+                    COALESCE(SUM(e.points) FILTER (WHERE e.created_at >= $2 AND e.created_at < $3), 0)::INT,
+                    SUM(e.points)::INT,
+                    MAX(e.created_at)
+             FROM groupironman.point_events e
+             JOIN groupironman.members m ON m.member_id = e.member_id
+             WHERE m.group_id = $1
+             AND ($4::TEXT IS NULL OR m.member_name = $4)
+             AND ($5::TEXT IS NULL OR e.point_type = $5)
+             GROUP BY m.member_name, e.point_type
+             ORDER BY m.member_name, e.point_type"
+        )
+        .await?;
+    
+    let rows = client
+        .query(
+            &stmt,
+            &[
+                &group_id,
+                &season.starts_at(),
+                &season.ends_at(),
+                &query.member_name,
+                &query.point_type,
+            ],
+        )
+        .await?;
+    
+    let points: Vec<MemberPoints> = rows
+        .iter()
+        .map(|row| {
+            Ok(MemberPoints {
+                member_name: row.try_get(0)?,
+                point_type: row.try_get(1)?,
+                points: row.try_get(2)?,
+                lifetime_points: Some(row.try_get(3)?),
+                last_updated: row.try_get(4)?,
+            })
+        })
+        .collect::<Result<_, tokio_postgres::Error>>()?;
+    
+    Ok(HttpResponse::Ok().json(points))
+}
+
+// Get ledger entries for the group, newest first
+pub async fn get_point_events(
     pool: web::Data<Pool>,
-    _auth: AuthedGroupId,
+    auth: AuthedGroupId,
+    query: web::Query<GetPointEventsRequest>,
 ) -> Result<HttpResponse, ApiError> {
     let client = pool.get().await?;
+    let limit = query.limit.clamp(1, 500);
     
     let stmt = client
         .prepare_cached(
-            "SELECT id, name, description, points, category, icon_url, requires_proof 
-             FROM groupironman.activities 
-             ORDER BY category, name"
+            "SELECT e.event_id, m.member_name, e.point_type, e.points, e.reason, e.source_type, 
+                    e.source_id, e.reverses_event_id, r.event_id, e.created_at
+             FROM groupironman.point_events e
+             JOIN groupironman.members m ON m.member_id = e.member_id
+             LEFT JOIN groupironman.point_events r ON r.reverses_event_id = e.event_id
+             WHERE m.group_id = $1
+             AND ($2::TEXT IS NULL OR m.member_name = $2)
+             AND ($3::TEXT IS NULL OR e.point_type = $3)
+             AND ($4::TEXT IS NULL OR e.source_type = $4)
+             AND ($5::BIGINT IS NULL OR e.event_id < $5)
+             ORDER BY e.event_id DESC
+             LIMIT $6"
+        )
+        .await?;
+    
+    let rows = client
+        .query(
+            &stmt,
+            &[
+                &auth.group_id,
+                &query.member_name,
+                &query.point_type,
+                &query.source_type,
+                &query.before_event_id,
+                &limit,
+            ],
+        )
+        .await?;
+    
+    let events: Vec<PointEvent> = rows
+        .iter()
+        .map(point_event_from_row)
+        .collect::<Result<_, tokio_postgres::Error>>()?;
+    
+    Ok(HttpResponse::Ok().json(events))
+}
+
+// Reverse a ledger entry by appending an offsetting event
+pub async fn reverse_point_event(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+    request: web::Json<ReversePointEventRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, event_id) = path.into_inner();
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    
+    // Lock the original event so concurrent reversals serialize
+    let stmt = transaction
+        .prepare_cached(
+            "SELECT e.member_id, e.point_type, e.points, e.reverses_event_id,
+                    (SELECT r.event_id FROM groupironman.point_events r WHERE r.reverses_event_id = e.event_id)
+             FROM groupironman.point_events e
+             JOIN groupironman.members m ON m.member_id = e.member_id
+             WHERE e.event_id = $1 AND m.group_id = $2
+             FOR UPDATE OF e"
+        )
+        .await?;
+    
+    let row = match transaction.query_opt(&stmt, &[&event_id, &auth.group_id]).await? {
+        Some(row) => row,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "status": "error",
+                "message": "Point event not found or not in your group"
+            })));
+        }
+    };
+    
+    let member_id: i64 = row.try_get(0)?;
+    let point_type: String = row.try_get(1)?;
+    let points: i32 = row.try_get(2)?;
+    let reverses_event_id: Option<i64> = row.try_get(3)?;
+    let reversed_by_event_id: Option<i64> = row.try_get(4)?;
+    
+    if reverses_event_id.is_some() {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": "Reversal events cannot be reversed"
+        })));
+    }
+    
+    if reversed_by_event_id.is_some() {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": "Point event has already been reversed"
+        })));
+    }
+    
+    let default_reason = format!("Reversal of event {}", event_id);
+    let reason = request.reason.as_deref().unwrap_or(&default_reason);
+    
+    let insert_stmt = transaction
+        .prepare_cached(
+            "INSERT INTO groupironman.point_events 
+             (member_id, point_type, points, reason, source_type, source_id, reverses_event_id) 
+             VALUES ($1, $2, $3, $4, 'reversal', $5, $6)
+             RETURNING event_id"
+        )
+        .await?;
+    
+    let reversal_id: i64 = transaction
+        .query_one(
+            &insert_stmt,
+            &[
+                &member_id,
+                &point_type,
+                &-points,
+                &reason,
+                &event_id.to_string(),
+                &event_id,
+            ],
+        )
+        .await?
+        .try_get(0)?;
+    
+    let select_stmt = transaction
+        .prepare_cached(
+            "SELECT e.event_id, m.member_name, e.point_type, e.points, e.reason, e.source_type, 
+                    e.source_id, e.reverses_event_id, NULL::BIGINT, e.created_at
+             FROM groupironman.point_events e
+             JOIN groupironman.members m ON m.member_id = e.member_id
+             WHERE e.event_id = $1"
         )
         .await?;
     
-    let rows = client.query(&stmt, &[]).await?;
+    let reversal = point_event_from_row(&transaction.query_one(&select_stmt, &[&reversal_id]).await?)?;
+    
+    transaction.commit().await?;
+    
+    Ok(HttpResponse::Ok().json(reversal))
+}
+
+fn point_event_from_row(row: &tokio_postgres::Row) -> Result<PointEvent, tokio_postgres::Error> {
+    Ok(PointEvent {
+        event_id: row.try_get(0)?,
+        member_name: row.try_get(1)?,
+        point_type: row.try_get(2)?,
+        points: row.try_get(3)?,
+        reason: row.try_get(4)?,
+        source_type: row.try_get(5)?,
+        source_id: row.try_get(6)?,
+        reverses_event_id: row.try_get(7)?,
+        reversed_by_event_id: row.try_get(8)?,
+        created_at: row.try_get(9)?,
+    })
+}
+
+// Get the group's activity catalog: global defaults with the group's changes, plus its own activities
+pub async fn get_activities(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    query: web::Query<GetActivitiesRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    
+    let stmt = client
+        .prepare_cached(&format!(
+            "SELECT {} 
+             FROM groupironman.group_activity_catalog($1) 
+             WHERE ($2 OR NOT is_disabled)
+             AND ($3::TEXT IS NULL OR category = $3)
+             ORDER BY category, activity_name",
+            ACTIVITY_COLUMNS
+        ))
+        .await?;
+    
+    let rows = client
+        .query(&stmt, &[&auth.group_id, &query.include_disabled, &query.category])
+        .await?;
     
     let activities: Vec<Activity> = rows
+        .iter()
+        .map(activity_from_row)
+        .collect::<Result<_, tokio_postgres::Error>>()?;
+    
+    Ok(HttpResponse::Ok().json(activities))
+}
+
+// Get the categories used in the group's catalog, with their icons
+pub async fn get_activity_categories(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    
+    let stmt = client
+        .prepare_cached(
+            "SELECT category, COUNT(*), MIN(icon_url)
+             FROM groupironman.group_activity_catalog($1)
+             WHERE NOT is_disabled AND category IS NOT NULL
+             GROUP BY category
+             ORDER BY category"
+        )
+        .await?;
+    
+    let categories: Vec<ActivityCategory> = client
+        .query(&stmt, &[&auth.group_id])
+        .await?
         .iter()
         .map(|row| {
-            Ok(Activity {
-                activity_id: row.try_get(0)?,
-                activity_name: row.try_get(1)?,
-                activity_description: row.try_get(2)?,
-                point_value: row.try_get(3)?,
-                category: row.try_get(4)?,
-                icon_url: row.try_get(5)?,
-                required_proof: row.try_get(6)?,
+            Ok(ActivityCategory {
+                category: row.try_get(0)?,
+                activity_count: row.try_get(1)?,
+                icon_url: row.try_get(2)?,
             })
         })
         .collect::<Result<_, tokio_postgres::Error>>()?;
     
-    Ok(HttpResponse::Ok().json(activities))
+    Ok(HttpResponse::Ok().json(categories))
 }
 
-// Mark an activity as completed
-pub async fn complete_activity(
+// Add an activity to the group's catalog
+pub async fn create_activity(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    request: web::Json<CompleteActivityRequest>,
+    request: web::Json<CreateActivityRequest>,
 ) -> Result<HttpResponse, ApiError> {
+    if let Err(message) = validate_activity_fields(
+        Some(&request.activity_name),
+        Some(request.point_value),
+        request.category.as_deref(),
+        request.icon_url.as_deref(),
+    ) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": message
+        })));
+    }
+    
     let client = pool.get().await?;
     
-    // Get member ID
-    let member_id = get_member_id(&client, auth.0, &request.member_name).await?;
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO groupironman.custom_activities 
+             (group_id, activity_name, activity_description, point_value, category, icon_url, required_proof) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING activity_id"
+        )
+        .await?;
+    
+    let activity_id: i32 = client
+        .query_one(
+            &stmt,
+            &[
+                &auth.group_id,
+                &request.activity_name.trim(),
+                &request.activity_description,
+                &request.point_value,
+                &request.category,
+                &request.icon_url,
+                &request.required_proof,
+            ],
+        )
+        .await?
+        .try_get(0)?;
+    
+    let activity = get_catalog_activity(&client, auth.group_id, activity_id).await?;
+    
+    Ok(HttpResponse::Created().json(activity))
+}
+
+// Edit an activity. Editing a global default only changes it for this group.
+pub async fn update_activity(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i32)>,
+    request: web::Json<UpdateActivityRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, activity_id) = path.into_inner();
+    
+    if let Err(message) = validate_activity_fields(
+        request.activity_name.as_deref(),
+        request.point_value,
+        request.category.as_deref(),
+        request.icon_url.as_deref(),
+    ) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": message
+        })));
+    }
+    
+    let client = pool.get().await?;
+    let activity_name = request.activity_name.as_deref().map(str::trim);
+    
+    let stmt = match get_activity_owner(&client, auth.group_id, activity_id).await? {
+        None => return Ok(activity_not_found()),
+        Some(ActivityOwner::Default) => {
+            client
+                .prepare_cached(
+                    "INSERT INTO groupironman.group_activity_overrides 
+                     (group_id, activity_id, activity_name, activity_description, point_value, 
+                      category, icon_url, required_proof) 
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                     ON CONFLICT (group_id, activity_id) DO UPDATE SET 
+                        activity_name = COALESCE($3, group_activity_overrides.activity_name),
+                        activity_description = COALESCE($4, group_activity_overrides.activity_description),
+                        point_value = COALESCE($5, group_activity_overrides.point_value),
+                        category = COALESCE($6, group_activity_overrides.category),
+                        icon_url = COALESCE($7, group_activity_overrides.icon_url),
+                        required_proof = COALESCE($8, group_activity_overrides.required_proof),
+                        updated_at = NOW()"
+                )
+                .await?
+        }
+        Some(ActivityOwner::Group) => {
+            client
+                .prepare_cached(
+                    "UPDATE groupironman.custom_activities SET 
+                        activity_name = COALESCE($3, activity_name),
+                        activity_description = COALESCE($4, activity_description),
+                        point_value = COALESCE($5, point_value),
+                        category = COALESCE($6, category),
+                        icon_url = COALESCE($7, icon_url),
+                        required_proof = COALESCE($8, required_proof),
+                        updated_at = NOW()
+                     WHERE group_id = $1 AND activity_id = $2"
+                )
+                .await?
+        }
+    };
+    
+    client
+        .execute(
+            &stmt,
+            &[
+                &auth.group_id,
+                &activity_id,
+                &activity_name,
+                &request.activity_description,
+                &request.point_value,
+                &request.category,
+                &request.icon_url,
+                &request.required_proof,
+            ],
+        )
+        .await?;
+    
+    let activity = get_catalog_activity(&client, auth.group_id, activity_id).await?;
+    
+    Ok(HttpResponse::Ok().json(activity))
+}
+
+// Disable an activity so it can't be completed; existing completions keep their points
+pub async fn disable_activity(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, activity_id) = path.into_inner();
+    set_activity_disabled(pool, auth.group_id, activity_id, true).await
+}
+
+// Enable a disabled activity
+pub async fn enable_activity(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, activity_id) = path.into_inner();
+    set_activity_disabled(pool, auth.group_id, activity_id, false).await
+}
+
+async fn set_activity_disabled(
+    pool: web::Data<Pool>,
+    group_id: i64,
+    activity_id: i32,
+    disabled: bool,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    
+    let stmt = match get_activity_owner(&client, group_id, activity_id).await? {
+        None => return Ok(activity_not_found()),
+        Some(ActivityOwner::Default) => {
+            client
+                .prepare_cached(
+                    "INSERT INTO groupironman.group_activity_overrides (group_id, activity_id, is_disabled) 
+                     VALUES ($1, $2, $3)
+                     ON CONFLICT (group_id, activity_id) DO UPDATE SET is_disabled = $3, updated_at = NOW()"
+                )
+                .await?
+        }
+        Some(ActivityOwner::Group) => {
+            client
+                .prepare_cached(
+                    "UPDATE groupironman.custom_activities SET is_disabled = $3, updated_at = NOW() 
+                     WHERE group_id = $1 AND activity_id = $2"
+                )
+                .await?
+        }
+    };
+    
+    client.execute(&stmt, &[&group_id, &activity_id, &disabled]).await?;
+    
+    let activity = get_catalog_activity(&client, group_id, activity_id).await?;
     
-    // Get activity details
+    Ok(HttpResponse::Ok().json(activity))
+}
+
+enum ActivityOwner {
+    // A global default, customized through group_activity_overrides
+    Default,
+    // One of the group's own activities
+    Group,
+}
+
+// Whether an activity in the group's catalog is a global default or the group's own
+async fn get_activity_owner(
+    client: &deadpool_postgres::Client,
+    group_id: i64,
+    activity_id: i32,
+) -> Result<Option<ActivityOwner>, ApiError> {
     let stmt = client
         .prepare_cached(
-            "SELECT name, points, requires_proof 
-             FROM groupironman.activities 
-             WHERE id = $1"
+            "SELECT group_id FROM groupironman.custom_activities 
+             WHERE activity_id = $1 AND (group_id IS NULL OR group_id = $2)"
         )
         .await?;
     
-    let row = client
-        .query_one(&stmt, &[&request.activity_id])
+    let owner = match client.query_opt(&stmt, &[&activity_id, &group_id]).await? {
+        Some(row) => match row.try_get::<_, Option<i64>>(0)? {
+            Some(_) => Some(ActivityOwner::Group),
+            None => Some(ActivityOwner::Default),
+        },
+        None => None,
+    };
+    
+    Ok(owner)
+}
+
+async fn get_catalog_activity(
+    client: &deadpool_postgres::Client,
+    group_id: i64,
+    activity_id: i32,
+) -> Result<Activity, ApiError> {
+    let stmt = client
+        .prepare_cached(&format!(
+            "SELECT {} FROM groupironman.group_activity_catalog($1) WHERE activity_id = $2",
+            ACTIVITY_COLUMNS
+        ))
+        .await?;
+    
+    let row = client.query_one(&stmt, &[&group_id, &activity_id]).await?;
+    
+    Ok(activity_from_row(&row)?)
+}
+
+fn activity_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "status": "error",
+        "message": "Activity not found or not in your group"
+    }))
+}
+
+fn validate_activity_fields(
+    activity_name: Option<&str>,
+    point_value: Option<i32>,
+    category: Option<&str>,
+    icon_url: Option<&str>,
+) -> Result<(), String> {
+    if let Some(name) = activity_name {
+        let name = name.trim();
+        if name.is_empty() || name.len() > 100 {
+            return Err("Activity name must be between 1 and 100 characters".to_string());
+        }
+    }
+    
+    if let Some(points) = point_value {
+        if !(0..=10_000).contains(&points) {
+            return Err("Point value must be between 0 and 10000".to_string());
+        }
+    }
+    
+    if let Some(category) = category {
+        if category.trim().is_empty() || category.len() > 50 {
+            return Err("Category must be between 1 and 50 characters".to_string());
+        }
+    }
+    
+    if let Some(icon_url) = icon_url {
+        let allowed = icon_url.starts_with("https://") || icon_url.starts_with("http://") || icon_url.starts_with('/');
+        if !allowed || icon_url.len() > 255 {
+            return Err("Icon URL must be an http(s) URL or a site path of at most 255 characters".to_string());
+        }
+    }
+    
+    Ok(())
+}
+
+const ACTIVITY_COLUMNS: &str = "activity_id, activity_name, activity_description, point_value, \
+    category, icon_url, required_proof, is_disabled, is_default";
+
+fn activity_from_row(row: &tokio_postgres::Row) -> Result<Activity, tokio_postgres::Error> {
+    Ok(Activity {
+        activity_id: row.try_get(0)?,
+        activity_name: row.try_get(1)?,
+        activity_description: row.try_get(2)?,
+        point_value: row.try_get(3)?,
+        category: row.try_get(4)?,
+        icon_url: row.try_get(5)?,
+        required_proof: row.try_get(6)?,
+        is_disabled: row.try_get(7)?,
+        is_default: row.try_get(8)?,
+    })
+}
+
+// Proof must be a screenshot reference or a text description
+fn has_proof(proof_data: &Option<serde_json::Value>) -> bool {
+    let non_empty = |field: &str| {
+        proof_data
+            .as_ref()
+            .and_then(|proof| proof.get(field))
+            .and_then(|value| value.as_str())
+            .is_some_and(|value| !value.trim().is_empty())
+    };
+    
+    non_empty("screenshot") || non_empty("text")
+}
+
+// Mark an activity as completed. Activities that require proof wait for another
+// member to approve them before any points are awarded.
+pub async fn complete_activity(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    request: web::Json<CompleteActivityRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    
+    // Get member ID
+    let member_id = get_member_id(&transaction, auth.group_id, &request.member_name).await?;
+    
+    // Get activity details from the group's catalog
+    let stmt = transaction
+        .prepare_cached(
+            "SELECT activity_name, point_value, required_proof, is_disabled 
+             FROM groupironman.group_activity_catalog($1) 
+             WHERE activity_id = $2"
+        )
         .await?;
     
+    let row = match transaction.query_opt(&stmt, &[&auth.group_id, &request.activity_id]).await? {
+        Some(row) => row,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "status": "error",
+                "message": "Activity not found"
+            })));
+        }
+    };
+    
     let activity_name: String = row.try_get(0)?;
     let points: i32 = row.try_get(1)?;
     let requires_proof: bool = row.try_get(2)?;
+    let is_disabled: bool = row.try_get(3)?;
     
-    // Check if proof is required but not provided
-    let verified = if requires_proof {
-        match &request.proof_data {
-            Some(_) => true,
-            None => false,
-        }
-    } else {
-        true
-    };
+    if is_disabled {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": "Activity is disabled for this group"
+        })));
+    }
+    
+    if requires_proof && !has_proof(&request.proof_data) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "This activity requires proof: a screenshot reference or text"
+        })));
+    }
+    
+    // A rejected completion can be resubmitted; anything else is a duplicate
+    let existing_stmt = transaction
+        .prepare_cached(
+            "SELECT review_status FROM groupironman.player_activities 
+             WHERE member_id = $1 AND activity_id = $2
+             FOR UPDATE"
+        )
+        .await?;
+    
+    let existing_status: Option<String> = transaction
+        .query_opt(&existing_stmt, &[&member_id, &request.activity_id])
+        .await?
+        .map(|row| row.try_get(0))
+        .transpose()?;
+    
+    if existing_status.as_deref().is_some_and(|status| status != "rejected") {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": "Activity has already been submitted"
+        })));
+    }
+    
+    let review_status = if requires_proof { "pending" } else { "approved" };
+    let verified = !requires_proof;
     
     // Record completion
-    let stmt = client
+    let stmt = transaction
         .prepare_cached(
             "INSERT INTO groupironman.player_activities 
-             (member_id, activity_id, completion_date, verified, proof_data) 
-             VALUES ($1, $2, CURRENT_TIMESTAMP, $3, $4)
-             RETURNING completion_date"
+             (member_id, activity_id, completion_date, verified, proof_data, review_status) 
+             VALUES ($1, $2, NOW(), $3, $4, $5)
+             ON CONFLICT (member_id, activity_id) DO UPDATE 
+             SET completion_date = NOW(), verified = $3, proof_data = $4, review_status = $5,
+                 reviewed_by_member_id = NULL, reviewed_at = NULL, review_note = NULL
+             RETURNING player_activity_id, completion_date"
         )
         .await?;
     
-    let row = client
+    let row = transaction
         .query_one(
             &stmt,
             &[
@@ -271,34 +1083,183 @@ pub async fn complete_activity(
                 &request.activity_id,
                 &verified,
                 &request.proof_data,
+                &review_status,
             ],
         )
         .await?;
     
-    let completion_date: chrono::DateTime<chrono::Utc> = row.try_get(0)?;
+    let player_activity_id: i64 = row.try_get(0)?;
+    let completion_date: chrono::DateTime<chrono::Utc> = row.try_get(1)?;
     
-    // Award points if verified
+    // Activities without a proof requirement are awarded straight away
     if verified {
-        let stmt = client
-            .prepare_cached(
-                "INSERT INTO groupironman.player_points (member_id, point_type, points) 
-                 VALUES ($1, 'activity', $2) 
-                 ON CONFLICT (member_id, point_type) 
-                 DO UPDATE SET points = groupironman.player_points.points + $2,
-                              last_updated = CURRENT_TIMESTAMP"
-            )
-            .await?;
-        
-        client
-            .execute(&stmt, &[&member_id, &points])
-            .await?;
+        record_point_event(
+            &transaction,
+            member_id,
+            "activity",
+            points,
+            &format!("Completed {}", activity_name),
+            "activity",
+            Some(&player_activity_id.to_string()),
+        )
+        .await?;
     }
     
+    transaction.commit().await?;
+    
     Ok(HttpResponse::Ok().json(PlayerActivity {
+        player_activity_id,
         activity_id: request.activity_id,
         activity_name,
         completion_date,
         verified,
+        review_status: review_status.to_string(),
+    }))
+}
+
+// Get completions waiting for review, oldest first
+pub async fn get_pending_reviews(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    query: web::Query<PendingReviewsRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    
+    let stmt = client
+        .prepare_cached(
+            "SELECT pa.player_activity_id, m.member_name, a.activity_id, a.activity_name, 
+                    a.point_value, pa.proof_data, pa.completion_date
+             FROM groupironman.player_activities pa
+             JOIN groupironman.members m ON m.member_id = pa.member_id
+             JOIN groupironman.group_activity_catalog($1) a ON a.activity_id = pa.activity_id
+             WHERE m.group_id = $1 
+             AND pa.review_status = 'pending'
+             AND ($2::TEXT IS NULL OR m.member_name != $2)
+             ORDER BY pa.completion_date"
+        )
+        .await?;
+    
+    let rows = client
+        .query(&stmt, &[&auth.group_id, &query.reviewer_name])
+        .await?;
+    
+    let reviews: Vec<PendingReview> = rows
+        .iter()
+        .map(|row| {
+            Ok(PendingReview {
+                player_activity_id: row.try_get(0)?,
+                member_name: row.try_get(1)?,
+                activity_id: row.try_get(2)?,
+                activity_name: row.try_get(3)?,
+                point_value: row.try_get(4)?,
+                proof_data: row.try_get(5)?,
+                completion_date: row.try_get(6)?,
+            })
+        })
+        .collect::<Result<_, tokio_postgres::Error>>()?;
+    
+    Ok(HttpResponse::Ok().json(reviews))
+}
+
+// Approve or reject a pending completion. Members cannot review their own submissions.
+pub async fn review_activity(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+    request: web::Json<ReviewActivityRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, player_activity_id) = path.into_inner();
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    
+    let reviewer_id = get_member_id(&transaction, auth.group_id, &request.reviewer_name).await?;
+    
+    // Lock the completion so two reviewers can't both decide it
+    let stmt = transaction
+        .prepare_cached(
+            "SELECT pa.member_id, pa.activity_id, pa.review_status, pa.completion_date, 
+                    a.activity_name, a.point_value
+             FROM groupironman.player_activities pa
+             JOIN groupironman.members m ON m.member_id = pa.member_id
+             JOIN groupironman.group_activity_catalog($2) a ON a.activity_id = pa.activity_id
+             WHERE pa.player_activity_id = $1 AND m.group_id = $2
+             FOR UPDATE OF pa"
+        )
+        .await?;
+    
+    let row = match transaction.query_opt(&stmt, &[&player_activity_id, &auth.group_id]).await? {
+        Some(row) => row,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "status": "error",
+                "message": "Completion not found or not in your group"
+            })));
+        }
+    };
+    
+    let member_id: i64 = row.try_get(0)?;
+    let activity_id: i32 = row.try_get(1)?;
+    let review_status: String = row.try_get(2)?;
+    let completion_date: chrono::DateTime<chrono::Utc> = row.try_get(3)?;
+    let activity_name: String = row.try_get(4)?;
+    let points: i32 = row.try_get(5)?;
+    
+    if member_id == reviewer_id {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "status": "error",
+            "message": "Members cannot review their own submissions"
+        })));
+    }
+    
+    if review_status != "pending" {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": format!("Completion has already been {}", review_status)
+        })));
+    }
+    
+    let approved = request.decision == ReviewDecision::Approve;
+    let new_status = if approved { "approved" } else { "rejected" };
+    
+    let update_stmt = transaction
+        .prepare_cached(
+            "UPDATE groupironman.player_activities 
+             SET review_status = $2, verified = $3, reviewed_by_member_id = $4, 
+                 reviewed_at = NOW(), review_note = $5
+             WHERE player_activity_id = $1"
+        )
+        .await?;
+    
+    transaction
+        .execute(
+            &update_stmt,
+            &[&player_activity_id, &new_status, &approved, &reviewer_id, &request.note],
+        )
+        .await?;
+    
+    // Points are only awarded once the completion is approved
+    if approved {
+        record_point_event(
+            &transaction,
+            member_id,
+            "activity",
+            points,
+            &format!("Completed {} (approved by {})", activity_name, request.reviewer_name),
+            "activity",
+            Some(&player_activity_id.to_string()),
+        )
+        .await?;
+    }
+    
+    transaction.commit().await?;
+    
+    Ok(HttpResponse::Ok().json(PlayerActivity {
+        player_activity_id,
+        activity_id,
+        activity_name,
+        completion_date,
+        verified: approved,
+        review_status: new_status.to_string(),
     }))
 }
 
@@ -306,33 +1267,37 @@ pub async fn complete_activity(
 pub async fn get_player_activities(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    member_name: web::Path<String>,
+    path: web::Path<(String, String)>,
 ) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
     let client = pool.get().await?;
     
     // Get member ID
-    let member_id = get_member_id(&client, auth.0, &member_name).await?;
+    let member_id = get_member_id(&client, auth.group_id, &member_name).await?;
     
     let stmt = client
         .prepare_cached(
-            "SELECT a.id, a.name, pa.completion_date, pa.verified
+            "SELECT pa.player_activity_id, a.activity_id, a.activity_name, pa.completion_date, 
+                    COALESCE(pa.verified, FALSE), pa.review_status
              FROM groupironman.player_activities pa
-             JOIN groupironman.activities a ON pa.activity_id = a.id
+             JOIN groupironman.group_activity_catalog($2) a ON pa.activity_id = a.activity_id
              WHERE pa.member_id = $1
              ORDER BY pa.completion_date DESC"
         )
         .await?;
     
-    let rows = client.query(&stmt, &[&member_id]).await?;
+    let rows = client.query(&stmt, &[&member_id, &auth.group_id]).await?;
     
     let activities: Vec<PlayerActivity> = rows
         .iter()
         .map(|row| {
             Ok(PlayerActivity {
-                activity_id: row.try_get(0)?,
-                activity_name: row.try_get(1)?,
-                completion_date: row.try_get(2)?,
-                verified: row.try_get(3)?,
+                player_activity_id: row.try_get(0)?,
+                activity_id: row.try_get(1)?,
+                activity_name: row.try_get(2)?,
+                completion_date: row.try_get(3)?,
+                verified: row.try_get(4)?,
+                review_status: row.try_get(5)?,
             })
         })
         .collect::<Result<_, tokio_postgres::Error>>()?;
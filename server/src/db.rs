@@ -112,17 +112,17 @@ pub async fn delete_collection_log_data_for_member(
     member_id: i64
 ) -> Result<(), ApiError> {
     let a = "DELETE FROM groupironman.collection_log WHERE member_id=$1";
-    let delete_collection_stmt = transaction.prepare_cached(&a).await?;
+    let delete_collection_stmt = transaction.prepare_cached(a).await?;
     transaction.execute(&delete_collection_stmt, &[&member_id]).await?;
 
     let b = "DELETE FROM groupironman.collection_log_new WHERE member_id=$1";
-    let delete_new_stmt = transaction.prepare_cached(&b).await?;
+    let delete_new_stmt = transaction.prepare_cached(b).await?;
     transaction.execute(&delete_new_stmt, &[&member_id]).await?;
 
     Ok(())
 }
 
-pub async fn get_member_id(client: &Client, group_id: i64, member_name: &str) -> Result<i64, ApiError> {
+pub async fn get_member_id<C: deadpool_postgres::GenericClient>(client: &C, group_id: i64, member_name: &str) -> Result<i64, ApiError> {
     let get_member_id_stmt = client
         .prepare_cached(
             "SELECT member_id FROM groupironman.members WHERE group_id=$1 AND member_name=$2",
@@ -141,7 +141,7 @@ pub async fn delete_group_member(
     group_id: i64,
     member_name: &str,
 ) -> Result<(), ApiError> {
-    let member_id = get_member_id(&client, group_id, member_name).await?;
+    let member_id = get_member_id(&*client, group_id, member_name).await?;
     let transaction = client.transaction().await?;
     delete_skills_data_for_member(&transaction, AggregatePeriod::Day, member_id).await?;
     delete_skills_data_for_member(&transaction, AggregatePeriod::Month, member_id).await?;
@@ -268,121 +268,106 @@ UPDATE groupironman.members SET
         .map_err(ApiError::UpdateGroupMemberError)?;
 
     // Merge deposited items into bank
-    match group_member.deposited {
-        Some(deposited) => {
-            deposit_items(client, group_id, &group_member.name, deposited).await?;
-        }
-        None => (),
+    if let Some(deposited) = group_member.deposited {
+        deposit_items(client, group_id, &group_member.name, deposited).await?;
     }
 
     // Update shared bank
-    match group_member.shared_bank {
-        Some(shared_bank) => {
-            let stmt = client
-                .prepare_cached(
-                    r#"
+    if let Some(shared_bank) = group_member.shared_bank {
+        let stmt = client
+            .prepare_cached(
+                r#"
 UPDATE groupironman.members SET
 bank=$1, bank_last_update=NOW()
 WHERE group_id=$2 AND member_name=$3"#,
-                )
-                .await?;
+            )
+            .await?;
 
-            client
-                .execute(
-                    &stmt,
-                    &[
-                        &shared_bank,
-                        &group_id,
-                        &SHARED_MEMBER,
-                    ],
-                )
-                .await
-                .map_err(ApiError::UpdateGroupMemberError)?;
-        }
-        None => (),
+        client
+            .execute(
+                &stmt,
+                &[
+                    &shared_bank,
+                    &group_id,
+                    &SHARED_MEMBER,
+                ],
+            )
+            .await
+            .map_err(ApiError::UpdateGroupMemberError)?;
     }
 
     // Update collection log items and kill/completion counts
-    match group_member.collection_log {
-        Some(collection_logs) => {
-            let member_id = get_member_id(client, group_id, &group_member.name).await?;
-            let stmt = client.prepare_cached(
-                r#"
+    if let Some(collection_logs) = group_member.collection_log {
+        let member_id = get_member_id(client, group_id, &group_member.name).await?;
+        let stmt = client.prepare_cached(
+            r#"
 INSERT INTO groupironman.collection_log (member_id, page_id, items, counts, last_updated, group_id)
 VALUES ($1, $2, $3, $4, NOW(), $5)
 ON CONFLICT (member_id, page_id)
 DO UPDATE SET items=EXCLUDED.items, counts=EXCLUDED.counts, last_updated=EXCLUDED.last_updated
 "#).await?;
-            let clear_new_items_stmt = client.prepare_cached(
-                r#"
+        let clear_new_items_stmt = client.prepare_cached(
+            r#"
 UPDATE groupironman.collection_log_new SET new_items=ARRAY[]::INTEGER[], last_updated=NOW()
 WHERE member_id=$1 AND page_id=$2
 "#).await?;
-            for collection_log in collection_logs {
-                let page_id = collection_log_info.page_name_to_id(&collection_log.page_name);
-                client
-                    .execute(&stmt, &[&member_id, &page_id, &collection_log.items, &collection_log.completion_counts, &group_id])
-                    .await
-                    .map_err(ApiError::UpdateGroupMemberError)?;
-                client.execute(&clear_new_items_stmt, &[&member_id, &page_id])
-                    .await
-                    .map_err(ApiError::UpdateGroupMemberError)?;
-            }
+        for collection_log in collection_logs {
+            let page_id = collection_log_info.page_name_to_id(&collection_log.page_name);
+            client
+                .execute(&stmt, &[&member_id, &page_id, &collection_log.items, &collection_log.completion_counts, &group_id])
+                .await
+                .map_err(ApiError::UpdateGroupMemberError)?;
+            client.execute(&clear_new_items_stmt, &[&member_id, &page_id])
+                .await
+                .map_err(ApiError::UpdateGroupMemberError)?;
         }
-        None => ()
     }
 
     // Update new collection log drops
-    match group_member.collection_log_new {
-        Some(collection_log_new) => {
-            let member_id = get_member_id(client, group_id, &group_member.name).await?;
-            let mut item_ids: Vec<i32> = vec![];
-            // Convert the item names to ids
-            for item_name in collection_log_new {
-                match collection_log_info.item_name_to_id(&item_name) {
-                    Some(id) => item_ids.push(*id),
-                    None => {
-                        return Err(ApiError::GroupMemberValidationError(format!("{} is not a known collection log item", item_name)));
-                    }
-                };
-            }
+    if let Some(collection_log_new) = group_member.collection_log_new {
+        let member_id = get_member_id(client, group_id, &group_member.name).await?;
+        let mut item_ids: Vec<i32> = vec![];
+        // Convert the item names to ids
+        for item_name in collection_log_new {
+            match collection_log_info.item_name_to_id(&item_name) {
+                Some(id) => item_ids.push(*id),
+                None => {
+                    return Err(ApiError::GroupMemberValidationError(format!("{} is not a known collection log item", item_name)));
+                }
+            };
+        }
 
-            // map the page ids we need to update to the set of item ids
-            let mut page_ids_to_item_ids: HashMap<i16, HashSet<i32>> = HashMap::new();
-            for item_id in item_ids {
-                match collection_log_info.page_ids_for_item(item_id) {
-                    Some(page_ids) => {
-                        for page_id in page_ids {
-                            if !page_ids_to_item_ids.contains_key(&page_id) {
-                                page_ids_to_item_ids.insert(*page_id, HashSet::new());
-                            }
-
-                            match page_ids_to_item_ids.get_mut(&page_id) {
-                                Some(x) => x.insert(item_id),
-                                None => true
-                            };
-                        }
+        // map the page ids we need to update to the set of item ids
+        let mut page_ids_to_item_ids: HashMap<i16, HashSet<i32>> = HashMap::new();
+        for item_id in item_ids {
+            if let Some(page_ids) = collection_log_info.page_ids_for_item(item_id) {
+                for page_id in page_ids {
+                    if !page_ids_to_item_ids.contains_key(page_id) {
+                        page_ids_to_item_ids.insert(*page_id, HashSet::new());
                     }
-                    None => ()
-                };
+
+                    match page_ids_to_item_ids.get_mut(page_id) {
+                        Some(x) => x.insert(item_id),
+                        None => true
+                    };
+                }
             }
+        }
 
-            let update_new_items_stmt = client.prepare_cached(r#"
+        let update_new_items_stmt = client.prepare_cached(r#"
 INSERT INTO groupironman.collection_log_new (member_id, page_id, new_items, last_updated, group_id)
 VALUES ($1, $2, $3, NOW(), $4)
 ON CONFLICT(member_id, page_id)
 DO UPDATE SET new_items=EXCLUDED.new_items, last_updated=EXCLUDED.last_updated
 "#).await?;
-            // Combine the existing items with the new items
-            for (page_id, item_ids) in page_ids_to_item_ids {
-                let existing_items: Vec<i32> = get_collection_new_for_page(&client, member_id, page_id).await.unwrap_or(Vec::new());
-                let mut combined: HashSet<i32> = HashSet::from_iter(existing_items);
-                combined.extend(&item_ids);
-                let combined_vec: Vec<i32> = Vec::from_iter(combined);
-                client.execute(&update_new_items_stmt, &[&member_id, &page_id, &combined_vec, &group_id]).await.map_err(ApiError::UpdateGroupMemberError)?;
-            }
+        // Combine the existing items with the new items
+        for (page_id, item_ids) in page_ids_to_item_ids {
+            let existing_items: Vec<i32> = get_collection_new_for_page(client, member_id, page_id).await.unwrap_or_default();
+            let mut combined: HashSet<i32> = HashSet::from_iter(existing_items);
+            combined.extend(&item_ids);
+            let combined_vec: Vec<i32> = Vec::from_iter(combined);
+            client.execute(&update_new_items_stmt, &[&member_id, &page_id, &combined_vec, &group_id]).await.map_err(ApiError::UpdateGroupMemberError)?;
         }
-        None => ()
     }
 
     Ok(())
@@ -417,49 +402,46 @@ pub async fn deposit_items(
     let opt_bank: Option<Vec<i32>> = row.try_get("bank").ok();
 
     // Merge the deposited items into the bank data
-    match opt_bank {
-        Some(mut bank) => {
-            let mut deposited_map = HashMap::new();
-            for i in (0..deposited.len()).step_by(2) {
-                deposited_map.insert(deposited[i], deposited[i + 1]);
-            }
+    if let Some(mut bank) = opt_bank {
+        let mut deposited_map = HashMap::new();
+        for i in (0..deposited.len()).step_by(2) {
+            deposited_map.insert(deposited[i], deposited[i + 1]);
+        }
 
-            // Add the quantity of a deposited item to an item already in the bank
-            for i in (0..bank.len()).step_by(2) {
-                let item_id = bank[i];
-                if deposited_map.contains_key(&item_id) {
-                    bank[i + 1] += deposited_map.get(&item_id).unwrap_or(&0);
-                    deposited_map.remove(&item_id);
-                }
+        // Add the quantity of a deposited item to an item already in the bank
+        for i in (0..bank.len()).step_by(2) {
+            let item_id = bank[i];
+            if deposited_map.contains_key(&item_id) {
+                bank[i + 1] += deposited_map.get(&item_id).unwrap_or(&0);
+                deposited_map.remove(&item_id);
             }
+        }
 
-            // Add the rest of the deposted items as new items into the bank
-            for id in deposited_map.keys() {
-                if *id == 0 {
-                    continue;
-                }
+        // Add the rest of the deposted items as new items into the bank
+        for id in deposited_map.keys() {
+            if *id == 0 {
+                continue;
+            }
 
-                let quantity = *deposited_map.get(id).unwrap_or(&0);
+            let quantity = *deposited_map.get(id).unwrap_or(&0);
 
-                if quantity > 0 {
-                    bank.push(*id);
-                    bank.push(quantity);
-                }
+            if quantity > 0 {
+                bank.push(*id);
+                bank.push(quantity);
             }
+        }
 
-            let update_bank_stmt = client
-                .prepare_cached(
-                    r#"
+        let update_bank_stmt = client
+            .prepare_cached(
+                r#"
 UPDATE groupironman.members SET bank=$1, bank_last_update=NOW() WHERE group_id=$2 AND member_name=$3
 "#,
-                )
-                .await?;
-            client
-                .execute(&update_bank_stmt, &[&bank, &group_id, &member_name])
-                .await
-                .map_err(ApiError::UpdateGroupMemberError)?;
-        }
-        None => (),
+            )
+            .await?;
+        client
+            .execute(&update_bank_stmt, &[&bank, &group_id, &member_name])
+            .await
+            .map_err(ApiError::UpdateGroupMemberError)?;
     }
 
     Ok(())
@@ -559,6 +541,10 @@ pub enum AggregatePeriod {
     Month,
     Year,
 }
+
+// Buckets start at local hour, day and month boundaries, in the member's time zone or else the group's.
+// Buckets are keyed by the instant they start, so after a time zone change the period in progress
+// gets a second bucket at the new zone's boundary; earlier buckets are left as they were.
 async fn aggregate_skills_for_period(
     transaction: &Transaction<'_>,
     period: AggregatePeriod,
@@ -566,9 +552,11 @@ async fn aggregate_skills_for_period(
 ) -> Result<(), ApiError> {
     let s = format!(
         r#"
-INSERT INTO groupironman.skills_{} (member_id, time, skills)
-SELECT member_id, date_trunc('{}', skills_last_update), skills FROM groupironman.members
-WHERE skills_last_update IS NOT NULL AND skills IS NOT NULL AND skills_last_update >= $1
+INSERT INTO groupironman.skills_{0} (member_id, time, skills)
+SELECT m.member_id, date_trunc('{1}', m.skills_last_update AT TIME ZONE COALESCE(m.time_zone, g.time_zone)) AT TIME ZONE COALESCE(m.time_zone, g.time_zone), m.skills
+FROM groupironman.members m
+INNER JOIN groupironman.groups g ON g.group_id=m.group_id
+WHERE m.skills_last_update IS NOT NULL AND m.skills IS NOT NULL AND m.skills_last_update >= $1
 ON CONFLICT (member_id, time)
 DO UPDATE SET skills=excluded.skills;
 "#,
@@ -778,7 +766,7 @@ WHERE groupironman.collection_log_new.group_id=$1
             page_name: row.try_get("page_name")?,
             completion_counts: row.try_get("counts")?,
             items: row.try_get("items")?,
-            new_items: new_items_lookup.remove(&(member_id, page_id)).unwrap_or(Vec::new())
+            new_items: new_items_lookup.remove(&(member_id, page_id)).unwrap_or_default()
         };
 
         let member_name: String = row.try_get("member_name")?;
@@ -829,7 +817,7 @@ pub async fn has_migration_run(client: &mut Client, name: &str) -> Result<bool,
         .await?
         .try_get(0)?;
 
-    Ok(if count > 0 { true } else { false })
+    Ok(count > 0)
 }
 
 pub async fn commit_migration(transaction: &Transaction<'_>, name: &str) -> Result<(), ApiError> {
@@ -1122,10 +1110,9 @@ ORDER BY GREATEST(
                     let uuid = uuid::Uuid::new_v4().to_hyphenated().to_string();
                     let new_name = &uuid[..uuid.find("-").unwrap()];
                     log::info!("Trying new name '{}'", new_name);
-                    match transaction
-                        .execute("UPDATE groupironman.members SET member_name=$1 WHERE member_id=$2", &[&new_name, &member_id]).await {
-                            Ok(_) => break,
-                            Err(_) => ()
+                    if transaction
+                        .execute("UPDATE groupironman.members SET member_name=$1 WHERE member_id=$2", &[&new_name, &member_id]).await.is_ok() {
+                            break;
                         }
                 }
             }
@@ -1183,6 +1170,177 @@ ON CONFLICT (activity_id) DO NOTHING
         commit_migration(&transaction, "add_custom_schema").await?;
         transaction.commit().await?;
     }
+
+    if !has_migration_run(client, "add_point_ledger").await? {
+        let transaction = client.transaction().await?;
+
+        // Replaces the mutable player_points totals with an append-only ledger
+        transaction.batch_execute(include_str!("sql/point_ledger.sql")).await?;
+
+        commit_migration(&transaction, "add_point_ledger").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "add_group_scoring_rules").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/group_scoring_rules.sql")).await?;
+
+        commit_migration(&transaction, "add_group_scoring_rules").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "add_seasons").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/seasons.sql")).await?;
+
+        commit_migration(&transaction, "add_seasons").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "add_activity_reviews").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/activity_reviews.sql")).await?;
+
+        commit_migration(&transaction, "add_activity_reviews").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "add_point_event_once_index").await? {
+        let transaction = client.transaction().await?;
+
+        // Level and collection log awards can only be earned once per member
+        transaction.execute(
+            r#"
+CREATE UNIQUE INDEX IF NOT EXISTS idx_point_events_once
+ON groupironman.point_events(member_id, source_type, source_id)
+WHERE source_type IN ('skill_level', 'collection_item', 'collection_set')
+"#,
+            &[],
+        ).await?;
+
+        commit_migration(&transaction, "add_point_event_once_index").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "add_plugin_submissions").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/plugin_submissions.sql")).await?;
+
+        commit_migration(&transaction, "add_plugin_submissions").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "add_group_activity_catalog").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/group_activity_catalog.sql")).await?;
+
+        commit_migration(&transaction, "add_group_activity_catalog").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "slayer_tasks_timestamptz").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/slayer_analytics.sql")).await?;
+
+        commit_migration(&transaction, "slayer_tasks_timestamptz").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "slayer_preferences").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/slayer_preferences.sql")).await?;
+
+        commit_migration(&transaction, "slayer_preferences").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "slayer_task_progress").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/slayer_task_progress.sql")).await?;
+
+        commit_migration(&transaction, "slayer_task_progress").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "slayer_point_ledger").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/slayer_point_ledger.sql")).await?;
+
+        commit_migration(&transaction, "slayer_point_ledger").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "valuable_drop_values").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/valuable_drop_values.sql")).await?;
+
+        commit_migration(&transaction, "valuable_drop_values").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "valuable_drop_keyset").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/valuable_drop_keyset.sql")).await?;
+
+        commit_migration(&transaction, "valuable_drop_keyset").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "attachments").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/attachments.sql")).await?;
+
+        commit_migration(&transaction, "attachments").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "drop_duplicates").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/drop_duplicates.sql")).await?;
+
+        commit_migration(&transaction, "drop_duplicates").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "calendar_feed_tokens").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/calendar_feed_tokens.sql")).await?;
+
+        commit_migration(&transaction, "calendar_feed_tokens").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "time_zones").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/time_zones.sql")).await?;
+
+        commit_migration(&transaction, "time_zones").await?;
+        transaction.commit().await?;
+    }
+
+    if !has_migration_run(client, "season_rollover_anchor").await? {
+        let transaction = client.transaction().await?;
+
+        transaction.batch_execute(include_str!("sql/season_rollover_anchor.sql")).await?;
+
+        commit_migration(&transaction, "season_rollover_anchor").await?;
+        transaction.commit().await?;
+    }
     
     Ok(())
 }
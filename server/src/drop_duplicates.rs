@@ -0,0 +1,428 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::error::ApiError;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use deadpool_postgres::{GenericClient, Pool};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod drop_duplicate_tests {
+    use super::*;
+
+    #[test]
+    fn sources_match_loosely() {
+        assert!(sources_match("Vorkath", "vorkath"));
+        assert!(sources_match("Chambers of Xeric", "Chambers of Xeric (CM)"));
+        assert!(sources_match("TzKal-Zuk", "tzkal zuk"));
+        assert!(sources_match("", "Zulrah"));
+        assert!(!sources_match("Zulrah", "Vorkath"));
+    }
+
+    #[test]
+    fn match_strength_depends_on_quantity() {
+        assert_eq!(match_strength(1, "Vorkath", 1, "Vorkath"), Some(MatchStrength::Likely));
+        assert_eq!(match_strength(2, "Vorkath", 1, "Vorkath"), Some(MatchStrength::Possible));
+        assert_eq!(match_strength(1, "Zulrah", 1, "Vorkath"), None);
+    }
+
+    #[test]
+    fn plugin_drops_are_kept_over_manual_ones() {
+        assert_eq!(preferred_drop((5, Some(DropEntrySource::Manual)), (9, Some(DropEntrySource::Plugin))), 9);
+        assert_eq!(preferred_drop((5, None), (9, Some(DropEntrySource::Manual))), 5);
+        assert_eq!(preferred_drop((9, Some(DropEntrySource::Plugin)), (5, Some(DropEntrySource::Plugin))), 5);
+    }
+}
+
+/// Drops of the same item this close in time may be the same drop
+pub const DUPLICATE_WINDOW_MINUTES: i64 = 30;
+
+/// Most pairs returned by the suspected duplicates list
+const MAX_SUSPECTED_DUPLICATES: i64 = 200;
+
+/// How a drop was recorded
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DropEntrySource {
+    Plugin,
+    /// Entered by hand on the site
+    Manual,
+    /// Imported from the loot tracker
+    Import,
+}
+
+impl DropEntrySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DropEntrySource::Plugin => "plugin",
+            DropEntrySource::Manual => "manual",
+            DropEntrySource::Import => "import",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "plugin" => Some(DropEntrySource::Plugin),
+            "manual" => Some(DropEntrySource::Manual),
+            "import" => Some(DropEntrySource::Import),
+            _ => None,
+        }
+    }
+}
+
+/// How sure we are that two drops are the same drop
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchStrength {
+    /// Same quantity and source; merged automatically when a new drop is recorded
+    Likely,
+    /// Same source but a different quantity
+    Possible,
+}
+
+fn normalize_source(source: &str) -> String {
+    source
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether two source names could describe the same kill. Hand-entered sources
+/// are often shortened or have extra detail, so one containing the other counts,
+/// as does a missing source.
+pub fn sources_match(a: &str, b: &str) -> bool {
+    let (a, b) = (normalize_source(a), normalize_source(b));
+    a.is_empty() || b.is_empty() || a.contains(&b) || b.contains(&a)
+}
+
+/// How alike two drops of the same item by the same member, close in time, are
+pub fn match_strength(a_quantity: i32, a_source: &str, b_quantity: i32, b_source: &str) -> Option<MatchStrength> {
+    if !sources_match(a_source, b_source) {
+        None
+    } else if a_quantity == b_quantity {
+        Some(MatchStrength::Likely)
+    } else {
+        Some(MatchStrength::Possible)
+    }
+}
+
+/// Which of two drops to keep when they're merged: plugin drops have exact
+/// timestamps and locations, so they win over imported, unknown and manual ones.
+/// Otherwise the older record is kept.
+pub fn preferred_drop(a: (i64, Option<DropEntrySource>), b: (i64, Option<DropEntrySource>)) -> i64 {
+    let rank = |source: Option<DropEntrySource>| match source {
+        Some(DropEntrySource::Plugin) => 0,
+        Some(DropEntrySource::Import) => 1,
+        None => 2,
+        Some(DropEntrySource::Manual) => 3,
+    };
+    if (rank(a.1), a.0) <= (rank(b.1), b.0) {
+        a.0
+    } else {
+        b.0
+    }
+}
+
+/// Find a drop recorded the other way (by hand for plugin and imported drops, and
+/// the reverse for manual ones) that is likely the same as the given drop
+#[allow(clippy::too_many_arguments)]
+pub async fn find_likely_duplicate<C: GenericClient>(
+    client: &C,
+    member_id: i64,
+    item_id: i32,
+    quantity: i32,
+    source_name: &str,
+    timestamp: DateTime<Utc>,
+    entry_source: DropEntrySource,
+) -> Result<Option<i64>, ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "SELECT drop_id, COALESCE(item_quantity, 1), COALESCE(source_name, '')
+             FROM groupironman.valuable_drops
+             WHERE member_id = $1 AND item_id = $2
+             AND timestamp BETWEEN $3 AND $4
+             AND entry_source IS NOT NULL
+             AND (entry_source = 'manual') <> ($5 = 'manual')
+             ORDER BY ABS(EXTRACT(EPOCH FROM timestamp - $6)), drop_id"
+        )
+        .await?;
+    let window = Duration::minutes(DUPLICATE_WINDOW_MINUTES);
+    let rows = client
+        .query(
+            &stmt,
+            &[
+                &member_id,
+                &item_id,
+                &(timestamp - window),
+                &(timestamp + window),
+                &entry_source.as_str(),
+                &timestamp,
+            ],
+        )
+        .await?;
+
+    for row in &rows {
+        let other_quantity: i32 = row.try_get(1)?;
+        let other_source: String = row.try_get(2)?;
+        if match_strength(quantity, source_name, other_quantity, &other_source) == Some(MatchStrength::Likely) {
+            return Ok(Some(row.try_get(0)?));
+        }
+    }
+    Ok(None)
+}
+
+/// Merge one drop into another
+///
+/// The kept drop takes any location or source the other drop had that it's
+/// missing, and the other drop's attachments. Points already awarded for either
+/// drop are left as they are.
+pub async fn merge_drops<C: GenericClient>(client: &C, keep_drop_id: i64, remove_drop_id: i64) -> Result<(), ApiError> {
+    let fill_stmt = client
+        .prepare_cached(
+            "UPDATE groupironman.valuable_drops k
+             SET x_coord = COALESCE(k.x_coord, r.x_coord),
+                 y_coord = COALESCE(k.y_coord, r.y_coord),
+                 z_coord = COALESCE(k.z_coord, r.z_coord),
+                 source_name = COALESCE(NULLIF(k.source_name, ''), r.source_name),
+                 reported_value = COALESCE(k.reported_value, r.reported_value)
+             FROM groupironman.valuable_drops r
+             WHERE k.drop_id = $1 AND r.drop_id = $2"
+        )
+        .await?;
+    client.execute(&fill_stmt, &[&keep_drop_id, &remove_drop_id]).await?;
+
+    let attachments_stmt = client
+        .prepare_cached("UPDATE groupironman.attachments SET drop_id = $1 WHERE drop_id = $2")
+        .await?;
+    client.execute(&attachments_stmt, &[&keep_drop_id, &remove_drop_id]).await?;
+
+    let delete_stmt = client
+        .prepare_cached("DELETE FROM groupironman.valuable_drops WHERE drop_id = $1")
+        .await?;
+    client.execute(&delete_stmt, &[&remove_drop_id]).await?;
+
+    Ok(())
+}
+
+/// Query parameters for the suspected duplicates list
+#[derive(Deserialize)]
+pub struct SuspectedDuplicateParams {
+    /// How far apart two drops can be, in minutes
+    pub window_minutes: Option<i64>,
+}
+
+/// One drop of a suspected duplicate pair
+#[derive(Serialize)]
+pub struct DuplicateDrop {
+    pub drop_id: i64,
+    pub entry_source: Option<DropEntrySource>,
+    pub item_quantity: i32,
+    pub item_value: i64,
+    pub source_name: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Two drops that may be the same drop recorded twice
+#[derive(Serialize)]
+pub struct SuspectedDuplicate {
+    pub member_name: String,
+    pub item_id: i32,
+    pub item_name: String,
+    pub drops: [DuplicateDrop; 2],
+    pub strength: MatchStrength,
+    pub seconds_apart: i64,
+
+    /// The drop a merge keeps unless told otherwise
+    pub suggested_keep_drop_id: i64,
+}
+
+/// List the group's drops that look like the same drop recorded twice
+///
+/// Pairs are drops of the same item by the same member within the window, with
+/// matching sources, where at least one was entered by hand (or predates entry
+/// sources being recorded). Dismissed pairs are left out.
+pub async fn get_suspected_duplicates(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    params: web::Query<SuspectedDuplicateParams>,
+) -> Result<HttpResponse, ApiError> {
+    let window_minutes = params
+        .window_minutes
+        .unwrap_or(DUPLICATE_WINDOW_MINUTES)
+        .clamp(1, 7 * 24 * 60) as i32;
+
+    let client = pool.get().await?;
+    let stmt = client
+        .prepare_cached(
+            "SELECT m.member_name, a.item_id, a.item_name,
+                a.drop_id, a.entry_source, COALESCE(a.item_quantity, 1), a.item_value, COALESCE(a.source_name, ''), a.timestamp,
+                b.drop_id, b.entry_source, COALESCE(b.item_quantity, 1), b.item_value, COALESCE(b.source_name, ''), b.timestamp
+             FROM groupironman.valuable_drops a
+             JOIN groupironman.members m ON m.member_id = a.member_id
+             JOIN groupironman.valuable_drops b ON b.member_id = a.member_id AND b.item_id = a.item_id
+                AND b.drop_id > a.drop_id
+                AND b.timestamp BETWEEN a.timestamp - make_interval(mins => $2) AND a.timestamp + make_interval(mins => $2)
+             WHERE m.group_id = $1
+             AND (a.entry_source IS DISTINCT FROM 'plugin' AND a.entry_source IS DISTINCT FROM 'import'
+                  OR b.entry_source IS DISTINCT FROM 'plugin' AND b.entry_source IS DISTINCT FROM 'import')
+             AND NOT EXISTS (
+                SELECT 1 FROM groupironman.valuable_drop_duplicate_dismissals x
+                WHERE x.drop_id = a.drop_id AND x.other_drop_id = b.drop_id
+             )
+             ORDER BY a.timestamp DESC, a.drop_id DESC
+             LIMIT $3"
+        )
+        .await?;
+    let rows = client
+        .query(&stmt, &[&auth.group_id, &window_minutes, &MAX_SUSPECTED_DUPLICATES])
+        .await?;
+
+    let mut duplicates = Vec::new();
+    for row in &rows {
+        let pair_drop = |offset: usize| -> Result<DuplicateDrop, tokio_postgres::Error> {
+            Ok(DuplicateDrop {
+                drop_id: row.try_get(offset)?,
+                entry_source: row
+                    .try_get::<_, Option<&str>>(offset + 1)?
+                    .and_then(DropEntrySource::parse),
+                item_quantity: row.try_get(offset + 2)?,
+                item_value: row.try_get(offset + 3)?,
+                source_name: row.try_get(offset + 4)?,
+                timestamp: row.try_get(offset + 5)?,
+            })
+        };
+        let (a, b) = (pair_drop(3)?, pair_drop(9)?);
+
+        let strength = match match_strength(a.item_quantity, &a.source_name, b.item_quantity, &b.source_name) {
+            Some(strength) => strength,
+            None => continue,
+        };
+        duplicates.push(SuspectedDuplicate {
+            member_name: row.try_get(0)?,
+            item_id: row.try_get(1)?,
+            item_name: row.try_get(2)?,
+            strength,
+            seconds_apart: (b.timestamp - a.timestamp).num_seconds().abs(),
+            suggested_keep_drop_id: preferred_drop((a.drop_id, a.entry_source), (b.drop_id, b.entry_source)),
+            drops: [a, b],
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(duplicates))
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateResolution {
+    /// Merge the two drops into one
+    Merge,
+    /// They're different drops; stop listing the pair
+    Dismiss,
+}
+
+/// Request to resolve a suspected duplicate
+#[derive(Deserialize)]
+pub struct ResolveDuplicateRequest {
+    pub drop_id: i64,
+    pub other_drop_id: i64,
+    pub action: DuplicateResolution,
+
+    /// Drop to keep when merging; defaults to the suggested one
+    pub keep_drop_id: Option<i64>,
+}
+
+/// Merge or dismiss a pair of suspected duplicate drops
+pub async fn resolve_duplicate(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    request: web::Json<ResolveDuplicateRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let bad_request = |message: &str| {
+        HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": message
+        }))
+    };
+
+    if request.drop_id == request.other_drop_id {
+        return Ok(bad_request("A drop can't be a duplicate of itself"));
+    }
+    if let Some(keep_drop_id) = request.keep_drop_id {
+        if keep_drop_id != request.drop_id && keep_drop_id != request.other_drop_id {
+            return Ok(bad_request("keep_drop_id must be one of the two drops"));
+        }
+    }
+
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    let stmt = transaction
+        .prepare_cached(
+            "SELECT d.drop_id, d.entry_source, d.member_id, d.item_id
+             FROM groupironman.valuable_drops d
+             JOIN groupironman.members m ON m.member_id = d.member_id
+             WHERE d.drop_id = ANY($1) AND m.group_id = $2
+             ORDER BY d.drop_id
+             FOR UPDATE OF d"
+        )
+        .await?;
+    let rows = transaction
+        .query(&stmt, &[&vec![request.drop_id, request.other_drop_id], &auth.group_id])
+        .await?;
+    if rows.len() != 2 {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "Valuable drop not found or not in your group"
+        })));
+    }
+
+    let drops = rows
+        .iter()
+        .map(|row| -> Result<_, tokio_postgres::Error> {
+            Ok((
+                row.try_get::<_, i64>(0)?,
+                row.try_get::<_, Option<&str>>(1)?.and_then(DropEntrySource::parse),
+                row.try_get::<_, i64>(2)?,
+                row.try_get::<_, i32>(3)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let (first, second) = (drops[0], drops[1]);
+
+    match request.action {
+        DuplicateResolution::Dismiss => {
+            let dismiss_stmt = transaction
+                .prepare_cached(
+                    "INSERT INTO groupironman.valuable_drop_duplicate_dismissals (drop_id, other_drop_id)
+                     VALUES ($1, $2)
+                     ON CONFLICT DO NOTHING"
+                )
+                .await?;
+            transaction.execute(&dismiss_stmt, &[&first.0, &second.0]).await?;
+            transaction.commit().await?;
+
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "status": "success",
+                "message": "Drops marked as not duplicates"
+            })))
+        }
+        DuplicateResolution::Merge => {
+            if first.2 != second.2 || first.3 != second.3 {
+                return Ok(bad_request("Only drops of the same item by the same member can be merged"));
+            }
+
+            let keep_drop_id = request
+                .keep_drop_id
+                .unwrap_or_else(|| preferred_drop((first.0, first.1), (second.0, second.1)));
+            let remove_drop_id = if keep_drop_id == first.0 { second.0 } else { first.0 };
+            merge_drops(&transaction, keep_drop_id, remove_drop_id).await?;
+            transaction.commit().await?;
+
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "status": "success",
+                "message": "Drops merged",
+                "kept_drop_id": keep_drop_id,
+                "removed_drop_id": remove_drop_id
+            })))
+        }
+    }
+}
@@ -3,6 +3,7 @@ use deadpool_postgres::PoolError;
 use derive_more::{Display, From};
 
 #[derive(Debug, Display, From)]
+#[allow(clippy::enum_variant_names)]
 pub enum ApiError {
     PoolError(PoolError),
     PGError(tokio_postgres::error::Error),
@@ -27,8 +28,11 @@ pub enum ApiError {
     GetSkillsDataError(tokio_postgres::error::Error),
     #[from(ignore)]
     GetCollectionLogError(tokio_postgres::error::Error),
+    #[from(ignore)]
+    GetLastSkillsAggregationError(tokio_postgres::error::Error),
     GroupFullError,
     ReqwestError(reqwest::Error),
+    BlobStoreError(std::io::Error),
     GroupMemberValidationError(String)
 }
 impl std::error::Error for ApiError {}
@@ -58,6 +62,9 @@ impl ResponseError for ApiError {
             ApiError::IsMemberInGroupError(ref err) => handle_pg_error(err, "IsMemberInGroupError"),
             ApiError::GetSkillsDataError(ref err) => handle_pg_error(err, "GetSkillsDataError"),
             ApiError::GetCollectionLogError(ref err) => handle_pg_error(err, "GetCollectionLogError"),
+            ApiError::GetLastSkillsAggregationError(ref err) => {
+                handle_pg_error(err, "GetLastSkillsAggregationError")
+            }
             ApiError::DeleteGroupMemberError(ref err) => {
                 handle_pg_error(err, "DeleteGroupMemberError")
             }
@@ -74,6 +81,10 @@ impl ResponseError for ApiError {
                 log::error!("ReqwestError: {}", err);
                 HttpResponse::InternalServerError().body(format!("ReqwestError: {}", err))
             },
+            ApiError::BlobStoreError(ref err) => {
+                log::error!("BlobStoreError: {}", err);
+                HttpResponse::InternalServerError().finish()
+            }
             ApiError::GroupMemberValidationError(ref reason) => {
                 log::error!("Validation error: {}", reason);
                 HttpResponse::BadRequest().body(reason.clone())
@@ -0,0 +1,184 @@
+use crate::error::ApiError;
+use actix_web::{web::Bytes, HttpResponse};
+use deadpool_postgres::Client;
+use futures::{stream, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Row, RowStream};
+
+#[cfg(test)]
+mod export_tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field(&Value::Null), "");
+        assert_eq!(csv_field(&serde_json::json!(-5)), "-5");
+        assert_eq!(csv_field(&serde_json::json!(true)), "true");
+        assert_eq!(csv_field(&serde_json::json!("Abyssal whip")), "Abyssal whip");
+        assert_eq!(csv_field(&serde_json::json!("Dagannoth, Rex")), "\"Dagannoth, Rex\"");
+        assert_eq!(csv_field(&serde_json::json!("5\" ring")), "\"5\"\" ring\"");
+    }
+
+    #[test]
+    fn csv_fields_cannot_start_formulas() {
+        assert_eq!(csv_field(&serde_json::json!("=HYPERLINK()")), "'=HYPERLINK()");
+        assert_eq!(csv_field(&serde_json::json!("-1+2")), "'-1+2");
+    }
+
+    #[test]
+    fn lines_match_the_format() {
+        let values = vec![serde_json::json!(1), serde_json::json!("Zulrah")];
+        assert_eq!(format_line(ExportFormat::Csv, &["id", "source"], &values), "1,Zulrah\n");
+        assert_eq!(
+            format_line(ExportFormat::Ndjson, &["id", "source"], &values),
+            "{\"id\":1,\"source\":\"Zulrah\"}\n"
+        );
+    }
+}
+
+/// Rows written to the response at a time
+const EXPORT_CHUNK_ROWS: usize = 500;
+
+/// File format for exports
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// Newline-delimited JSON, one object per row
+    Ndjson,
+}
+
+impl ExportFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// Query parameters shared by the export endpoints
+#[derive(Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// A CSV field, quoted when needed. Text that a spreadsheet would read as a
+/// formula is prefixed with an apostrophe.
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(text) => text.clone(),
+        other => return other.to_string(),
+    };
+
+    let text = if text.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", text)
+    } else {
+        text
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// One row of the export, including the trailing newline
+fn format_line(format: ExportFormat, columns: &[&str], values: &[Value]) -> String {
+    match format {
+        ExportFormat::Csv => {
+            let fields: Vec<String> = values.iter().map(csv_field).collect();
+            format!("{}\n", fields.join(","))
+        }
+        ExportFormat::Ndjson => {
+            let object: serde_json::Map<String, Value> = columns
+                .iter()
+                .zip(values)
+                .map(|(column, value)| (column.to_string(), value.clone()))
+                .collect();
+            format!("{}\n", Value::Object(object))
+        }
+    }
+}
+
+struct ExportState {
+    // Held so the connection isn't returned to the pool while rows are streamed
+    _client: Client,
+    rows: std::pin::Pin<Box<RowStream>>,
+    finished: bool,
+}
+
+/// Stream the results of a query as a CSV or NDJSON download
+///
+/// Rows are read from the database as the response is written, so memory use
+/// doesn't grow with the number of rows. `to_values` must return one value per column.
+pub async fn stream_export(
+    client: Client,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+    format: ExportFormat,
+    name: &str,
+    columns: &'static [&'static str],
+    to_values: fn(&Row) -> Result<Vec<Value>, tokio_postgres::Error>,
+) -> Result<HttpResponse, ApiError> {
+    let stmt = client.prepare(query).await?;
+    let rows = client.query_raw(&stmt, params.iter().copied()).await?;
+
+    let header = match format {
+        ExportFormat::Csv => format!("{}\n", columns.join(",")),
+        ExportFormat::Ndjson => String::new(),
+    };
+    let state = ExportState {
+        _client: client,
+        rows: Box::pin(rows),
+        finished: false,
+    };
+
+    let body = stream::unfold(state, move |mut state| async move {
+        if state.finished {
+            return None;
+        }
+
+        let mut chunk = String::new();
+        for _ in 0..EXPORT_CHUNK_ROWS {
+            match state.rows.next().await {
+                Some(Ok(row)) => match to_values(&row) {
+                    Ok(values) => chunk.push_str(&format_line(format, columns, &values)),
+                    Err(err) => {
+                        state.finished = true;
+                        return Some((Err(err), state));
+                    }
+                },
+                Some(Err(err)) => {
+                    state.finished = true;
+                    return Some((Err(err), state));
+                }
+                None => {
+                    state.finished = true;
+                    break;
+                }
+            }
+        }
+        Some((Ok::<_, tokio_postgres::Error>(Bytes::from(chunk)), state))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .append_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.{}\"", name, format.extension()),
+        ))
+        .streaming(stream::once(async move { Ok(Bytes::from(header)) }).chain(body)))
+}
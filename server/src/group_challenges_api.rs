@@ -1,8 +1,131 @@
-use actix_web::{web, HttpResponse, Responder};
-use deadpool_postgres::{Client, Pool};
+use crate::auth_middleware::AuthedGroupId;
+use crate::error::ApiError;
+use actix_web::{web, HttpResponse};
+use deadpool_postgres::{Client, Pool, Transaction};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use std::error::Error;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::{task, time};
+use tokio_postgres::Row;
+
+#[cfg(test)]
+mod challenge_status_tests {
+    use super::*;
+
+    #[test]
+    fn allowed_transitions() {
+        use ChallengeStatus::*;
+        let allowed = [
+            (Draft, Scheduled),
+            (Draft, Active),
+            (Scheduled, Draft),
+            (Scheduled, Active),
+            (Active, Ended),
+            (Active, Completed),
+            (Active, Failed),
+            (Ended, Completed),
+            (Ended, Failed),
+        ];
+    
+        for (from, to) in allowed {
+            assert!(from.can_transition_to(to), "{:?} -> {:?} should be allowed", from, to);
+        }
+    }
+
+    #[test]
+    fn rejected_transitions() {
+        use ChallengeStatus::*;
+        let rejected = [
+            (Draft, Ended),
+            (Draft, Completed),
+            (Scheduled, Completed),
+            (Active, Draft),
+            (Active, Scheduled),
+            (Ended, Active),
+            (Completed, Active),
+            (Completed, Failed),
+            (Failed, Completed),
+            (Active, Active),
+        ];
+    
+        for (from, to) in rejected {
+            assert!(!from.can_transition_to(to), "{:?} -> {:?} should be rejected", from, to);
+        }
+    }
+
+    #[test]
+    fn xp_gained_ignores_decreases() {
+        assert_eq!(xp_gained(&[100, 200, 300], &[150, 200, 250]), 50);
+        assert_eq!(xp_gained(&[], &[10, 20]), 0);
+    }
+}
+
+/// Lifecycle state of a group challenge
+///
+/// draft -> scheduled -> active -> ended -> completed / failed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChallengeStatus {
+    #[default]
+    Draft,
+    Scheduled,
+    Active,
+    Ended,
+    Completed,
+    Failed,
+}
+
+impl ChallengeStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChallengeStatus::Draft => "draft",
+            ChallengeStatus::Scheduled => "scheduled",
+            ChallengeStatus::Active => "active",
+            ChallengeStatus::Ended => "ended",
+            ChallengeStatus::Completed => "completed",
+            ChallengeStatus::Failed => "failed",
+        }
+    }
+    
+    pub fn can_transition_to(&self, next: ChallengeStatus) -> bool {
+        use ChallengeStatus::*;
+        matches!(
+            (self, next),
+            (Draft, Scheduled)
+                | (Draft, Active)
+                | (Scheduled, Draft)
+                | (Scheduled, Active)
+                | (Active, Ended)
+                | (Active, Completed)
+                | (Active, Failed)
+                | (Ended, Completed)
+                | (Ended, Failed)
+        )
+    }
+    
+    /// Only challenges that haven't started yet can have their definition changed
+    pub fn is_editable(&self) -> bool {
+        matches!(self, ChallengeStatus::Draft | ChallengeStatus::Scheduled)
+    }
+}
+
+impl FromStr for ChallengeStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        match status {
+            "draft" => Ok(ChallengeStatus::Draft),
+            "scheduled" => Ok(ChallengeStatus::Scheduled),
+            "active" => Ok(ChallengeStatus::Active),
+            "ended" => Ok(ChallengeStatus::Ended),
+            "completed" => Ok(ChallengeStatus::Completed),
+            "failed" => Ok(ChallengeStatus::Failed),
+            _ => Err(format!("Unknown challenge status '{}'", status)),
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GroupChallenge {
@@ -20,22 +143,51 @@ pub struct GroupChallenge {
     pub end_date: Option<DateTime<Utc>>,
     pub completed: bool,
     pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub status: ChallengeStatus,
+    #[serde(default, skip_deserializing)]
+    pub activated_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_deserializing)]
+    pub ended_at: Option<DateTime<Utc>>,
 }
 
-// Get all challenges for a group
-pub async fn get_challenges(
-    pool: web::Data<Pool>,
-    path: web::Path<(i64,)>,
-) -> Result<HttpResponse, Box<dyn Error>> {
-    let client = pool.get().await?;
-    let (group_id,) = path.into_inner();
-    
-    let rows = client.query(
-        "SELECT id, group_id, title, description, challenge_type, target_value, current_value, reward, created_by, created_at, start_date, end_date, completed, completed_at FROM group_challenges WHERE group_id = $1 ORDER BY created_at DESC",
-        &[&group_id]
-    ).await?;
-    
-    let challenges: Vec<GroupChallenge> = rows.iter().map(|row| GroupChallenge {
+#[derive(Deserialize)]
+pub struct ChallengeStatusUpdate {
+    pub status: ChallengeStatus,
+}
+
+#[derive(Serialize)]
+pub struct MemberChallengeProgress {
+    pub member_name: String,
+    pub xp_gained: i64,
+    pub points_gained: i64,
+}
+
+#[derive(Serialize)]
+pub struct ChallengeProgress {
+    pub challenge_id: i64,
+    pub status: ChallengeStatus,
+    pub activated_at: Option<DateTime<Utc>>,
+    pub total_xp_gained: i64,
+    pub total_points_gained: i64,
+    pub members: Vec<MemberChallengeProgress>,
+}
+
+/// Result of attempting to move a challenge to a new status
+pub enum TransitionOutcome {
+    Applied,
+    NotFound,
+    Rejected(String),
+}
+
+const CHALLENGE_COLUMNS: &str = "id, group_id, title, description, challenge_type, target_value, current_value, reward, created_by, created_at, start_date, end_date, completed, completed_at, status, activated_at, ended_at";
+
+// Total points for a member, used when snapshotting challenge baselines
+const MEMBER_POINTS_TOTAL: &str = "COALESCE((SELECT SUM(pp.points) FROM groupironman.player_points pp WHERE pp.member_id = m.member_id), 0)::BIGINT";
+
+fn challenge_from_row(row: &Row) -> GroupChallenge {
+    let status: String = row.get(14);
+    GroupChallenge {
         id: row.get(0),
         group_id: row.get(1),
         title: row.get(2),
@@ -50,7 +202,47 @@ pub async fn get_challenges(
         end_date: row.get(11),
         completed: row.get(12),
         completed_at: row.get(13),
-    }).collect();
+        status: ChallengeStatus::from_str(&status).unwrap_or_default(),
+        activated_at: row.get(15),
+        ended_at: row.get(16),
+    }
+}
+
+fn validate_dates(challenge: &GroupChallenge) -> Option<&'static str> {
+    if let (Some(start_date), Some(end_date)) = (challenge.start_date, challenge.end_date) {
+        if end_date <= start_date {
+            return Some("end_date must be after start_date");
+        }
+    }
+    if challenge.status == ChallengeStatus::Scheduled && challenge.start_date.is_none() {
+        return Some("Scheduled challenges require a start_date");
+    }
+    None
+}
+
+/// XP gained across all skills between two snapshots of a member's skills array
+fn xp_gained(baseline: &[i32], current: &[i32]) -> i64 {
+    baseline
+        .iter()
+        .zip(current.iter())
+        .map(|(before, after)| (*after as i64 - *before as i64).max(0))
+        .sum()
+}
+
+// Get all challenges for a group
+pub async fn get_challenges(
+    pool: web::Data<Pool>,
+    path: web::Path<(i64,)>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let client = pool.get().await?;
+    let (group_id,) = path.into_inner();
+    
+    let rows = client.query(
+        &format!("SELECT {} FROM group_challenges WHERE group_id = $1 ORDER BY created_at DESC", CHALLENGE_COLUMNS),
+        &[&group_id]
+    ).await?;
+    
+    let challenges: Vec<GroupChallenge> = rows.iter().map(challenge_from_row).collect();
     
     Ok(HttpResponse::Ok().json(challenges))
 }
@@ -64,26 +256,11 @@ pub async fn get_challenge(
     let (group_id, challenge_id) = path.into_inner();
     
     let row = client.query_one(
-        "SELECT id, group_id, title, description, challenge_type, target_value, current_value, reward, created_by, created_at, start_date, end_date, completed, completed_at FROM group_challenges WHERE id = $1 AND group_id = $2",
+        &format!("SELECT {} FROM group_challenges WHERE id = $1 AND group_id = $2", CHALLENGE_COLUMNS),
         &[&challenge_id, &group_id]
     ).await?;
     
-    let challenge = GroupChallenge {
-        id: row.get(0),
-        group_id: row.get(1),
-        title: row.get(2),
-        description: row.get(3),
-        challenge_type: row.get(4),
-        target_value: row.get(5),
-        current_value: row.get(6),
-        reward: row.get(7),
-        created_by: row.get(8),
-        created_at: row.get(9),
-        start_date: row.get(10),
-        end_date: row.get(11),
-        completed: row.get(12),
-        completed_at: row.get(13),
-    };
+    let challenge = challenge_from_row(&row);
     
     Ok(HttpResponse::Ok().json(challenge))
 }
@@ -97,19 +274,28 @@ pub async fn create_challenge(
     let client = pool.get().await?;
     let (group_id,) = path.into_inner();
     
+    // New challenges start out as drafts or scheduled; the scheduler activates them
+    if !challenge.status.is_editable() {
+        return Ok(HttpResponse::BadRequest().body("New challenges must be draft or scheduled"));
+    }
+    if let Some(reason) = validate_dates(&challenge) {
+        return Ok(HttpResponse::BadRequest().body(reason));
+    }
+    
     let row = client.query_one(
-        "INSERT INTO group_challenges (group_id, title, description, challenge_type, target_value, current_value, reward, created_by, start_date, end_date) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id",
+        "INSERT INTO group_challenges (group_id, title, description, challenge_type, target_value, current_value, reward, created_by, start_date, end_date, status) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING id",
         &[
-            &group_id, 
-            &challenge.title, 
-            &challenge.description, 
-            &challenge.challenge_type, 
-            &challenge.target_value, 
-            &challenge.current_value, 
-            &challenge.reward, 
+            &group_id,
+            &challenge.title,
+            &challenge.description,
+            &challenge.challenge_type,
+            &challenge.target_value,
+            &challenge.current_value,
+            &challenge.reward,
             &challenge.created_by,
             &challenge.start_date,
-            &challenge.end_date
+            &challenge.end_date,
+            &challenge.status.as_str()
         ]
     ).await?;
     
@@ -127,14 +313,34 @@ pub async fn update_challenge(
     let client = pool.get().await?;
     let (group_id, challenge_id) = path.into_inner();
     
+    let row = client.query_opt(
+        "SELECT status FROM group_challenges WHERE id = $1 AND group_id = $2",
+        &[&challenge_id, &group_id]
+    ).await?;
+    let status = match row {
+        Some(row) => ChallengeStatus::from_str(row.get(0)).unwrap_or_default(),
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+    
+    // Status changes go through the status endpoint so the lifecycle is enforced
+    if !status.is_editable() {
+        return Ok(HttpResponse::Conflict().body(format!("Challenge is {} and can no longer be edited", status.as_str())));
+    }
+    if status == ChallengeStatus::Scheduled && challenge.start_date.is_none() {
+        return Ok(HttpResponse::BadRequest().body("Scheduled challenges require a start_date"));
+    }
+    if let Some(reason) = validate_dates(&challenge) {
+        return Ok(HttpResponse::BadRequest().body(reason));
+    }
+    
     client.execute(
         "UPDATE group_challenges SET title = $1, description = $2, challenge_type = $3, target_value = $4, current_value = $5, reward = $6, start_date = $7, end_date = $8 WHERE id = $9 AND group_id = $10",
         &[
-            &challenge.title, 
-            &challenge.description, 
-            &challenge.challenge_type, 
-            &challenge.target_value, 
-            &challenge.current_value, 
+            &challenge.title,
+            &challenge.description,
+            &challenge.challenge_type,
+            &challenge.target_value,
+            &challenge.current_value,
             &challenge.reward,
             &challenge.start_date,
             &challenge.end_date,
@@ -167,13 +373,237 @@ pub async fn complete_challenge(
     pool: web::Data<Pool>,
     path: web::Path<(i64, i64)>,
 ) -> Result<HttpResponse, Box<dyn Error>> {
-    let client = pool.get().await?;
+    let mut client = pool.get().await?;
     let (group_id, challenge_id) = path.into_inner();
     
-    client.execute(
-        "UPDATE group_challenges SET completed = true, completed_at = NOW() WHERE id = $1 AND group_id = $2",
+    let outcome = transition_challenge(&mut client, group_id, challenge_id, ChallengeStatus::Completed).await?;
+    
+    Ok(transition_response(outcome))
+}
+
+// Move a challenge to another lifecycle state
+pub async fn update_challenge_status(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+    update: web::Json<ChallengeStatusUpdate>,
+) -> Result<HttpResponse, ApiError> {
+    let mut client = pool.get().await?;
+    let (_group_name, challenge_id) = path.into_inner();
+    
+    let outcome = transition_challenge(&mut client, auth.group_id, challenge_id, update.status).await?;
+    
+    Ok(transition_response(outcome))
+}
+
+// Get per-member progress measured from the challenge's activation baseline
+pub async fn get_challenge_progress(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let (_group_name, challenge_id) = path.into_inner();
+    
+    let row = client.query_opt(
+        "SELECT status, activated_at FROM group_challenges WHERE id = $1 AND group_id = $2",
+        &[&challenge_id, &auth.group_id]
+    ).await?;
+    let (status, activated_at) = match row {
+        Some(row) => (ChallengeStatus::from_str(row.get(0)).unwrap_or_default(), row.get(1)),
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+    
+    // Finished challenges are measured against the snapshot taken when they stopped being active
+    let rows = client.query(
+        &format!(
+            "SELECT m.member_name, b.skills, COALESCE(b.final_skills, m.skills), b.points, COALESCE(b.final_points, {})
+             FROM challenge_baselines b
+             JOIN groupironman.members m ON m.member_id = b.member_id
+             WHERE b.challenge_id = $1
+             ORDER BY m.member_name",
+            MEMBER_POINTS_TOTAL
+        ),
+        &[&challenge_id]
+    ).await?;
+    
+    let members: Vec<MemberChallengeProgress> = rows.iter().map(|row| {
+        let baseline_skills: Option<Vec<i32>> = row.get(1);
+        let current_skills: Option<Vec<i32>> = row.get(2);
+        let baseline_points: i64 = row.get(3);
+        let current_points: i64 = row.get(4);
+    
+        MemberChallengeProgress {
+            member_name: row.get(0),
+            xp_gained: xp_gained(
+                &baseline_skills.unwrap_or_default(),
+                &current_skills.unwrap_or_default(),
+            ),
+            points_gained: (current_points - baseline_points).max(0),
+        }
+    }).collect();
+    
+    Ok(HttpResponse::Ok().json(ChallengeProgress {
+        challenge_id,
+        status,
+        activated_at,
+        total_xp_gained: members.iter().map(|m| m.xp_gained).sum(),
+        total_points_gained: members.iter().map(|m| m.points_gained).sum(),
+        members,
+    }))
+}
+
+fn transition_response(outcome: TransitionOutcome) -> HttpResponse {
+    match outcome {
+        TransitionOutcome::Applied => HttpResponse::Ok().finish(),
+        TransitionOutcome::NotFound => HttpResponse::NotFound().finish(),
+        TransitionOutcome::Rejected(reason) => HttpResponse::Conflict().body(reason),
+    }
+}
+
+/// Moves a challenge to `next`, enforcing the lifecycle rules.
+///
+/// Baselines for every group member are captured when the challenge becomes active and
+/// finalized when it stops being active, so progress only counts what happened in between.
+pub async fn transition_challenge(
+    client: &mut Client,
+    group_id: i64,
+    challenge_id: i64,
+    next: ChallengeStatus,
+) -> Result<TransitionOutcome, tokio_postgres::Error> {
+    let transaction = client.transaction().await?;
+    
+    let row = transaction.query_opt(
+        "SELECT status, start_date FROM group_challenges WHERE id = $1 AND group_id = $2 FOR UPDATE",
         &[&challenge_id, &group_id]
     ).await?;
+    let (current, start_date): (ChallengeStatus, Option<DateTime<Utc>>) = match row {
+        Some(row) => (ChallengeStatus::from_str(row.get(0)).unwrap_or_default(), row.get(1)),
+        None => return Ok(TransitionOutcome::NotFound),
+    };
     
-    Ok(HttpResponse::Ok().finish())
+    if !current.can_transition_to(next) {
+        return Ok(TransitionOutcome::Rejected(format!(
+            "Challenge cannot move from {} to {}",
+            current.as_str(),
+            next.as_str()
+        )));
+    }
+    if next == ChallengeStatus::Scheduled && start_date.is_none() {
+        return Ok(TransitionOutcome::Rejected("Scheduled challenges require a start_date".to_string()));
+    }
+    
+    transaction.execute(
+        "UPDATE group_challenges SET
+            status = $1,
+            start_date = CASE WHEN $1 = 'active' THEN COALESCE(start_date, NOW()) ELSE start_date END,
+            activated_at = CASE WHEN $1 = 'active' THEN NOW() ELSE activated_at END,
+            ended_at = CASE WHEN $1 IN ('ended', 'completed', 'failed') THEN COALESCE(ended_at, NOW()) ELSE ended_at END,
+            completed = ($1 = 'completed'),
+            completed_at = CASE WHEN $1 = 'completed' THEN NOW() ELSE NULL END
+         WHERE id = $2",
+        &[&next.as_str(), &challenge_id]
+    ).await?;
+    
+    if next == ChallengeStatus::Active {
+        snapshot_baselines(&transaction, challenge_id, group_id).await?;
+    } else if current == ChallengeStatus::Active {
+        finalize_baselines(&transaction, challenge_id).await?;
+    }
+    
+    transaction.commit().await?;
+    
+    Ok(TransitionOutcome::Applied)
+}
+
+async fn snapshot_baselines(
+    transaction: &Transaction<'_>,
+    challenge_id: i64,
+    group_id: i64,
+) -> Result<(), tokio_postgres::Error> {
+    transaction.execute(
+        &format!(
+            "INSERT INTO challenge_baselines (challenge_id, member_id, skills, points)
+             SELECT $1, m.member_id, m.skills, {}
+             FROM groupironman.members m
+             WHERE m.group_id = $2 AND m.member_name <> '@SHARED'
+             ON CONFLICT (challenge_id, member_id)
+             DO UPDATE SET skills = excluded.skills, points = excluded.points, captured_at = NOW(),
+                           final_skills = NULL, final_points = NULL, finalized_at = NULL",
+            MEMBER_POINTS_TOTAL
+        ),
+        &[&challenge_id, &group_id]
+    ).await?;
+    
+    Ok(())
+}
+
+async fn finalize_baselines(
+    transaction: &Transaction<'_>,
+    challenge_id: i64,
+) -> Result<(), tokio_postgres::Error> {
+    transaction.execute(
+        &format!(
+            "UPDATE challenge_baselines b
+             SET final_skills = m.skills, final_points = {}, finalized_at = NOW()
+             FROM groupironman.members m
+             WHERE b.challenge_id = $1 AND m.member_id = b.member_id AND b.finalized_at IS NULL",
+            MEMBER_POINTS_TOTAL
+        ),
+        &[&challenge_id]
+    ).await?;
+    
+    Ok(())
+}
+
+/// Activates scheduled challenges and ends active challenges once their dates pass
+async fn run_scheduled_transitions(client: &mut Client) -> Result<(), tokio_postgres::Error> {
+    let due_rows = client.query(
+        "SELECT id, group_id, 'active' FROM group_challenges WHERE status = 'scheduled' AND start_date <= NOW()
+         UNION ALL
+         SELECT id, group_id, 'ended' FROM group_challenges WHERE status = 'active' AND end_date IS NOT NULL AND end_date <= NOW()",
+        &[]
+    ).await?;
+    
+    for row in due_rows {
+        let challenge_id: i64 = row.get(0);
+        let group_id: i64 = row.get(1);
+        let next = ChallengeStatus::from_str(row.get(2)).unwrap_or_default();
+    
+        match transition_challenge(client, group_id, challenge_id, next).await? {
+            TransitionOutcome::Applied => {
+                log::info!("Challenge {} is now {}", challenge_id, next.as_str());
+            }
+            TransitionOutcome::NotFound => (),
+            TransitionOutcome::Rejected(reason) => {
+                log::warn!("Skipping scheduled transition for challenge {}: {}", challenge_id, reason);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+pub fn start_challenge_scheduler(db_pool: Pool) {
+    task::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(60));
+    
+        loop {
+            interval.tick().await;
+    
+            match db_pool.get().await {
+                Ok(mut client) => {
+                    match run_scheduled_transitions(&mut client).await {
+                        Ok(_) => (),
+                        Err(err) => {
+                            log::error!("Failed to run challenge transitions: {}", err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    log::error!("Failed to get db client: {}", err);
+                }
+            }
+        }
+    });
 }
@@ -1,7 +1,8 @@
 use crate::auth_middleware::AuthedGroupId;
 use crate::error::ApiError;
-use crate::db::{get_member_id, get_group_members};
-use actix_web::{web, HttpResponse, Scope};
+use crate::db::get_member_id;
+use crate::models::GroupMember;
+use actix_web::{web, HttpResponse};
 use deadpool_postgres::Pool;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
@@ -133,7 +134,7 @@ pub async fn create_milestone(
 pub async fn update_status(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    path: web::Path<i32>,
+    path: web::Path<(String, i32)>,
     status: web::Json<serde_json::Value>,
 ) -> Result<HttpResponse, ApiError> {
     update_milestone_status(pool, auth, path, status).await
@@ -144,7 +145,7 @@ pub async fn update_status(
 pub async fn update_progress(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    path: web::Path<(i32, String)>,
+    path: web::Path<(String, i32, String)>,
     progress: web::Json<serde_json::Value>,
 ) -> Result<HttpResponse, ApiError> {
     update_member_progress(pool, auth, path, progress).await
@@ -155,7 +156,7 @@ pub async fn update_progress(
 pub async fn delete(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    path: web::Path<i32>,
+    path: web::Path<(String, i32)>,
 ) -> Result<HttpResponse, ApiError> {
     delete_milestone(pool, auth, path).await
 }
@@ -188,7 +189,7 @@ pub async fn get_group_milestones(
             m.group_id = $1"
     );
     
-    let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&auth.0];
+    let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&auth.group_id];
     
     // Add filter for milestone type if provided
     if let Some(milestone_type) = &query.milestone_type {
@@ -208,9 +209,6 @@ pub async fn get_group_milestones(
     let stmt = client.prepare(&base_query).await?;
     let rows = client.query(&stmt, &params[..]).await?;
     
-    // Get group members
-    let members = get_group_members(&client, auth.0).await?;
-    
     // Build response
     let mut milestones: Vec<MilestoneWithProgress> = Vec::new();
     
@@ -304,7 +302,7 @@ pub async fn create_group_milestone(
     let row = client.query_one(
         &stmt,
         &[
-            &auth.0,
+            &auth.group_id,
             &milestone_data.title,
             &milestone_data.description,
             &milestone_data.milestone_type,
@@ -328,11 +326,11 @@ pub async fn create_group_milestone(
 pub async fn update_milestone_status(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    path: web::Path<i32>,
+    path: web::Path<(String, i32)>,
     status: web::Json<serde_json::Value>,
 ) -> Result<HttpResponse, ApiError> {
     let client = pool.get().await?;
-    let milestone_id = path.into_inner();
+    let (_group_name, milestone_id) = path.into_inner();
     
     // Check if milestone belongs to the authenticated group
     let check_stmt = client.prepare(
@@ -340,10 +338,10 @@ pub async fn update_milestone_status(
          WHERE milestone_id = $1 AND group_id = $2"
     ).await?;
     
-    let row = client.query_opt(&check_stmt, &[&milestone_id, &auth.0]).await?;
+    let row = client.query_opt(&check_stmt, &[&milestone_id, &auth.group_id]).await?;
     
     if row.is_none() {
-        return Err(ApiError::NotFound("Milestone not found".to_string()));
+        return Ok(HttpResponse::NotFound().body("Milestone not found"));
     }
     
     // Update milestone status
@@ -369,11 +367,11 @@ pub async fn update_milestone_status(
 pub async fn update_member_progress(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    path: web::Path<(i32, String)>,
+    path: web::Path<(String, i32, String)>,
     progress: web::Json<serde_json::Value>,
 ) -> Result<HttpResponse, ApiError> {
     let client = pool.get().await?;
-    let (milestone_id, member_name) = path.into_inner();
+    let (_group_name, milestone_id, member_name) = path.into_inner();
     
     // Check if milestone belongs to the authenticated group
     let check_stmt = client.prepare(
@@ -381,14 +379,14 @@ pub async fn update_member_progress(
          WHERE milestone_id = $1 AND group_id = $2"
     ).await?;
     
-    let row = client.query_opt(&check_stmt, &[&milestone_id, &auth.0]).await?;
+    let row = client.query_opt(&check_stmt, &[&milestone_id, &auth.group_id]).await?;
     
     if row.is_none() {
-        return Err(ApiError::NotFound("Milestone not found".to_string()));
+        return Ok(HttpResponse::NotFound().body("Milestone not found"));
     }
     
     // Get member ID
-    let member_id = get_member_id(&client, auth.0, &member_name).await?;
+    let member_id = get_member_id(&client, auth.group_id, &member_name).await?;
     
     // Extract progress data
     let current_progress = progress.get("current_progress").cloned().unwrap_or(serde_json::json!({}));
@@ -429,7 +427,7 @@ pub async fn update_member_progress(
             AND m.group_id = $2"
     ).await?;
     
-    let completion_row = client.query_one(&check_completion_stmt, &[&milestone_id, &auth.0]).await?;
+    let completion_row = client.query_one(&check_completion_stmt, &[&milestone_id, &auth.group_id]).await?;
     let total_members: i64 = completion_row.get("total_members");
     let completed_members: i64 = completion_row.get("completed_members");
     
@@ -457,10 +455,10 @@ pub async fn update_member_progress(
 pub async fn delete_milestone(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    path: web::Path<i32>,
+    path: web::Path<(String, i32)>,
 ) -> Result<HttpResponse, ApiError> {
     let client = pool.get().await?;
-    let milestone_id = path.into_inner();
+    let (_group_name, milestone_id) = path.into_inner();
     
     // Check if milestone belongs to the authenticated group
     let check_stmt = client.prepare(
@@ -468,10 +466,10 @@ pub async fn delete_milestone(
          WHERE milestone_id = $1 AND group_id = $2"
     ).await?;
     
-    let row = client.query_opt(&check_stmt, &[&milestone_id, &auth.0]).await?;
+    let row = client.query_opt(&check_stmt, &[&milestone_id, &auth.group_id]).await?;
     
     if row.is_none() {
-        return Err(ApiError::NotFound("Milestone not found".to_string()));
+        return Ok(HttpResponse::NotFound().body("Milestone not found"));
     }
     
     // Delete milestone (will cascade to progress records)
@@ -561,7 +559,7 @@ pub async fn auto_update_milestone_progress(
 fn calculate_progress(
     milestone_type: &str,
     target_data: &serde_json::Value,
-    completion_criteria: &serde_json::Value,
+    _completion_criteria: &serde_json::Value,
     member_data: &GroupMember,
 ) -> Result<(serde_json::Value, f32), ApiError> {
     let mut current_progress = serde_json::json!({});
@@ -571,7 +569,7 @@ fn calculate_progress(
         "skill_total" => {
             // Calculate total level based on skills data
             if let Some(skills) = &member_data.skills {
-                let skills_json: serde_json::Value = serde_json::from_str(skills).unwrap_or_default();
+                let skills_json: serde_json::Value = serde_json::to_value(skills).unwrap_or_default();
                 
                 if let Some(skills_obj) = skills_json.as_object() {
                     let mut total_level = 0;
@@ -603,7 +601,7 @@ fn calculate_progress(
         "boss_kc" => {
             // Calculate boss KC from stats data
             if let Some(stats) = &member_data.stats {
-                let stats_json: serde_json::Value = serde_json::from_str(stats).unwrap_or_default();
+                let stats_json: serde_json::Value = serde_json::to_value(stats).unwrap_or_default();
                 
                 if let Some(boss_name) = target_data.get("bossName").and_then(|b| b.as_str()) {
                     let boss_key = format!("{}_kc", boss_name.to_lowercase().replace(' ', "_"));
@@ -633,7 +631,7 @@ fn calculate_progress(
             // Calculate collection log completion
             if let Some(collection_log) = &member_data.collection_log {
                 let collection_log_json: serde_json::Value = 
-                    serde_json::from_str(collection_log).unwrap_or_default();
+                    serde_json::to_value(collection_log).unwrap_or_default();
                 
                 if let Some(collection_name) = target_data.get("collectionName").and_then(|c| c.as_str()) {
                     // Check if this collection exists in the member's data
@@ -664,16 +662,17 @@ fn calculate_progress(
             // Calculate quest completion
             if let Some(quests) = &member_data.quests {
                 let quests_json: serde_json::Value = 
-                    serde_json::from_str(quests).unwrap_or_default();
+                    serde_json::to_value(quests).unwrap_or_default();
                 
                 let target_quests = target_data.get("questList")
                     .and_then(|q| q.as_array())
-                    .unwrap_or(&Vec::new());
+                    .cloned()
+                    .unwrap_or_default();
                 
                 if !target_quests.is_empty() {
                     let mut completed_count = 0;
                     
-                    for quest in target_quests {
+                    for quest in &target_quests {
                         if let Some(quest_name) = quest.as_str() {
                             if let Some(status) = quests_json.get(quest_name) {
                                 if status == "FINISHED" {
@@ -696,7 +695,7 @@ fn calculate_progress(
             // Calculate achievement diary completion
             if let Some(diary_vars) = &member_data.diary_vars {
                 let diary_json: serde_json::Value = 
-                    serde_json::from_str(diary_vars).unwrap_or_default();
+                    serde_json::to_value(diary_vars).unwrap_or_default();
                 
                 if let Some(diary_name) = target_data.get("diaryName").and_then(|d| d.as_str()) {
                     if let Some(tier) = target_data.get("tier").and_then(|t| t.as_str()) {
@@ -766,14 +765,3 @@ async fn check_milestone_completion(
     
     Ok(())
 }
-
-pub fn endpoints(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/api")
-            .service(web::resource("/milestones").route(web::get().to(get_milestones)))
-            .service(web::resource("/milestones").route(web::post().to(create_milestone)))
-            .service(web::resource("/milestones/{milestone_id}/status").route(web::patch().to(update_status)))
-            .service(web::resource("/milestones/{milestone_id}/members/{member_name}/progress").route(web::patch().to(update_progress)))
-            .service(web::resource("/milestones/{milestone_id}").route(web::delete().to(delete)))
-    );
-}
@@ -1,5 +1,5 @@
-use actix_web::{web, HttpResponse, Responder};
-use deadpool_postgres::{Client, Pool};
+use actix_web::{web, HttpResponse};
+use deadpool_postgres::Pool;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use std::error::Error;
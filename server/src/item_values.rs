@@ -0,0 +1,317 @@
+use crate::error::ApiError;
+use crate::models::{GEPrices, WikiPricePoint};
+use crate::unauthed::{current_ge_prices, fetch_price_history};
+use chrono::{DateTime, Duration, Utc};
+use deadpool_postgres::Client;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+#[cfg(test)]
+mod item_value_tests {
+    use super::*;
+
+    fn point(timestamp: i64, high: Option<i64>, low: Option<i64>) -> WikiPricePoint {
+        WikiPricePoint {
+            timestamp,
+            avg_high_price: high,
+            avg_low_price: low,
+        }
+    }
+
+    #[test]
+    fn ge_price_is_preferred_over_alch_and_reported() {
+        let prices: GEPrices = HashMap::from([(1, 1000)]);
+        assert_eq!(unit_value(1, &prices, 50, Some(10)), (1000, ValueSource::Ge));
+        assert_eq!(unit_value(2, &prices, 50, Some(10)), (50, ValueSource::Alch));
+        assert_eq!(unit_value(3, &prices, 0, Some(10)), (10, ValueSource::Reported));
+        assert_eq!(unit_value(3, &prices, 0, None), (0, ValueSource::Unknown));
+    }
+
+    #[test]
+    fn price_at_uses_latest_point_before_drop() {
+        let points = vec![
+            point(100, Some(10), Some(20)),
+            point(200, Some(30), None),
+            point(300, None, None),
+        ];
+        let at = |seconds| DateTime::from_timestamp(seconds, 0).unwrap();
+        assert_eq!(price_at(&points, at(50)), None);
+        assert_eq!(price_at(&points, at(150)), Some(15));
+        assert_eq!(price_at(&points, at(250)), Some(30));
+        // Days without trades fall back to the last day that had them
+        assert_eq!(price_at(&points, at(350)), Some(30));
+    }
+}
+
+/// Most price histories fetched from the wiki in one revaluation. Drops of items past
+/// the limit are left as they are; revaluing again reuses the cached histories and
+/// fetches the next items.
+const MAX_HISTORY_FETCHES: usize = 25;
+
+/// How long a fetched price history is reused. The wiki adds one point a day.
+const PRICE_HISTORY_TTL_HOURS: i64 = 6;
+
+type PriceHistory = Arc<Vec<WikiPricePoint>>;
+
+lazy_static! {
+    /// Item names and alch values by item id, from the same item data as the site.
+    /// Built into the binary like the slayer masters table.
+    pub static ref ITEM_DATA: HashMap<i32, ItemInfo> = {
+        serde_json::from_str(include_str!("../item_data.json")).expect("item_data.json is not valid")
+    };
+
+    /// Daily price histories by item id, with when they were fetched
+    static ref PRICE_HISTORIES: Mutex<HashMap<i32, (DateTime<Utc>, PriceHistory)>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Deserialize)]
+pub struct ItemInfo {
+    pub name: String,
+    #[serde(default)]
+    pub highalch: i64,
+}
+
+/// Where a drop's value came from
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueSource {
+    Ge,
+    Alch,
+    Reported,
+    Unknown,
+}
+
+impl ValueSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValueSource::Ge => "ge",
+            ValueSource::Alch => "alch",
+            ValueSource::Reported => "reported",
+            ValueSource::Unknown => "unknown",
+        }
+    }
+}
+
+/// Which prices a revaluation uses
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceBasis {
+    /// The latest GE prices
+    #[default]
+    Current,
+
+    /// GE prices on the day of each drop, where the price history goes back far enough
+    AtTime,
+}
+
+/// Which drops to revalue
+#[derive(Deserialize)]
+pub struct RevaluationRequest {
+    #[serde(default)]
+    pub prices: PriceBasis,
+    pub item_id: Option<i32>,
+    pub start_date: Option<DateTime<Utc>>,
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+/// What a revaluation changed
+#[derive(Serialize)]
+pub struct RevaluationSummary {
+    pub drops_checked: i64,
+    pub drops_updated: i64,
+
+    /// Total value of the checked drops before and after
+    pub total_value_before: i64,
+    pub total_value_after: i64,
+
+    /// Drops skipped because their item's price history is past this revaluation's
+    /// fetch limit. Revalue again to update them.
+    pub drops_deferred: i64,
+}
+
+/// Value of one item: its GE price, then its high alch value, then whatever the client reported
+pub fn unit_value(item_id: i32, prices: &GEPrices, highalch: i64, reported: Option<i64>) -> (i64, ValueSource) {
+    match prices.get(&item_id) {
+        Some(price) if *price > 0 => (*price, ValueSource::Ge),
+        _ if highalch > 0 => (highalch, ValueSource::Alch),
+        _ => match reported {
+            Some(reported) if reported > 0 => (reported, ValueSource::Reported),
+            _ => (0, ValueSource::Unknown),
+        },
+    }
+}
+
+/// Average price on the last day with trades at or before `at`, from daily price points in time order
+pub fn price_at(points: &[WikiPricePoint], at: DateTime<Utc>) -> Option<i64> {
+    points
+        .iter()
+        .take_while(|point| point.timestamp <= at.timestamp())
+        .filter_map(|point| match (point.avg_high_price, point.avg_low_price) {
+            (Some(high), Some(low)) => Some((high + low) / 2),
+            (Some(price), None) | (None, Some(price)) => Some(price),
+            (None, None) => None,
+        })
+        .last()
+}
+
+/// Price histories for the items, from the cache or the wiki. At most
+/// `MAX_HISTORY_FETCHES` are fetched; items past that are left out. Failed
+/// lookups get an empty history and aren't cached, so they're retried next time.
+async fn load_price_histories(item_ids: &[i32]) -> HashMap<i32, PriceHistory> {
+    let now = Utc::now();
+    let stale_before = now - Duration::hours(PRICE_HISTORY_TTL_HOURS);
+    let mut histories = HashMap::new();
+    let mut missing = Vec::new();
+    {
+        let mut cache = PRICE_HISTORIES.lock().unwrap();
+        cache.retain(|_, (fetched_at, _)| *fetched_at >= stale_before);
+        for item_id in item_ids {
+            match cache.get(item_id) {
+                Some((_, history)) => {
+                    histories.insert(*item_id, history.clone());
+                }
+                None => missing.push(*item_id),
+            }
+        }
+    }
+
+    for item_id in missing.into_iter().take(MAX_HISTORY_FETCHES) {
+        let history = match fetch_price_history(item_id).await {
+            Ok(timeseries) => {
+                let history = Arc::new(timeseries.data);
+                PRICE_HISTORIES.lock().unwrap().insert(item_id, (now, history.clone()));
+                history
+            }
+            Err(err) => {
+                log::error!("Failed to fetch price history for item {}: {}", item_id, err);
+                Arc::new(Vec::new())
+            }
+        };
+        histories.insert(item_id, history);
+    }
+
+    histories
+}
+
+fn highalch(item_id: i32) -> i64 {
+    ITEM_DATA.get(&item_id).map_or(0, |item| item.highalch)
+}
+
+/// Name of an item, if it's in the item data
+pub fn item_name(item_id: i32) -> Option<&'static str> {
+    ITEM_DATA.get(&item_id).map(|item| item.name.as_str())
+}
+
+/// Value an item at current prices for storing a new drop
+pub fn value_item(item_id: i32, reported: Option<i64>) -> (i64, ValueSource) {
+    let prices = current_ge_prices();
+    // Until the first price fetch completes, trust the client over the alch value
+    if prices.is_empty() {
+        if let Some(reported) = reported.filter(|reported| *reported > 0) {
+            return (reported, ValueSource::Reported);
+        }
+    }
+    unit_value(item_id, &prices, highalch(item_id), reported)
+}
+
+/// Recompute the value of a group's drops at current or at-the-time prices
+///
+/// At-the-time prices come from the wiki's daily price history, which only goes
+/// back about a year; older drops and failed lookups use current prices instead.
+/// Histories are cached and only a limited number are fetched per revaluation.
+pub async fn revalue_drops(
+    client: &mut Client,
+    group_id: i64,
+    request: &RevaluationRequest,
+) -> Result<RevaluationSummary, ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "SELECT d.drop_id, d.item_id, COALESCE(d.item_quantity, 1), d.item_value, d.reported_value, d.value_source, d.timestamp
+             FROM groupironman.valuable_drops d
+             JOIN groupironman.members m ON m.member_id = d.member_id
+             WHERE m.group_id = $1
+             AND ($2::INT IS NULL OR d.item_id = $2)
+             AND ($3::TIMESTAMPTZ IS NULL OR d.timestamp >= $3)
+             AND ($4::TIMESTAMPTZ IS NULL OR d.timestamp <= $4)"
+        )
+        .await?;
+    let rows = client
+        .query(&stmt, &[&group_id, &request.item_id, &request.start_date, &request.end_date])
+        .await?;
+
+    let prices = current_ge_prices();
+    let histories = match request.prices {
+        PriceBasis::AtTime => {
+            let mut seen = HashSet::new();
+            let mut item_ids = Vec::new();
+            for row in &rows {
+                let item_id: i32 = row.try_get(1)?;
+                if seen.insert(item_id) {
+                    item_ids.push(item_id);
+                }
+            }
+            load_price_histories(&item_ids).await
+        }
+        PriceBasis::Current => HashMap::new(),
+    };
+    let mut summary = RevaluationSummary {
+        drops_checked: 0,
+        drops_updated: 0,
+        total_value_before: 0,
+        total_value_after: 0,
+        drops_deferred: 0,
+    };
+    let mut updates = Vec::new();
+
+    for row in &rows {
+        let drop_id: i64 = row.try_get(0)?;
+        let item_id: i32 = row.try_get(1)?;
+        let quantity: i32 = row.try_get(2)?;
+        let item_value: i64 = row.try_get(3)?;
+        let reported: Option<i64> = row.try_get(4)?;
+        let value_source: String = row.try_get(5)?;
+        let timestamp: DateTime<Utc> = row.try_get(6)?;
+
+        let historical = match request.prices {
+            PriceBasis::AtTime => match histories.get(&item_id) {
+                Some(history) => price_at(history, timestamp).filter(|price| *price > 0),
+                None => {
+                    summary.drops_deferred += 1;
+                    continue;
+                }
+            },
+            PriceBasis::Current => None,
+        };
+        let (value, source) = match historical {
+            Some(price) => (price, ValueSource::Ge),
+            None => unit_value(item_id, &prices, highalch(item_id), reported),
+        };
+
+        summary.drops_checked += 1;
+        summary.total_value_before += item_value * quantity as i64;
+        summary.total_value_after += value * quantity as i64;
+        if value != item_value || source.as_str() != value_source {
+            updates.push((drop_id, value, source));
+        }
+    }
+
+    let transaction = client.transaction().await?;
+    let update_stmt = transaction
+        .prepare_cached(
+            "UPDATE groupironman.valuable_drops
+             SET item_value = $2, value_source = $3, valued_at = NOW()
+             WHERE drop_id = $1"
+        )
+        .await?;
+    for (drop_id, value, source) in &updates {
+        transaction
+            .execute(&update_stmt, &[drop_id, value, &source.as_str()])
+            .await?;
+    }
+    transaction.commit().await?;
+    summary.drops_updated = updates.len() as i64;
+
+    Ok(summary)
+}
@@ -0,0 +1,321 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::custom_config::CustomConfig;
+use crate::error::ApiError;
+use crate::models::SHARED_MEMBER;
+use crate::time_zones::{group_time_zone, local_date, start_of_local_day};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod leaderboard_tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn ties_share_a_rank() {
+        let ranks = competition_ranks(&[
+            ("a".to_string(), 50),
+            ("b".to_string(), 80),
+            ("c".to_string(), 50),
+            ("d".to_string(), 10),
+        ]);
+        assert_eq!(ranks["b"], 1);
+        assert_eq!(ranks["a"], 2);
+        assert_eq!(ranks["c"], 2);
+        assert_eq!(ranks["d"], 4);
+    }
+
+    #[test]
+    fn week_compares_with_previous_week() {
+        // 2024-06-05 is a Wednesday
+        let now = at(2024, 6, 5, 12);
+        let (current, previous) = period_windows(LeaderboardPeriod::Week, None, None, now, Tz::UTC).unwrap();
+        assert_eq!(current.start, Some(at(2024, 6, 3, 0)));
+        assert_eq!(current.end, now);
+        assert_eq!(previous.start, Some(at(2024, 5, 27, 0)));
+        assert_eq!(previous.end, at(2024, 6, 3, 0));
+    }
+
+    #[test]
+    fn month_rolls_back_across_year() {
+        let now = at(2024, 1, 15, 8);
+        let (current, previous) = period_windows(LeaderboardPeriod::Month, None, None, now, Tz::UTC).unwrap();
+        assert_eq!(current.start, Some(at(2024, 1, 1, 0)));
+        assert_eq!(previous.start, Some(at(2023, 12, 1, 0)));
+        assert_eq!(previous.end, at(2024, 1, 1, 0));
+    }
+
+    #[test]
+    fn custom_range_compares_with_preceding_range() {
+        let now = at(2024, 6, 5, 12);
+        let (current, previous) = period_windows(
+            LeaderboardPeriod::Custom,
+            Some(at(2024, 5, 10, 0)),
+            Some(at(2024, 5, 20, 0)),
+            now,
+            Tz::UTC,
+        )
+        .unwrap();
+        assert_eq!(current.start, Some(at(2024, 5, 10, 0)));
+        assert_eq!(previous.start, Some(at(2024, 4, 30, 0)));
+        assert_eq!(previous.end, at(2024, 5, 10, 0));
+
+        assert!(period_windows(LeaderboardPeriod::Custom, None, None, now, Tz::UTC).is_err());
+        assert!(period_windows(
+            LeaderboardPeriod::Custom,
+            Some(at(2024, 5, 20, 0)),
+            Some(at(2024, 5, 10, 0)),
+            now,
+            Tz::UTC
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn periods_start_at_local_midnight() {
+        // Already Monday morning in Auckland while it's still Sunday in UTC
+        let now = at(2024, 6, 2, 20);
+        let (current, previous) = period_windows(LeaderboardPeriod::Week, None, None, now, chrono_tz::Pacific::Auckland).unwrap();
+        assert_eq!(current.start, Some(at(2024, 6, 2, 12)));
+        assert_eq!(previous.start, Some(at(2024, 5, 26, 12)));
+
+        // Still May in Los Angeles
+        let now = at(2024, 6, 1, 3);
+        let (current, previous) = period_windows(LeaderboardPeriod::Month, None, None, now, chrono_tz::America::Los_Angeles).unwrap();
+        assert_eq!(current.start, Some(at(2024, 5, 1, 7)));
+        assert_eq!(previous.start, Some(at(2024, 4, 1, 7)));
+    }
+}
+
+/// Time window a leaderboard covers
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardPeriod {
+    #[default]
+    AllTime,
+    Week,
+    Month,
+    Custom,
+}
+
+/// A `[start, end)` range of ledger events. No start means from the beginning.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct PeriodWindow {
+    pub start: Option<DateTime<Utc>>,
+    pub end: DateTime<Utc>,
+}
+
+/// Query parameters for a leaderboard
+#[derive(Deserialize)]
+pub struct LeaderboardQuery {
+    /// Only count this point type; all types when omitted
+    point_type: Option<String>,
+    #[serde(default)]
+    period: LeaderboardPeriod,
+    /// Range for the custom period
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+}
+
+/// A member's standing on a leaderboard
+#[derive(Serialize)]
+pub struct LeaderboardEntry {
+    rank: i64,
+    member_name: String,
+    points: i64,
+    /// Rank in the previous period, if the member scored in it
+    previous_rank: Option<i64>,
+    previous_points: i64,
+    /// Places gained since the previous period (negative when dropping)
+    rank_change: Option<i64>,
+}
+
+/// A ranked leaderboard for one period
+#[derive(Serialize)]
+pub struct Leaderboard {
+    point_type: Option<String>,
+    period: LeaderboardPeriod,
+    window: PeriodWindow,
+    previous_window: PeriodWindow,
+    entries: Vec<LeaderboardEntry>,
+}
+
+/// Work out the window being ranked and the one it is compared against.
+/// Weeks start on Monday and days at midnight in the group's time zone.
+/// All-time standings are compared with the standings at the start of the current week.
+pub fn period_windows(
+    period: LeaderboardPeriod,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    tz: Tz,
+) -> Result<(PeriodWindow, PeriodWindow), String> {
+    let today = local_date(tz, now);
+    let start_of_day = |date: NaiveDate| start_of_local_day(tz, date);
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let week_start = start_of_day(monday);
+    let previous_week_start = start_of_day(monday - Duration::days(7));
+
+    match period {
+        LeaderboardPeriod::AllTime => Ok((
+            PeriodWindow { start: None, end: now },
+            PeriodWindow { start: None, end: week_start },
+        )),
+        LeaderboardPeriod::Week => Ok((
+            PeriodWindow { start: Some(week_start), end: now },
+            PeriodWindow { start: Some(previous_week_start), end: week_start },
+        )),
+        LeaderboardPeriod::Month => {
+            let month_start = NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap();
+            let previous_month_start = if today.month() == 1 {
+                NaiveDate::from_ymd_opt(today.year() - 1, 12, 1).unwrap()
+            } else {
+                NaiveDate::from_ymd_opt(today.year(), today.month() - 1, 1).unwrap()
+            };
+            Ok((
+                PeriodWindow { start: Some(start_of_day(month_start)), end: now },
+                PeriodWindow { start: Some(start_of_day(previous_month_start)), end: start_of_day(month_start) },
+            ))
+        }
+        LeaderboardPeriod::Custom => {
+            let start = start.ok_or("start is required for a custom period")?;
+            let end = end.unwrap_or(now);
+            if end <= start {
+                return Err("end must be after start".to_string());
+            }
+            Ok((
+                PeriodWindow { start: Some(start), end },
+                PeriodWindow { start: Some(start - (end - start)), end: start },
+            ))
+        }
+    }
+}
+
+/// Standard competition ranking: tied members share a rank and the next rank is skipped
+pub fn competition_ranks(scores: &[(String, i64)]) -> HashMap<String, i64> {
+    let mut sorted: Vec<&(String, i64)> = scores.iter().collect();
+    sorted.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+
+    let mut ranks = HashMap::new();
+    let mut previous_score = None;
+    let mut rank = 0;
+    for (position, (member_name, score)) in sorted.into_iter().enumerate() {
+        if previous_score != Some(*score) {
+            rank = position as i64 + 1;
+            previous_score = Some(*score);
+        }
+        ranks.insert(member_name.clone(), rank);
+    }
+    ranks
+}
+
+/// Get the group's leaderboard for a point type (or overall) and period
+pub async fn get_leaderboard(
+    pool: web::Data<Pool>,
+    config: web::Data<CustomConfig>,
+    auth: AuthedGroupId,
+    query: web::Query<LeaderboardQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if !config.enable_leaderboards {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "Leaderboards are disabled"
+        })));
+    }
+
+    let client = pool.get().await?;
+    let tz = group_time_zone(&client, auth.group_id).await?;
+    let (window, previous_window) = match period_windows(query.period, query.start, query.end, Utc::now(), tz) {
+        Ok(windows) => windows,
+        Err(message) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": message
+            })));
+        }
+    };
+
+    let stmt = client
+        .prepare_cached(
+            "SELECT m.member_name,
+                    COALESCE(SUM(e.points) FILTER (
+                        WHERE ($2::TIMESTAMPTZ IS NULL OR e.created_at >= $2) AND e.created_at < $3
+                    ), 0)::BIGINT,
+                    COALESCE(SUM(e.points) FILTER (
+                        WHERE ($4::TIMESTAMPTZ IS NULL OR e.created_at >= $4) AND e.created_at < $5
+                    ), 0)::BIGINT,
+                    COUNT(e.event_id) FILTER (
+                        WHERE ($4::TIMESTAMPTZ IS NULL OR e.created_at >= $4) AND e.created_at < $5
+                    )
+             FROM groupironman.members m
+             LEFT JOIN groupironman.point_events e
+                ON e.member_id = m.member_id
+                AND ($6::TEXT IS NULL OR e.point_type = $6)
+             WHERE m.group_id = $1 AND m.member_name != $7
+             GROUP BY m.member_name"
+        )
+        .await?;
+
+    let rows = client
+        .query(
+            &stmt,
+            &[
+                &auth.group_id,
+                &window.start,
+                &window.end,
+                &previous_window.start,
+                &previous_window.end,
+                &query.point_type,
+                &SHARED_MEMBER,
+            ],
+        )
+        .await?;
+
+    let mut current_scores = Vec::new();
+    let mut previous_scores = Vec::new();
+    for row in &rows {
+        let member_name: String = row.try_get(0)?;
+        let previous_event_count: i64 = row.try_get(3)?;
+        current_scores.push((member_name.clone(), row.try_get::<_, i64>(1)?));
+        if previous_event_count > 0 {
+            previous_scores.push((member_name, row.try_get::<_, i64>(2)?));
+        }
+    }
+
+    let current_ranks = competition_ranks(&current_scores);
+    let previous_ranks = competition_ranks(&previous_scores);
+    let previous_points: HashMap<String, i64> = previous_scores.into_iter().collect();
+
+    let mut entries: Vec<LeaderboardEntry> = current_scores
+        .into_iter()
+        .map(|(member_name, points)| {
+            let rank = current_ranks[&member_name];
+            let previous_rank = previous_ranks.get(&member_name).copied();
+            LeaderboardEntry {
+                rank,
+                previous_points: previous_points.get(&member_name).copied().unwrap_or(0),
+                rank_change: previous_rank.map(|previous_rank| previous_rank - rank),
+                previous_rank,
+                member_name,
+                points,
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| a.rank.cmp(&b.rank).then_with(|| a.member_name.cmp(&b.member_name)));
+
+    Ok(HttpResponse::Ok().json(Leaderboard {
+        point_type: query.point_type.clone(),
+        period: query.period,
+        window,
+        previous_window,
+        entries,
+    }))
+}
@@ -0,0 +1,306 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::db::get_member_id;
+use crate::error::ApiError;
+use crate::item_values::{item_name, value_item, ValueSource};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod loot_import_tests {
+    use super::*;
+
+    fn records() -> Vec<LootTrackerRecord> {
+        serde_json::from_value(serde_json::json!([
+            {"eventId": "Vorkath", "type": "NPC", "date": 1700000000000i64,
+             "drops": [{"id": 11286, "qty": 1}, {"id": 536, "qty": 2}, {"id": 536, "qty": 2}]},
+            {"eventId": "Barrows", "type": "EVENT", "date": "2024-03-01T10:00:00Z",
+             "drops": [{"id": 4716, "qty": 1}, {"id": 999999, "qty": 1}]},
+            {"eventId": "Zulrah", "type": "NPC", "date": {"seconds": 1700000100, "nanos": 0},
+             "drops": [{"id": 12934, "qty": 0}]},
+            {"eventId": "Zulrah", "type": "NPC", "drops": [{"id": 12922, "qty": 1}]}
+        ]))
+        .unwrap()
+    }
+
+    fn lookup(item_id: i32) -> Option<(String, i64, ValueSource)> {
+        match item_id {
+            11286 => Some(("Draconic visage".to_string(), 3_000_000, ValueSource::Ge)),
+            536 => Some(("Dragon bones".to_string(), 2_000, ValueSource::Ge)),
+            4716 => Some(("Dharok's helm".to_string(), 500_000, ValueSource::Ge)),
+            12934 | 12922 => Some(("Zulrah loot".to_string(), 200, ValueSource::Ge)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn dates_are_read_in_every_format() {
+        let records = records();
+        assert_eq!(records[0].date.as_ref().and_then(LootTrackerDate::to_datetime), Utc.timestamp_opt(1_700_000_000, 0).single());
+        assert_eq!(records[2].date.as_ref().and_then(LootTrackerDate::to_datetime), Utc.timestamp_opt(1_700_000_100, 0).single());
+        assert!(records[3].date.is_none());
+    }
+
+    #[test]
+    fn plan_filters_and_merges_drops() {
+        let (candidates, skips) = plan_import(&records(), 100_000, lookup);
+        let items: Vec<(i32, i32)> = candidates.iter().map(|c| (c.item_id, c.quantity)).collect();
+        assert_eq!(items, vec![(11286, 1), (4716, 1)]);
+        assert_eq!(candidates[0].source_name, "Vorkath");
+        assert_eq!(
+            skips,
+            ImportSkips {
+                below_min_value: 1,
+                duplicate: 0,
+                missing_date: 1,
+                unknown_item: 1,
+                invalid_quantity: 1,
+            }
+        );
+    }
+}
+
+/// Minimum total value of an imported drop when the request doesn't set one
+pub const DEFAULT_IMPORT_MIN_VALUE: i64 = 100_000;
+
+/// An existing drop of the same item and quantity this close in time counts as the same drop
+const IMPORT_DEDUPE_WINDOW_SECONDS: i64 = 60;
+
+/// A kill or event from RuneLite's loot tracker
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LootTrackerRecord {
+    /// NPC or event name, e.g. "Vorkath" or "Barrows"
+    pub event_id: String,
+    pub date: Option<LootTrackerDate>,
+    #[serde(default)]
+    pub drops: Vec<LootTrackerItem>,
+}
+
+#[derive(Deserialize)]
+pub struct LootTrackerItem {
+    pub id: i32,
+    pub qty: i32,
+}
+
+/// Loot tracker dates come as epoch milliseconds, ISO 8601 text or a seconds/nanos pair
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum LootTrackerDate {
+    Millis(i64),
+    Text(DateTime<Utc>),
+    Instant {
+        seconds: i64,
+        #[serde(default)]
+        nanos: u32,
+    },
+}
+
+impl LootTrackerDate {
+    pub fn to_datetime(&self) -> Option<DateTime<Utc>> {
+        match self {
+            LootTrackerDate::Millis(millis) => Utc.timestamp_millis_opt(*millis).single(),
+            LootTrackerDate::Text(date) => Some(*date),
+            LootTrackerDate::Instant { seconds, nanos } => Utc.timestamp_opt(*seconds, *nanos).single(),
+        }
+    }
+}
+
+/// Query parameters for a loot tracker import
+#[derive(Deserialize)]
+pub struct LootImportParams {
+    /// Only import drops worth at least this much in total
+    pub min_value: Option<i64>,
+}
+
+/// A loot tracker drop that is valuable enough to import
+pub struct ImportCandidate {
+    pub item_id: i32,
+    pub item_name: String,
+    pub quantity: i32,
+    pub item_value: i64,
+    pub value_source: ValueSource,
+    pub source_name: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Number of drops skipped for each reason
+#[derive(Serialize, Default, Debug, PartialEq, Eq)]
+pub struct ImportSkips {
+    pub below_min_value: i64,
+    pub duplicate: i64,
+    pub missing_date: i64,
+    pub unknown_item: i64,
+    pub invalid_quantity: i64,
+}
+
+/// A drop added by an import
+#[derive(Serialize)]
+pub struct ImportedDrop {
+    pub drop_id: i64,
+    pub item_id: i32,
+    pub item_name: String,
+    pub item_quantity: i32,
+    pub total_value: i64,
+    pub value_source: ValueSource,
+    pub source_name: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// What a loot tracker import did
+#[derive(Serialize)]
+pub struct ImportReport {
+    pub member_name: String,
+    pub min_value: i64,
+    pub imported_count: i64,
+    pub imported: Vec<ImportedDrop>,
+    pub skipped: ImportSkips,
+}
+
+/// Pick out the drops worth importing. `lookup` gives an item's name, value and where the value came from.
+/// Repeats of an item within one kill are merged before the value is checked.
+pub fn plan_import(
+    records: &[LootTrackerRecord],
+    min_value: i64,
+    lookup: impl Fn(i32) -> Option<(String, i64, ValueSource)>,
+) -> (Vec<ImportCandidate>, ImportSkips) {
+    let mut candidates = Vec::new();
+    let mut skips = ImportSkips::default();
+
+    for record in records {
+        let timestamp = match record.date.as_ref().and_then(LootTrackerDate::to_datetime) {
+            Some(timestamp) => timestamp,
+            None => {
+                skips.missing_date += record.drops.len() as i64;
+                continue;
+            }
+        };
+        let source_name: String = record.event_id.trim().chars().take(100).collect();
+
+        let mut quantities: BTreeMap<i32, i64> = BTreeMap::new();
+        for drop in &record.drops {
+            *quantities.entry(drop.id).or_default() += drop.qty as i64;
+        }
+
+        for (item_id, quantity) in quantities {
+            let quantity = match i32::try_from(quantity) {
+                Ok(quantity) if quantity > 0 => quantity,
+                _ => {
+                    skips.invalid_quantity += 1;
+                    continue;
+                }
+            };
+            let (item_name, item_value, value_source) = match lookup(item_id) {
+                Some(item) => item,
+                None => {
+                    skips.unknown_item += 1;
+                    continue;
+                }
+            };
+            if item_value.saturating_mul(quantity as i64) < min_value {
+                skips.below_min_value += 1;
+                continue;
+            }
+
+            candidates.push(ImportCandidate {
+                item_id,
+                item_name: item_name.chars().take(100).collect(),
+                quantity,
+                item_value,
+                value_source,
+                source_name: source_name.clone(),
+                timestamp,
+            });
+        }
+    }
+
+    (candidates, skips)
+}
+
+/// Import a member's RuneLite loot tracker history as valuable drops
+///
+/// Takes the loot tracker's list of kills. Drops are valued like any other drop and
+/// keep their original timestamps. Drops already recorded for the member (same item
+/// and quantity within a minute) are skipped. Imported drops don't award points.
+pub async fn import_loot_tracker(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, String)>,
+    params: web::Query<LootImportParams>,
+    records: web::Json<Vec<LootTrackerRecord>>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
+    let min_value = params.min_value.unwrap_or(DEFAULT_IMPORT_MIN_VALUE).max(0);
+
+    let (candidates, mut skipped) = plan_import(&records, min_value, |item_id| {
+        let name = item_name(item_id)?;
+        let (value, source) = value_item(item_id, None);
+        Some((name.to_string(), value, source))
+    });
+
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    let member_id = get_member_id(&transaction, auth.group_id, &member_name).await?;
+
+    let insert_stmt = transaction
+        .prepare_cached(
+            "INSERT INTO groupironman.valuable_drops
+             (member_id, item_id, item_name, item_quantity, item_value, source_name, timestamp, value_source, valued_at, entry_source)
+             SELECT $1, $2, $3, $4, $5, $6, $7, $8, NOW(), 'import'
+             WHERE NOT EXISTS (
+                SELECT 1 FROM groupironman.valuable_drops
+                WHERE member_id = $1 AND item_id = $2 AND item_quantity = $4
+                AND timestamp BETWEEN $9 AND $10
+             )
+             RETURNING drop_id"
+        )
+        .await?;
+
+    let window = Duration::seconds(IMPORT_DEDUPE_WINDOW_SECONDS);
+    let mut imported = Vec::new();
+    for candidate in candidates {
+        let row = transaction
+            .query_opt(
+                &insert_stmt,
+                &[
+                    &member_id,
+                    &candidate.item_id,
+                    &candidate.item_name,
+                    &candidate.quantity,
+                    &candidate.item_value,
+                    &candidate.source_name,
+                    &candidate.timestamp,
+                    &candidate.value_source.as_str(),
+                    &(candidate.timestamp - window),
+                    &(candidate.timestamp + window),
+                ],
+            )
+            .await?;
+
+        match row {
+            Some(row) => imported.push(ImportedDrop {
+                drop_id: row.try_get(0)?,
+                item_id: candidate.item_id,
+                item_name: candidate.item_name,
+                item_quantity: candidate.quantity,
+                total_value: candidate.item_value * candidate.quantity as i64,
+                value_source: candidate.value_source,
+                source_name: candidate.source_name,
+                timestamp: candidate.timestamp,
+            }),
+            None => skipped.duplicate += 1,
+        }
+    }
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ImportReport {
+        member_name,
+        min_value,
+        imported_count: imported.len() as i64,
+        imported,
+        skipped,
+    }))
+}
@@ -1,7 +1,10 @@
 mod activities_api;
+mod attachments;
 mod auth_middleware;
 mod authed;
 mod boss_strategy_api;
+mod calendar_feed;
+mod calendar_rsvp_api;
 mod collection_log;
 mod config;
 mod crypto;
@@ -10,13 +13,23 @@ mod custom_plugin_api;
 mod custom_points;
 mod custom_routes;
 mod db;
+mod drop_duplicates;
 mod error;
+mod exports;
 mod group_challenges_api;
 mod group_milestones;
 mod group_milestones_api;
+mod item_values;
+mod leaderboards;
+mod loot_import;
 mod models;
+mod scoring_rules;
+mod seasons;
 mod shared_calendar_api;
+mod slayer_assignments;
+mod slayer_points;
 mod slayer_task_api;
+mod time_zones;
 mod unauthed;
 mod validators;
 mod valuable_drops_api;
@@ -24,7 +37,7 @@ mod valuable_drops_api;
 use crate::auth_middleware::AuthenticateMiddlewareFactory;
 use crate::config::Config;
 use crate::collection_log::CollectionLogInfo;
-use deadpool_postgres::Pool;
+use crate::custom_config::CustomConfig;
 
 use actix_cors::Cors;
 use actix_web::{http::header, middleware, web, App, HttpServer};
@@ -34,6 +47,7 @@ use tokio_postgres::NoTls;
 async fn main() -> std::io::Result<()> {
     let config = Config::from_env().unwrap();
     let pool = config.pg.create_pool(None, NoTls).unwrap();
+    let custom_config = CustomConfig::from_env();
     env_logger::init_from_env(
         env_logger::Env::new().default_filter_or(config.logger.level.to_string()),
     );
@@ -44,13 +58,19 @@ async fn main() -> std::io::Result<()> {
 
     unauthed::start_ge_updater();
     unauthed::start_skills_aggregator(pool.clone());
+    group_challenges_api::start_challenge_scheduler(pool.clone());
+    seasons::start_season_rollover(pool.clone());
+    calendar_rsvp_api::start_attendance_tracker(pool.clone());
+    let blob_store = attachments::blob_store_from_config(&config.attachments);
+    attachments::start_attachment_cleanup(pool.clone(), blob_store.clone());
 
     HttpServer::new(move || {
         let unauthed_scope = web::scope("/api")
             .service(unauthed::create_group)
             .service(unauthed::get_ge_prices)
             .service(unauthed::captcha_enabled)
-            .service(unauthed::collection_log_info);
+            .service(unauthed::collection_log_info)
+            .route("/calendar/{group_name}/{feed_token}/feed.ics", web::get().to(calendar_feed::get_calendar_feed));
         let authed_scope = web::scope("/api/group/{group_name}")
             .wrap(AuthenticateMiddlewareFactory::new())
             .service(authed::update_group_member)
@@ -66,11 +86,88 @@ async fn main() -> std::io::Result<()> {
             .service(group_milestones::create_milestone)
             .service(group_milestones::update_status)
             .service(group_milestones::update_progress)
-            .service(group_milestones::delete);
+            .service(group_milestones::delete)
+            .route("/plugin", web::post().to(custom_plugin_api::process_custom_plugin_data))
+            .route("/points", web::get().to(custom_points::get_points))
+            .route("/points", web::post().to(custom_points::add_points))
+            .route("/points/events", web::get().to(custom_points::get_point_events))
+            .route("/points/events/{event_id}/reverse", web::post().to(custom_points::reverse_point_event))
+            .route("/scoring-rules", web::get().to(scoring_rules::get_scoring_rules))
+            .route("/scoring-rules", web::put().to(scoring_rules::update_scoring_rules))
+            .route("/scoring-rules", web::delete().to(scoring_rules::reset_scoring_rules))
+            .route("/calendar/feed-token", web::get().to(calendar_feed::get_feed_token))
+            .route("/calendar/feed-token", web::post().to(calendar_feed::create_feed_token))
+            .route("/calendar/feed-token", web::delete().to(calendar_feed::revoke_feed_token))
+            .route("/events", web::get().to(shared_calendar_api::get_events))
+            .route("/events", web::post().to(shared_calendar_api::create_event))
+            .route("/events/{event_id}", web::get().to(shared_calendar_api::get_event))
+            .route("/events/{event_id}", web::put().to(shared_calendar_api::update_event))
+            .route("/events/{event_id}", web::delete().to(shared_calendar_api::delete_event))
+            .route("/events/{event_id}/attendees", web::get().to(calendar_rsvp_api::get_attendees))
+            .route("/events/{event_id}/occurrences/{occurrence_start}", web::put().to(shared_calendar_api::update_occurrence))
+            .route("/events/{event_id}/occurrences/{occurrence_start}", web::delete().to(shared_calendar_api::delete_occurrence))
+            .route("/events/{event_id}/rsvps", web::put().to(calendar_rsvp_api::set_rsvp))
+            .route("/events/{event_id}/rsvps/{member_name}", web::delete().to(calendar_rsvp_api::delete_rsvp))
+            .route("/leaderboard", web::get().to(leaderboards::get_leaderboard))
+            .route("/challenges/{challenge_id}/progress", web::get().to(group_challenges_api::get_challenge_progress))
+            .route("/challenges/{challenge_id}/status", web::put().to(group_challenges_api::update_challenge_status))
+            .route("/time-zone", web::get().to(time_zones::get_time_zones))
+            .route("/time-zone", web::put().to(time_zones::update_group_time_zone))
+            .route("/seasons", web::get().to(seasons::get_seasons))
+            .route("/seasons", web::post().to(seasons::create_season))
+            .route("/seasons/current", web::get().to(seasons::get_current_season))
+            .route("/seasons/{season_id}/standings", web::get().to(seasons::get_season_standings))
+            .service(
+                web::resource("/attachments")
+                    .app_data(web::PayloadConfig::new(config.attachments.max_bytes))
+                    .route(web::get().to(attachments::get_attachments))
+                    .route(web::post().to(attachments::upload_attachment)),
+            )
+            .route("/attachments/{attachment_id}", web::get().to(attachments::get_attachment_content))
+            .route("/attachments/{attachment_id}", web::delete().to(attachments::delete_attachment))
+            .route("/activities", web::get().to(custom_points::get_activities))
+            .route("/activities", web::post().to(custom_points::create_activity))
+            .route("/activities/categories", web::get().to(custom_points::get_activity_categories))
+            .route("/activities/complete", web::post().to(custom_points::complete_activity))
+            .route("/activities/reviews", web::get().to(custom_points::get_pending_reviews))
+            .route("/activities/reviews/{player_activity_id}", web::post().to(custom_points::review_activity))
+            .route("/activities/{activity_id}", web::put().to(custom_points::update_activity))
+            .route("/activities/{activity_id}/disable", web::post().to(custom_points::disable_activity))
+            .route("/activities/{activity_id}/enable", web::post().to(custom_points::enable_activity))
+            .route("/members/{member_name}/activities", web::get().to(custom_points::get_player_activities))
+            .route("/members/{member_name}/time-zone", web::put().to(time_zones::update_member_time_zone))
+            .route("/slayer/analytics", web::get().to(slayer_task_api::get_slayer_analytics))
+            .route("/slayer/export", web::get().to(slayer_task_api::export_slayer_tasks))
+            .route("/valuable-drops", web::get().to(valuable_drops_api::get_valuable_drops))
+            .route("/valuable-drops", web::post().to(valuable_drops_api::add_valuable_drop))
+            .route("/valuable-drops/export", web::get().to(valuable_drops_api::export_valuable_drops))
+            .service(
+                // Loot tracker histories are much larger than other request bodies
+                web::resource("/valuable-drops/import/{member_name}")
+                    .app_data(web::JsonConfig::default().limit(10_000_000))
+                    .route(web::post().to(loot_import::import_loot_tracker)),
+            )
+            .route("/valuable-drops/duplicates", web::get().to(drop_duplicates::get_suspected_duplicates))
+            .route("/valuable-drops/duplicates/resolve", web::post().to(drop_duplicates::resolve_duplicate))
+            .route("/valuable-drops/stats", web::get().to(valuable_drops_api::get_valuable_drop_stats))
+            .route("/valuable-drops/revalue", web::post().to(valuable_drops_api::revalue_valuable_drops))
+            .route("/valuable-drops/{drop_id}", web::get().to(valuable_drops_api::get_valuable_drop))
+            .route("/valuable-drops/{drop_id}", web::delete().to(valuable_drops_api::delete_valuable_drop))
+            .route("/slayer/group", web::get().to(slayer_task_api::get_group_slayer_overview))
+            .route("/slayer/masters", web::get().to(slayer_assignments::get_slayer_masters))
+            .route("/slayer/points/{member_name}", web::get().to(slayer_points::get_slayer_points))
+            .route("/slayer/points/{member_name}/spend", web::post().to(slayer_points::spend_slayer_points))
+            .route("/slayer/preferences/{member_name}", web::get().to(slayer_assignments::get_slayer_preferences))
+            .route("/slayer/preferences/{member_name}", web::put().to(slayer_assignments::update_slayer_preferences))
+            .route("/slayer/probabilities/{member_name}", web::get().to(slayer_assignments::get_task_probabilities))
+            .route("/slayer/tasks/{member_name}", web::get().to(slayer_task_api::get_slayer_task))
+            .route("/slayer/tasks/{member_name}", web::post().to(slayer_task_api::submit_slayer_task))
+            .route("/slayer/tasks/{member_name}/progress", web::get().to(slayer_task_api::get_task_progress))
+            .route("/slayer/tasks/{member_name}/progress", web::post().to(slayer_task_api::report_task_progress));
             
         // Register our custom API routes
         let api_v1_scope = web::scope("/api/v1")
-            .service(custom_routes::custom_routes(pool.clone()));
+            .configure(custom_routes::custom_routes);
         let json_config = web::JsonConfig::default().limit(100000);
         let cors = Cors::default()
             .allow_any_origin()
@@ -93,7 +190,9 @@ async fn main() -> std::io::Result<()> {
             .app_data(json_config)
             .app_data(web::Data::new(pool.clone()))
             .app_data(web::Data::new(config.clone()))
+            .app_data(web::Data::new(custom_config.clone()))
             .app_data(web::Data::new(collection_log_info.clone()))
+            .app_data(web::Data::from(blob_store.clone()))
             .service(authed_scope)
             .service(unauthed_scope)
             .service(api_v1_scope)
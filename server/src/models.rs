@@ -4,7 +4,7 @@ use crate::collection_log::CollectionLog;
 
 pub const SHARED_MEMBER: &str = "@SHARED";
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct Coordinates {
     x: i32,
@@ -12,7 +12,7 @@ pub struct Coordinates {
     plane: i32,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct Interacting {
     name: String,
@@ -33,7 +33,7 @@ pub struct RenameGroupMember {
     pub new_name: String,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct GroupMember {
     pub name: String,
@@ -111,8 +111,21 @@ pub struct WikiGEPrices {
 }
 pub type GEPrices = std::collections::HashMap<i32, i64>;
 #[derive(Deserialize)]
+pub struct WikiPricePoint {
+    pub timestamp: i64,
+    #[serde(rename = "avgHighPrice")]
+    pub avg_high_price: Option<i64>,
+    #[serde(rename = "avgLowPrice")]
+    pub avg_low_price: Option<i64>,
+}
+#[derive(Deserialize)]
+pub struct WikiPriceTimeseries {
+    pub data: Vec<WikiPricePoint>,
+}
+#[derive(Deserialize)]
 pub struct CaptchaVerifyResponse {
     pub success: bool,
     #[serde(rename = "error-codes", default)]
+    #[allow(dead_code)]
     pub error_codes: std::vec::Vec<String>,
 }
@@ -0,0 +1,571 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::collection_log::CollectionLogInfo;
+use crate::error::ApiError;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{GenericClient, Pool};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+#[cfg(test)]
+mod scoring_rules_tests {
+    use super::*;
+
+    #[test]
+    fn default_rules_are_valid() {
+        let mut rules = ScoringRules::default();
+        assert!(rules.validate(|_| true).is_ok());
+    }
+
+    #[test]
+    fn boss_points_prefer_boss_over_difficulty() {
+        let mut rules = ScoringRules::default();
+        rules.bosses.boss_points.insert("Vorkath".to_string(), 40);
+        rules.validate(|_| true).unwrap();
+
+        assert_eq!(rules.boss_kill_points("vorkath", Some("easy")), 40);
+        assert_eq!(rules.boss_kill_points("Zulrah", Some("hard")), 50);
+        assert_eq!(rules.boss_kill_points("Zulrah", Some("unknown")), 10);
+        assert_eq!(rules.boss_kill_points("Zulrah", None), 10);
+    }
+
+    #[test]
+    fn skill_thresholds_fall_back_to_defaults() {
+        let mut rules = ScoringRules::default();
+        rules.skills.skill_thresholds.insert(
+            "Slayer".to_string(),
+            vec![LevelThreshold { level: 85, points: 30 }],
+        );
+        rules.validate(|_| true).unwrap();
+
+        let points = |skill_id, previous_level, level| -> Vec<i32> {
+            rules
+                .skill_level_points(skill_id, previous_level, level)
+                .iter()
+                .map(|threshold| threshold.points)
+                .collect()
+        };
+        assert_eq!(points(18, 84, 85), vec![30]);
+        assert_eq!(points(18, 98, 99), Vec::<i32>::new());
+        assert_eq!(points(0, 98, 99), vec![50]);
+        assert_eq!(points(0, 97, 98), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn skill_jumps_score_every_threshold_crossed() {
+        let mut rules = ScoringRules::default();
+        rules.skills.skill_thresholds.insert(
+            "Slayer".to_string(),
+            vec![LevelThreshold { level: 85, points: 30 }, LevelThreshold { level: 90, points: 40 }],
+        );
+        rules.validate(|_| true).unwrap();
+
+        let levels = |previous_level, level| -> Vec<i32> {
+            rules
+                .skill_level_points(18, previous_level, level)
+                .iter()
+                .map(|threshold| threshold.level)
+                .collect()
+        };
+        assert_eq!(levels(84, 91), vec![85, 90]);
+        assert_eq!(levels(85, 91), vec![90]);
+        assert_eq!(levels(86, 89), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn drop_points_use_highest_matching_tier() {
+        let mut rules = ScoringRules {
+            drop_value_tiers: vec![
+                DropValueTier { min_value: 10_000_000, points: 25 },
+                DropValueTier { min_value: 1_000_000, points: 5 },
+            ],
+            ..Default::default()
+        };
+        rules.validate(|_| true).unwrap();
+
+        assert_eq!(rules.drop_points(500_000), None);
+        assert_eq!(rules.drop_points(1_000_000), Some(5));
+        assert_eq!(rules.drop_points(50_000_000), Some(25));
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let mut rules = ScoringRules::default();
+        rules.bosses.difficulty_points.insert("nightmare".to_string(), 5);
+        rules.skills.level_thresholds.push(LevelThreshold { level: 120, points: 5 });
+        rules.skills.skill_thresholds.insert("Sailing".to_string(), vec![]);
+        rules.collection_log.page_bonuses.insert("Not a page".to_string(), 10);
+        rules.drop_value_tiers = vec![
+            DropValueTier { min_value: 1_000_000, points: 5 },
+            DropValueTier { min_value: 1_000_000, points: 10 },
+        ];
+
+        let errors = rules.validate(|page| page != "Not a page").unwrap_err();
+        assert_eq!(errors.len(), 5, "{:?}", errors);
+    }
+}
+
+/// Skill names in the order the plugin reports skill ids
+pub const SKILL_NAMES: [&str; 23] = [
+    "attack", "defence", "strength", "hitpoints", "ranged", "prayer", "magic", "cooking",
+    "woodcutting", "fletching", "fishing", "firemaking", "crafting", "smithing", "mining",
+    "herblore", "agility", "thieving", "slayer", "farming", "runecraft", "hunter", "construction",
+];
+
+/// Skill names in the order members' skill xp is stored, which is alphabetical
+const STORED_SKILL_NAMES: [&str; 23] = [
+    "agility", "attack", "construction", "cooking", "crafting", "defence", "farming", "firemaking",
+    "fishing", "fletching", "herblore", "hitpoints", "hunter", "magic", "mining", "prayer", "ranged",
+    "runecraft", "slayer", "smithing", "strength", "thieving", "woodcutting",
+];
+
+/// Where overall xp sits in skills stored before it was dropped from updates
+const STORED_OVERALL_INDEX: usize = 15;
+
+/// Index of a skill in a stored skills array with `len` entries. Arrays have an entry
+/// per skill, or one more when they still include overall xp.
+pub fn stored_skill_index(skill: &str, len: usize) -> Option<usize> {
+    let index = STORED_SKILL_NAMES.iter().position(|name| *name == skill)?;
+    if len > STORED_SKILL_NAMES.len() && index >= STORED_OVERALL_INDEX {
+        Some(index + 1)
+    } else {
+        Some(index)
+    }
+}
+
+/// A member's xp in a skill from their stored skills array
+pub fn stored_skill_xp(skills: &[i32], skill: &str) -> Option<i32> {
+    stored_skill_index(skill, skills.len())
+        .and_then(|index| skills.get(index))
+        .copied()
+}
+
+const BOSS_DIFFICULTIES: [&str; 3] = ["easy", "medium", "hard"];
+const MAX_RULE_POINTS: i32 = 10_000;
+
+lazy_static! {
+    /// Rules used by groups that haven't saved their own, loaded from the scoring rules file
+    pub static ref DEFAULT_SCORING_RULES: ScoringRules = ScoringRules::load_default();
+}
+
+/// Points awarded when a skill reaches a level
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct LevelThreshold {
+    pub level: i32,
+    pub points: i32,
+}
+
+/// Points awarded per boss kill
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BossRules {
+    /// Points for bosses without a specific or difficulty entry
+    pub default_points: i32,
+
+    /// Points by reported difficulty ("easy", "medium", "hard")
+    #[serde(default)]
+    pub difficulty_points: HashMap<String, i32>,
+
+    /// Points for specific bosses, overriding the difficulty
+    #[serde(default)]
+    pub boss_points: HashMap<String, i32>,
+}
+
+/// Points awarded for reaching skill levels
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SkillRules {
+    /// Thresholds used for every skill without its own entry
+    pub level_thresholds: Vec<LevelThreshold>,
+
+    /// Per-skill thresholds, keyed by skill name
+    #[serde(default)]
+    pub skill_thresholds: HashMap<String, Vec<LevelThreshold>>,
+}
+
+/// Points awarded for collection log progress
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CollectionLogRules {
+    /// Points per newly collected item
+    pub points_per_item: i32,
+
+    /// Bonus for completing a page without its own entry
+    pub set_completion_bonus: i32,
+
+    /// Completion bonus for specific collection log pages, keyed by page name
+    #[serde(default)]
+    pub page_bonuses: HashMap<String, i32>,
+}
+
+/// Points awarded for a valuable drop worth at least `min_value`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct DropValueTier {
+    pub min_value: i64,
+    pub points: i32,
+}
+
+/// A group's scoring rules document
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ScoringRules {
+    pub bosses: BossRules,
+    pub skills: SkillRules,
+    pub collection_log: CollectionLogRules,
+    #[serde(default)]
+    pub drop_value_tiers: Vec<DropValueTier>,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        Self {
+            bosses: BossRules {
+                default_points: 10,
+                difficulty_points: HashMap::from([
+                    ("easy".to_string(), 10),
+                    ("medium".to_string(), 25),
+                    ("hard".to_string(), 50),
+                ]),
+                boss_points: HashMap::new(),
+            },
+            skills: SkillRules {
+                level_thresholds: vec![
+                    LevelThreshold { level: 92, points: 25 },
+                    LevelThreshold { level: 99, points: 50 },
+                ],
+                skill_thresholds: HashMap::new(),
+            },
+            collection_log: CollectionLogRules {
+                points_per_item: 1,
+                set_completion_bonus: 25,
+                page_bonuses: HashMap::new(),
+            },
+            drop_value_tiers: Vec::new(),
+        }
+    }
+}
+
+impl ScoringRules {
+    /// Load the server-wide default rules from `CUSTOM_SCORING_RULES_FILE`
+    /// (default `scoring_rules.json`), falling back to the built-in defaults
+    pub fn load_default() -> Self {
+        let path = std::env::var("CUSTOM_SCORING_RULES_FILE")
+            .unwrap_or_else(|_| "scoring_rules.json".to_string());
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => {
+                log::info!("No scoring rules file at {}, using built-in defaults", path);
+                return Self::default();
+            }
+        };
+
+        let mut rules: ScoringRules = match serde_json::from_str(&contents) {
+            Ok(rules) => rules,
+            Err(err) => {
+                log::error!("Invalid scoring rules file {}: {}", path, err);
+                return Self::default();
+            }
+        };
+
+        // Page names can't be checked before the collection log pages are loaded
+        match rules.validate(|_| true) {
+            Ok(_) => rules,
+            Err(errors) => {
+                log::error!("Invalid scoring rules file {}: {}", path, errors.join("; "));
+                Self::default()
+            }
+        }
+    }
+
+    /// Validate the document and normalize boss, difficulty and skill keys to lowercase.
+    /// Returns every problem found rather than stopping at the first one.
+    pub fn validate<F>(&mut self, is_known_page: F) -> Result<(), Vec<String>>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut errors = Vec::new();
+
+        check_points(&mut errors, "bosses.default_points", self.bosses.default_points);
+
+        let mut difficulty_points = HashMap::new();
+        for (difficulty, points) in self.bosses.difficulty_points.drain() {
+            let key = difficulty.trim().to_lowercase();
+            if !BOSS_DIFFICULTIES.contains(&key.as_str()) {
+                errors.push(format!("bosses.difficulty_points: unknown difficulty '{}'", difficulty));
+            }
+            check_points(&mut errors, &format!("bosses.difficulty_points.{}", key), points);
+            difficulty_points.insert(key, points);
+        }
+        self.bosses.difficulty_points = difficulty_points;
+
+        let mut boss_points = HashMap::new();
+        for (boss_name, points) in self.bosses.boss_points.drain() {
+            let key = boss_name.trim().to_lowercase();
+            if key.is_empty() {
+                errors.push("bosses.boss_points: boss name cannot be empty".to_string());
+            }
+            check_points(&mut errors, &format!("bosses.boss_points.{}", key), points);
+            boss_points.insert(key, points);
+        }
+        self.bosses.boss_points = boss_points;
+
+        check_thresholds(&mut errors, "skills.level_thresholds", &mut self.skills.level_thresholds);
+
+        let mut skill_thresholds = HashMap::new();
+        for (skill, mut thresholds) in self.skills.skill_thresholds.drain() {
+            let key = skill.trim().to_lowercase();
+            if !SKILL_NAMES.contains(&key.as_str()) {
+                errors.push(format!("skills.skill_thresholds: unknown skill '{}'", skill));
+            }
+            check_thresholds(&mut errors, &format!("skills.skill_thresholds.{}", key), &mut thresholds);
+            skill_thresholds.insert(key, thresholds);
+        }
+        self.skills.skill_thresholds = skill_thresholds;
+
+        check_points(&mut errors, "collection_log.points_per_item", self.collection_log.points_per_item);
+        check_points(&mut errors, "collection_log.set_completion_bonus", self.collection_log.set_completion_bonus);
+        for (page_name, points) in &self.collection_log.page_bonuses {
+            if !is_known_page(page_name) {
+                errors.push(format!("collection_log.page_bonuses: unknown collection log page '{}'", page_name));
+            }
+            check_points(&mut errors, &format!("collection_log.page_bonuses.{}", page_name), *points);
+        }
+
+        self.drop_value_tiers.sort_by_key(|tier| tier.min_value);
+        let mut previous_min_value = 0;
+        for tier in &self.drop_value_tiers {
+            if tier.min_value <= previous_min_value {
+                errors.push(format!(
+                    "drop_value_tiers: min_value {} must be positive and unique",
+                    tier.min_value
+                ));
+            }
+            check_points(&mut errors, &format!("drop_value_tiers.{}", tier.min_value), tier.points);
+            previous_min_value = tier.min_value;
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Points for a single kill of `boss_name`
+    pub fn boss_kill_points(&self, boss_name: &str, difficulty: Option<&str>) -> i32 {
+        if let Some(points) = self.bosses.boss_points.get(&boss_name.trim().to_lowercase()) {
+            return *points;
+        }
+
+        difficulty
+            .and_then(|difficulty| self.bosses.difficulty_points.get(&difficulty.to_lowercase()))
+            .copied()
+            .unwrap_or(self.bosses.default_points)
+    }
+
+    /// Scored levels in the skill with id `skill_id` that a member passed going from
+    /// `previous_level` to `level`, so a jump of several levels scores every threshold it crosses
+    pub fn skill_level_points(&self, skill_id: i32, previous_level: i32, level: i32) -> Vec<LevelThreshold> {
+        let thresholds = usize::try_from(skill_id)
+            .ok()
+            .and_then(|index| SKILL_NAMES.get(index))
+            .and_then(|skill| self.skills.skill_thresholds.get(*skill))
+            .unwrap_or(&self.skills.level_thresholds);
+
+        thresholds
+            .iter()
+            .filter(|threshold| previous_level < threshold.level && threshold.level <= level)
+            .cloned()
+            .collect()
+    }
+
+    /// Points per newly collected collection log item
+    pub fn collection_item_points(&self) -> i32 {
+        self.collection_log.points_per_item
+    }
+
+    /// Bonus for completing the collection log page `page_name`
+    pub fn collection_page_bonus(&self, page_name: &str) -> i32 {
+        self.collection_log
+            .page_bonuses
+            .get(page_name)
+            .copied()
+            .unwrap_or(self.collection_log.set_completion_bonus)
+    }
+
+    /// Points for a drop with a total value of `total_value`, if it reaches a tier
+    pub fn drop_points(&self, total_value: i64) -> Option<i32> {
+        self.drop_value_tiers
+            .iter()
+            .rev()
+            .find(|tier| total_value >= tier.min_value)
+            .map(|tier| tier.points)
+    }
+}
+
+fn check_points(errors: &mut Vec<String>, field: &str, points: i32) {
+    if !(0..=MAX_RULE_POINTS).contains(&points) {
+        errors.push(format!("{}: points must be between 0 and {}", field, MAX_RULE_POINTS));
+    }
+}
+
+fn check_thresholds(errors: &mut Vec<String>, field: &str, thresholds: &mut [LevelThreshold]) {
+    thresholds.sort_by_key(|threshold| threshold.level);
+
+    let mut seen = HashSet::new();
+    for threshold in thresholds.iter() {
+        if !(2..=99).contains(&threshold.level) {
+            errors.push(format!("{}: level {} must be between 2 and 99", field, threshold.level));
+        }
+        if !seen.insert(threshold.level) {
+            errors.push(format!("{}: level {} is listed more than once", field, threshold.level));
+        }
+        check_points(errors, &format!("{}.{}", field, threshold.level), threshold.points);
+    }
+}
+
+/// Get the scoring rules for a group, or the server defaults if it hasn't saved any
+pub async fn get_group_rules<C: GenericClient>(
+    client: &C,
+    group_id: i64,
+) -> Result<ScoringRules, ApiError> {
+    let stmt = client
+        .prepare_cached("SELECT rules FROM groupironman.group_scoring_rules WHERE group_id = $1")
+        .await?;
+
+    match client.query_opt(&stmt, &[&group_id]).await? {
+        Some(row) => {
+            let rules: serde_json::Value = row.try_get(0)?;
+            Ok(serde_json::from_value(rules)?)
+        }
+        None => Ok(DEFAULT_SCORING_RULES.clone()),
+    }
+}
+
+/// Request to replace a group's scoring rules
+#[derive(Deserialize)]
+pub struct UpdateScoringRulesRequest {
+    /// The new rules document
+    pub rules: ScoringRules,
+
+    /// Member who made the change
+    pub updated_by: Option<String>,
+}
+
+/// Response for scoring rules requests
+#[derive(Serialize)]
+pub struct ScoringRulesResponse {
+    /// The rules in effect for the group
+    pub rules: ScoringRules,
+
+    /// Whether the group is using the server defaults
+    pub is_default: bool,
+
+    /// Member who last changed the rules
+    pub updated_by: Option<String>,
+
+    /// When the rules were last changed
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Get the scoring rules in effect for the group
+pub async fn get_scoring_rules(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+
+    let stmt = client
+        .prepare_cached(
+            "SELECT rules, updated_by, updated_at FROM groupironman.group_scoring_rules WHERE group_id = $1"
+        )
+        .await?;
+
+    let response = match client.query_opt(&stmt, &[&auth.group_id]).await? {
+        Some(row) => {
+            let rules: serde_json::Value = row.try_get(0)?;
+            ScoringRulesResponse {
+                rules: serde_json::from_value(rules)?,
+                is_default: false,
+                updated_by: row.try_get(1)?,
+                updated_at: row.try_get(2)?,
+            }
+        }
+        None => ScoringRulesResponse {
+            rules: DEFAULT_SCORING_RULES.clone(),
+            is_default: true,
+            updated_by: None,
+            updated_at: None,
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Validate and save the group's scoring rules
+pub async fn update_scoring_rules(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    collection_log_info: web::Data<CollectionLogInfo>,
+    request: web::Json<UpdateScoringRulesRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let request = request.into_inner();
+    let mut rules = request.rules;
+
+    if let Err(errors) = rules.validate(|page| {
+        collection_log_info.page_name_to_id(&page.to_string()).is_some()
+    }) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "Scoring rules are invalid",
+            "errors": errors
+        })));
+    }
+
+    let client = pool.get().await?;
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO groupironman.group_scoring_rules (group_id, rules, updated_by, updated_at)
+             VALUES ($1, $2, $3, NOW())
+             ON CONFLICT (group_id)
+             DO UPDATE SET rules = $2, updated_by = $3, updated_at = NOW()
+             RETURNING updated_at"
+        )
+        .await?;
+
+    let rules_json = serde_json::to_value(&rules)?;
+    let updated_at: DateTime<Utc> = client
+        .query_one(&stmt, &[&auth.group_id, &rules_json, &request.updated_by])
+        .await?
+        .try_get(0)?;
+
+    Ok(HttpResponse::Ok().json(ScoringRulesResponse {
+        rules,
+        is_default: false,
+        updated_by: request.updated_by,
+        updated_at: Some(updated_at),
+    }))
+}
+
+/// Discard the group's scoring rules and go back to the server defaults
+pub async fn reset_scoring_rules(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let stmt = client
+        .prepare_cached("DELETE FROM groupironman.group_scoring_rules WHERE group_id = $1")
+        .await?;
+    client.execute(&stmt, &[&auth.group_id]).await?;
+
+    Ok(HttpResponse::Ok().json(ScoringRulesResponse {
+        rules: DEFAULT_SCORING_RULES.clone(),
+        is_default: true,
+        updated_by: None,
+        updated_at: None,
+    }))
+}
@@ -0,0 +1,583 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::error::ApiError;
+use crate::leaderboards::competition_ranks;
+use crate::models::SHARED_MEMBER;
+use crate::time_zones::{group_time_zone, local_to_utc};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use chrono_tz::Tz;
+use deadpool_postgres::{Client, GenericClient, Pool};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use tokio::{task, time};
+
+#[cfg(test)]
+mod season_tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn weekly_rollover_adds_seven_days() {
+        let end = Utc.with_ymd_and_hms(2024, 6, 3, 0, 0, 0).unwrap();
+        assert_eq!(
+            SeasonRollover::Weekly.next_end(end, end, Tz::UTC),
+            Utc.with_ymd_and_hms(2024, 6, 10, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn monthly_rollover_clamps_to_month_end() {
+        let end = Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap();
+        assert_eq!(
+            SeasonRollover::Monthly.next_end(end, end, Tz::UTC),
+            Utc.with_ymd_and_hms(2024, 2, 29, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn monthly_rollover_returns_to_anchor_day() {
+        let anchor = Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap();
+        let mut end = anchor;
+        let mut ends = Vec::new();
+        for _ in 0..4 {
+            end = SeasonRollover::Monthly.next_end(anchor, end, Tz::UTC);
+            ends.push(end);
+        }
+        assert_eq!(
+            ends,
+            vec![
+                Utc.with_ymd_and_hms(2024, 2, 29, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 3, 31, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 4, 30, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 5, 31, 0, 0, 0).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn rollover_keeps_local_midnight_across_dst() {
+        // Clocks go forward in London on 2024-03-31
+        let end = Utc.with_ymd_and_hms(2024, 3, 25, 0, 0, 0).unwrap();
+        assert_eq!(
+            SeasonRollover::Weekly.next_end(end, end, chrono_tz::Europe::London),
+            Utc.with_ymd_and_hms(2024, 3, 31, 23, 0, 0).unwrap()
+        );
+        assert_eq!(
+            SeasonRollover::Monthly.next_end(end, end, chrono_tz::Europe::London),
+            Utc.with_ymd_and_hms(2024, 4, 24, 23, 0, 0).unwrap()
+        );
+    }
+}
+
+/// How the next season is created when a season ends
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SeasonRollover {
+    Weekly,
+    Monthly,
+}
+
+impl SeasonRollover {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SeasonRollover::Weekly => "weekly",
+            SeasonRollover::Monthly => "monthly",
+        }
+    }
+
+    /// End of the season that starts at `start`, in a series whose first boundary was
+    /// `anchor`. Monthly seasons end on the anchor's day of the month, clamped to shorter
+    /// months, so a series anchored on the 31st doesn't stay on the 28th after February.
+    /// Seasons keep the same local time of day in the group's time zone when clocks change.
+    pub fn next_end(&self, anchor: DateTime<Utc>, start: DateTime<Utc>, tz: Tz) -> DateTime<Utc> {
+        let local_start = start.with_timezone(&tz).naive_local();
+        match self {
+            SeasonRollover::Weekly => local_to_utc(tz, local_start + Duration::days(7)),
+            SeasonRollover::Monthly => {
+                let local_anchor = anchor.with_timezone(&tz).naive_local();
+                let elapsed_months = (local_start.year() - local_anchor.year()) * 12
+                    + local_start.month() as i32
+                    - local_anchor.month() as i32;
+                let mut months = elapsed_months.max(0) as u32;
+                loop {
+                    months += 1;
+                    let end = match local_anchor.checked_add_months(Months::new(months)) {
+                        Some(local_end) => local_to_utc(tz, local_end),
+                        None => return local_to_utc(tz, local_start + Duration::days(30)),
+                    };
+                    if end > start {
+                        return end;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for SeasonRollover {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "weekly" => Ok(SeasonRollover::Weekly),
+            "monthly" => Ok(SeasonRollover::Monthly),
+            _ => Err(format!("Unknown season rollover '{}'", value)),
+        }
+    }
+}
+
+/// A group season
+#[derive(Serialize)]
+pub struct Season {
+    season_id: i64,
+    season_number: i32,
+    name: String,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    rollover: Option<SeasonRollover>,
+    /// Set once the final standings have been archived
+    archived_at: Option<DateTime<Utc>>,
+}
+
+impl Season {
+    pub fn starts_at(&self) -> DateTime<Utc> {
+        self.starts_at
+    }
+
+    pub fn ends_at(&self) -> DateTime<Utc> {
+        self.ends_at
+    }
+}
+
+/// A member's standing in a season
+#[derive(Serialize)]
+pub struct SeasonStanding {
+    rank: i64,
+    member_name: String,
+    total_points: i64,
+    points_by_type: HashMap<String, i64>,
+}
+
+/// A season with its standings
+#[derive(Serialize)]
+pub struct SeasonStandings {
+    season: Season,
+    /// Whether the standings are the archived final standings or computed live
+    is_final: bool,
+    standings: Vec<SeasonStanding>,
+}
+
+/// Request to create a season
+#[derive(Deserialize)]
+pub struct CreateSeasonRequest {
+    /// Defaults to "Season N"
+    name: Option<String>,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    rollover: Option<SeasonRollover>,
+}
+
+const SEASON_COLUMNS: &str =
+    "season_id, season_number, name, starts_at, ends_at, rollover, archived_at";
+
+fn season_from_row(row: &tokio_postgres::Row) -> Result<Season, tokio_postgres::Error> {
+    let rollover: Option<String> = row.try_get(5)?;
+    Ok(Season {
+        season_id: row.try_get(0)?,
+        season_number: row.try_get(1)?,
+        name: row.try_get(2)?,
+        starts_at: row.try_get(3)?,
+        ends_at: row.try_get(4)?,
+        rollover: rollover.and_then(|rollover| rollover.parse().ok()),
+        archived_at: row.try_get(6)?,
+    })
+}
+
+/// Find a group's season by id, or the season running now when `season` is "current"
+pub async fn find_season<C: GenericClient>(
+    client: &C,
+    group_id: i64,
+    season: &str,
+) -> Result<Option<Season>, ApiError> {
+    let row = if season == "current" {
+        let stmt = client
+            .prepare_cached(&format!(
+                "SELECT {} FROM groupironman.seasons
+                 WHERE group_id = $1 AND starts_at <= NOW() AND ends_at > NOW()
+                 ORDER BY starts_at DESC LIMIT 1",
+                SEASON_COLUMNS
+            ))
+            .await?;
+        client.query_opt(&stmt, &[&group_id]).await?
+    } else {
+        let season_id: i64 = match season.parse() {
+            Ok(season_id) => season_id,
+            Err(_) => return Ok(None),
+        };
+        let stmt = client
+            .prepare_cached(&format!(
+                "SELECT {} FROM groupironman.seasons WHERE group_id = $1 AND season_id = $2",
+                SEASON_COLUMNS
+            ))
+            .await?;
+        client.query_opt(&stmt, &[&group_id, &season_id]).await?
+    };
+
+    Ok(row.as_ref().map(season_from_row).transpose()?)
+}
+
+/// Rank the group's members by the points they earned in `[starts_at, ends_at)`
+pub async fn compute_standings<C: GenericClient>(
+    client: &C,
+    group_id: i64,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+) -> Result<Vec<SeasonStanding>, ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "SELECT m.member_name, e.point_type, COALESCE(SUM(e.points), 0)::BIGINT
+             FROM groupironman.members m
+             LEFT JOIN groupironman.point_events e
+                ON e.member_id = m.member_id
+                AND e.created_at >= $2
+                AND e.created_at < $3
+             WHERE m.group_id = $1 AND m.member_name != $4
+             GROUP BY m.member_name, e.point_type"
+        )
+        .await?;
+
+    let rows = client
+        .query(&stmt, &[&group_id, &starts_at, &ends_at, &SHARED_MEMBER])
+        .await?;
+
+    let mut points_by_member: HashMap<String, HashMap<String, i64>> = HashMap::new();
+    for row in rows {
+        let member_name: String = row.try_get(0)?;
+        let point_type: Option<String> = row.try_get(1)?;
+        let points: i64 = row.try_get(2)?;
+
+        let member_points = points_by_member.entry(member_name).or_default();
+        if let Some(point_type) = point_type {
+            member_points.insert(point_type, points);
+        }
+    }
+
+    let totals: Vec<(String, i64)> = points_by_member
+        .iter()
+        .map(|(member_name, points)| (member_name.clone(), points.values().sum()))
+        .collect();
+    let ranks = competition_ranks(&totals);
+
+    let mut standings: Vec<SeasonStanding> = totals
+        .into_iter()
+        .map(|(member_name, total_points)| SeasonStanding {
+            rank: ranks[&member_name],
+            points_by_type: points_by_member.remove(&member_name).unwrap_or_default(),
+            member_name,
+            total_points,
+        })
+        .collect();
+    standings.sort_by(|a, b| a.rank.cmp(&b.rank).then_with(|| a.member_name.cmp(&b.member_name)));
+
+    Ok(standings)
+}
+
+/// List the group's seasons, newest first
+pub async fn get_seasons(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let stmt = client
+        .prepare_cached(&format!(
+            "SELECT {} FROM groupironman.seasons WHERE group_id = $1 ORDER BY starts_at DESC",
+            SEASON_COLUMNS
+        ))
+        .await?;
+
+    let seasons: Vec<Season> = client
+        .query(&stmt, &[&auth.group_id])
+        .await?
+        .iter()
+        .map(season_from_row)
+        .collect::<Result<_, tokio_postgres::Error>>()?;
+
+    Ok(HttpResponse::Ok().json(seasons))
+}
+
+/// Create a season. Seasons in a group cannot overlap.
+pub async fn create_season(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    request: web::Json<CreateSeasonRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if request.ends_at <= request.starts_at {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "Season must end after it starts"
+        })));
+    }
+
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+
+    // Serialize season creation per group so the overlap check holds
+    let lock_stmt = transaction
+        .prepare_cached("SELECT group_id FROM groupironman.groups WHERE group_id = $1 FOR UPDATE")
+        .await?;
+    transaction.query_one(&lock_stmt, &[&auth.group_id]).await?;
+
+    if has_overlapping_season(&transaction, auth.group_id, request.starts_at, request.ends_at).await? {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": "Season overlaps an existing season"
+        })));
+    }
+
+    let season = insert_season(
+        &transaction,
+        auth.group_id,
+        request.name.as_deref(),
+        request.starts_at,
+        request.ends_at,
+        request.rollover,
+        request.ends_at,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Created().json(season))
+}
+
+/// Get the season running now with its live standings
+pub async fn get_current_season(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+
+    match find_season(&client, auth.group_id, "current").await? {
+        Some(season) => {
+            let standings = compute_standings(&client, auth.group_id, season.starts_at, season.ends_at).await?;
+            Ok(HttpResponse::Ok().json(SeasonStandings {
+                season,
+                is_final: false,
+                standings,
+            }))
+        }
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "No season is running"
+        }))),
+    }
+}
+
+/// Get a season's standings: the archived final standings once it has ended, otherwise live
+pub async fn get_season_standings(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, season_id) = path.into_inner();
+    let client = pool.get().await?;
+
+    let season = match find_season(&client, auth.group_id, &season_id.to_string()).await? {
+        Some(season) => season,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "status": "error",
+                "message": "Season not found or not in your group"
+            })));
+        }
+    };
+
+    if season.archived_at.is_none() {
+        let standings = compute_standings(&client, auth.group_id, season.starts_at, season.ends_at).await?;
+        return Ok(HttpResponse::Ok().json(SeasonStandings {
+            season,
+            is_final: false,
+            standings,
+        }));
+    }
+
+    let stmt = client
+        .prepare_cached(
+            "SELECT rank, member_name, total_points, points_by_type
+             FROM groupironman.season_standings
+             WHERE season_id = $1
+             ORDER BY rank, member_name"
+        )
+        .await?;
+
+    let mut standings = Vec::new();
+    for row in client.query(&stmt, &[&season_id]).await? {
+        let points_by_type: serde_json::Value = row.try_get(3)?;
+        standings.push(SeasonStanding {
+            rank: row.try_get(0)?,
+            member_name: row.try_get(1)?,
+            total_points: row.try_get(2)?,
+            points_by_type: serde_json::from_value(points_by_type)?,
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(SeasonStandings {
+        season,
+        is_final: true,
+        standings,
+    }))
+}
+
+async fn has_overlapping_season<C: GenericClient>(
+    client: &C,
+    group_id: i64,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+) -> Result<bool, ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "SELECT EXISTS (
+                SELECT 1 FROM groupironman.seasons
+                WHERE group_id = $1 AND starts_at < $3 AND ends_at > $2
+             )"
+        )
+        .await?;
+
+    Ok(client.query_one(&stmt, &[&group_id, &starts_at, &ends_at]).await?.try_get(0)?)
+}
+
+async fn insert_season<C: GenericClient>(
+    client: &C,
+    group_id: i64,
+    name: Option<&str>,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    rollover: Option<SeasonRollover>,
+    rollover_anchor: DateTime<Utc>,
+) -> Result<Season, ApiError> {
+    let number_stmt = client
+        .prepare_cached(
+            "SELECT COALESCE(MAX(season_number), 0) + 1 FROM groupironman.seasons WHERE group_id = $1"
+        )
+        .await?;
+    let season_number: i32 = client.query_one(&number_stmt, &[&group_id]).await?.try_get(0)?;
+
+    let name = match name.map(str::trim) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => format!("Season {}", season_number),
+    };
+
+    let stmt = client
+        .prepare_cached(&format!(
+            "INSERT INTO groupironman.seasons (group_id, season_number, name, starts_at, ends_at, rollover, rollover_anchor)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING {}",
+            SEASON_COLUMNS
+        ))
+        .await?;
+
+    // Only seasons that roll over belong to a series
+    let rollover_anchor = rollover.map(|_| rollover_anchor);
+    let rollover = rollover.map(|rollover| rollover.as_str());
+    let row = client
+        .query_one(&stmt, &[&group_id, &season_number, &name, &starts_at, &ends_at, &rollover, &rollover_anchor])
+        .await?;
+
+    Ok(season_from_row(&row)?)
+}
+
+/// Archive the final standings of every season that has ended and start the next
+/// season for those that roll over
+async fn roll_over_seasons(client: &mut Client) -> Result<(), ApiError> {
+    loop {
+        let transaction = client.transaction().await?;
+
+        let stmt = transaction
+            .prepare_cached(&format!(
+                "SELECT group_id, {}, COALESCE(rollover_anchor, ends_at) FROM groupironman.seasons
+                 WHERE archived_at IS NULL AND ends_at <= NOW()
+                 ORDER BY ends_at
+                 LIMIT 1
+                 FOR UPDATE SKIP LOCKED",
+                SEASON_COLUMNS
+            ))
+            .await?;
+
+        let row = match transaction.query_opt(&stmt, &[]).await? {
+            Some(row) => row,
+            None => return Ok(()),
+        };
+        let group_id: i64 = row.try_get(0)?;
+        let season_id: i64 = row.try_get(1)?;
+        let ends_at: DateTime<Utc> = row.try_get(5)?;
+        let rollover: Option<String> = row.try_get(6)?;
+        let starts_at: DateTime<Utc> = row.try_get(4)?;
+        let rollover_anchor: DateTime<Utc> = row.try_get(8)?;
+
+        let standings = compute_standings(&transaction, group_id, starts_at, ends_at).await?;
+        let insert_stmt = transaction
+            .prepare_cached(
+                "INSERT INTO groupironman.season_standings
+                 (season_id, member_name, rank, total_points, points_by_type)
+                 VALUES ($1, $2, $3, $4, $5)"
+            )
+            .await?;
+        for standing in &standings {
+            let points_by_type = serde_json::to_value(&standing.points_by_type)?;
+            transaction
+                .execute(
+                    &insert_stmt,
+                    &[
+                        &season_id,
+                        &standing.member_name,
+                        &standing.rank,
+                        &standing.total_points,
+                        &points_by_type,
+                    ],
+                )
+                .await?;
+        }
+
+        let archive_stmt = transaction
+            .prepare_cached("UPDATE groupironman.seasons SET archived_at = NOW() WHERE season_id = $1")
+            .await?;
+        transaction.execute(&archive_stmt, &[&season_id]).await?;
+
+        // Start the next season unless the group already scheduled one for that window
+        if let Some(rollover) = rollover.and_then(|rollover| rollover.parse::<SeasonRollover>().ok()) {
+            let tz = group_time_zone(&transaction, group_id).await?;
+            let next_ends_at = rollover.next_end(rollover_anchor, ends_at, tz);
+            if !has_overlapping_season(&transaction, group_id, ends_at, next_ends_at).await? {
+                insert_season(&transaction, group_id, None, ends_at, next_ends_at, Some(rollover), rollover_anchor).await?;
+            }
+        }
+
+        transaction.commit().await?;
+        log::info!("Archived standings for season {} of group {}", season_id, group_id);
+    }
+}
+
+pub fn start_season_rollover(db_pool: Pool) {
+    task::spawn(async move {
+        let mut interval = time::interval(std::time::Duration::from_secs(60));
+
+        loop {
+            interval.tick().await;
+
+            match db_pool.get().await {
+                Ok(mut client) => {
+                    match roll_over_seasons(&mut client).await {
+                        Ok(_) => (),
+                        Err(err) => {
+                            log::error!("Failed to roll over seasons: {}", err);
+                        }
+                    }
+                }
+                Err(err) => {
+                    log::error!("Failed to get db client: {}", err);
+                }
+            }
+        }
+    });
+}
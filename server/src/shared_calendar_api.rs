@@ -1,10 +1,287 @@
-use actix_web::{web, HttpResponse, Responder};
+use crate::auth_middleware::AuthedGroupId;
+use crate::calendar_rsvp_api::{load_headcounts, Headcount};
+use crate::error::ApiError;
+use crate::time_zones::{group_time_zone, local_to_utc};
+use actix_web::{web, HttpResponse};
 use deadpool_postgres::{Client, Pool};
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
-use std::error::Error;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tokio_postgres::Row;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[cfg(test)]
+mod recurrence_tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+    }
+
+    fn rule(frequency: RecurrenceFrequency, interval: i32) -> RecurrenceRule {
+        RecurrenceRule {
+            frequency,
+            interval,
+            until: None,
+            count: None,
+            exceptions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn weekly_occurrences_in_window() {
+        let weekly = rule(RecurrenceFrequency::Weekly, 1);
+        let occurrences = weekly.occurrences(at(2024, 5, 6, 19), Duration::hours(2), at(2024, 5, 10, 0), at(2024, 5, 31, 0), Tz::UTC);
+        assert_eq!(occurrences, vec![at(2024, 5, 13, 19), at(2024, 5, 20, 19), at(2024, 5, 27, 19)]);
+
+        let fortnightly = rule(RecurrenceFrequency::Weekly, 2);
+        let occurrences = fortnightly.occurrences(at(2024, 5, 6, 19), Duration::hours(2), at(2024, 5, 10, 0), at(2024, 5, 31, 0), Tz::UTC);
+        assert_eq!(occurrences, vec![at(2024, 5, 20, 19)]);
+    }
+
+    #[test]
+    fn occurrences_still_running_at_window_start_are_included() {
+        let daily = rule(RecurrenceFrequency::Daily, 1);
+        let occurrences = daily.occurrences(at(2024, 5, 1, 19), Duration::hours(3), at(2024, 5, 6, 20), at(2024, 5, 7, 0), Tz::UTC);
+        assert_eq!(occurrences, vec![at(2024, 5, 6, 19)]);
+    }
+
+    #[test]
+    fn count_and_until_end_the_series() {
+        let mut daily = rule(RecurrenceFrequency::Daily, 1);
+        daily.count = Some(3);
+        daily.exceptions = vec![at(2024, 5, 2, 19)];
+        // Removed occurrences still count towards the total
+        let occurrences = daily.occurrences(at(2024, 5, 1, 19), Duration::zero(), at(2024, 4, 1, 0), at(2024, 6, 1, 0), Tz::UTC);
+        assert_eq!(occurrences, vec![at(2024, 5, 1, 19), at(2024, 5, 3, 19)]);
+
+        let mut daily = rule(RecurrenceFrequency::Daily, 1);
+        daily.until = Some(at(2024, 5, 2, 19));
+        let occurrences = daily.occurrences(at(2024, 5, 1, 19), Duration::zero(), at(2024, 4, 1, 0), at(2024, 6, 1, 0), Tz::UTC);
+        assert_eq!(occurrences, vec![at(2024, 5, 1, 19), at(2024, 5, 2, 19)]);
+    }
+
+    #[test]
+    fn monthly_skips_months_without_the_day() {
+        let monthly = rule(RecurrenceFrequency::Monthly, 1);
+        let occurrences = monthly.occurrences(at(2024, 1, 31, 12), Duration::zero(), at(2024, 1, 1, 0), at(2024, 6, 1, 0), Tz::UTC);
+        assert_eq!(occurrences, vec![at(2024, 1, 31, 12), at(2024, 3, 31, 12), at(2024, 5, 31, 12)]);
+    }
+
+    #[test]
+    fn occurrences_keep_their_local_time_across_dst() {
+        // Clocks go forward in London on 2024-03-31
+        let weekly = rule(RecurrenceFrequency::Weekly, 1);
+        let occurrences = weekly.occurrences(at(2024, 3, 25, 19), Duration::zero(), at(2024, 3, 20, 0), at(2024, 4, 5, 0), chrono_tz::Europe::London);
+        assert_eq!(occurrences, vec![at(2024, 3, 25, 19), at(2024, 4, 1, 18)]);
+        assert!(weekly.has_occurrence(at(2024, 3, 25, 19), at(2024, 4, 1, 18), chrono_tz::Europe::London));
+        assert!(!weekly.has_occurrence(at(2024, 3, 25, 19), at(2024, 4, 1, 19), chrono_tz::Europe::London));
+    }
+
+    #[test]
+    fn rules_are_written_as_rrules() {
+        let mut weekly = rule(RecurrenceFrequency::Weekly, 2);
+        assert_eq!(weekly.to_rrule(false), "FREQ=WEEKLY;INTERVAL=2");
+        weekly.until = Some(at(2024, 6, 1, 18));
+        assert_eq!(weekly.to_rrule(false), "FREQ=WEEKLY;INTERVAL=2;UNTIL=20240601T180000Z");
+        assert_eq!(weekly.to_rrule(true), "FREQ=WEEKLY;INTERVAL=2;UNTIL=20240601");
+    }
+}
+
+/// Longest gap allowed between occurrences, in days, weeks or months
+const MAX_RECURRENCE_INTERVAL: i32 = 365;
+
+/// Most occurrences a series can have when it ends after a count
+const MAX_RECURRENCE_COUNT: i32 = 1000;
+
+/// Most occurrences returned for one series in a single request
+const MAX_EXPANDED_OCCURRENCES: usize = 1000;
+
+/// Longest date window events can be listed for
+const MAX_EVENT_WINDOW_DAYS: i64 = 366;
+
+/// How often a recurring event repeats
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecurrenceFrequency {
+    Daily,
+    Weekly,
+    /// On the same day of the month; months without that day are skipped
+    Monthly,
+}
+
+impl RecurrenceFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecurrenceFrequency::Daily => "daily",
+            RecurrenceFrequency::Weekly => "weekly",
+            RecurrenceFrequency::Monthly => "monthly",
+        }
+    }
+}
+
+impl FromStr for RecurrenceFrequency {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "daily" => Ok(RecurrenceFrequency::Daily),
+            "weekly" => Ok(RecurrenceFrequency::Weekly),
+            "monthly" => Ok(RecurrenceFrequency::Monthly),
+            _ => Err(format!("Unknown recurrence frequency '{}'", value)),
+        }
+    }
+}
+
+fn default_interval() -> i32 {
+    1
+}
+
+/// When a recurring event repeats. Occurrences start at the event's start_time
+/// and last as long as the event does.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: RecurrenceFrequency,
+    /// Repeat every `interval` days, weeks or months
+    #[serde(default = "default_interval")]
+    pub interval: i32,
+    /// No occurrences start after this
+    pub until: Option<DateTime<Utc>>,
+    /// Number of occurrences in the series, including removed ones
+    pub count: Option<i32>,
+    /// Start times of occurrences removed from the series
+    #[serde(default)]
+    pub exceptions: Vec<DateTime<Utc>>,
+}
+
+impl RecurrenceRule {
+    /// Start of the `n`th occurrence, or None for a monthly occurrence that falls in a month without the day.
+    /// Occurrences repeat at the same local time in the group's time zone.
+    fn nth_occurrence(&self, start: DateTime<Utc>, n: i64, tz: Tz) -> Option<DateTime<Utc>> {
+        let steps = n * self.interval as i64;
+        let local_start = start.with_timezone(&tz).naive_local();
+        let local = match self.frequency {
+            RecurrenceFrequency::Daily => local_start + Duration::days(steps),
+            RecurrenceFrequency::Weekly => local_start + Duration::weeks(steps),
+            RecurrenceFrequency::Monthly => {
+                let months = local_start.month0() as i64 + steps;
+                let year = local_start.year() + (months / 12) as i32;
+                let date = NaiveDate::from_ymd_opt(year, (months % 12) as u32 + 1, local_start.day())?;
+                date.and_time(local_start.time())
+            }
+        };
+        Some(local_to_utc(tz, local))
+    }
+
+    /// Index of an occurrence starting at or before `time`, to skip ahead without walking the whole series
+    fn index_before(&self, start: DateTime<Utc>, time: DateTime<Utc>) -> i64 {
+        if time <= start {
+            return 0;
+        }
+        let units = match self.frequency {
+            RecurrenceFrequency::Daily => (time - start).num_days(),
+            RecurrenceFrequency::Weekly => (time - start).num_weeks(),
+            RecurrenceFrequency::Monthly => {
+                (time.year() - start.year()) as i64 * 12 + time.month0() as i64 - start.month0() as i64
+            }
+        };
+        (units / self.interval as i64 - 1).max(0)
+    }
+
+    /// Start times of the occurrences that overlap the window, for a series starting
+    /// at `start` whose occurrences last `duration`
+    pub fn occurrences(
+        &self,
+        start: DateTime<Utc>,
+        duration: Duration,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+        tz: Tz,
+    ) -> Vec<DateTime<Utc>> {
+        // With a count every occurrence from the first has to be seen to know where the series ends
+        let first = match self.count {
+            Some(_) => 0,
+            None => self.index_before(start, window_start - duration),
+        };
+        let last = first + MAX_RECURRENCE_COUNT as i64 + MAX_EXPANDED_OCCURRENCES as i64 * 12;
+
+        let mut found = Vec::new();
+        let mut seen = 0;
+        for n in first..last {
+            let occurrence = match self.nth_occurrence(start, n, tz) {
+                Some(occurrence) => occurrence,
+                None => continue,
+            };
+            if let Some(count) = self.count {
+                if seen >= count {
+                    break;
+                }
+                seen += 1;
+            }
+            if occurrence >= window_end || self.until.is_some_and(|until| occurrence > until) {
+                break;
+            }
+
+            let overlaps = occurrence >= window_start || occurrence + duration > window_start;
+            if overlaps && !self.exceptions.contains(&occurrence) {
+                found.push(occurrence);
+                if found.len() >= MAX_EXPANDED_OCCURRENCES {
+                    break;
+                }
+            }
+        }
+        found
+    }
+
+    /// Whether an occurrence of the series starts at `time`
+    pub fn has_occurrence(&self, start: DateTime<Utc>, time: DateTime<Utc>, tz: Tz) -> bool {
+        self.occurrences(start, Duration::zero(), time, time + Duration::seconds(1), tz)
+            .contains(&time)
+    }
+
+    /// The rule as an iCalendar RRULE value. UNTIL is a date for all-day events.
+    pub fn to_rrule(&self, all_day: bool) -> String {
+        let mut rule = format!("FREQ={};INTERVAL={}", self.frequency.as_str().to_uppercase(), self.interval);
+        if let Some(count) = self.count {
+            rule.push_str(&format!(";COUNT={}", count));
+        }
+        if let Some(until) = self.until {
+            let until = if all_day {
+                until.format("%Y%m%d")
+            } else {
+                until.format("%Y%m%dT%H%M%SZ")
+            };
+            rule.push_str(&format!(";UNTIL={}", until));
+        }
+        rule
+    }
+}
+
+// Check a recurrence rule for a series starting at `start`, returning the reason it's invalid
+fn validate_recurrence(rule: &RecurrenceRule, start: DateTime<Utc>) -> Option<String> {
+    if rule.interval < 1 || rule.interval > MAX_RECURRENCE_INTERVAL {
+        return Some(format!("Recurrence interval must be between 1 and {}", MAX_RECURRENCE_INTERVAL));
+    }
+    if rule.until.is_some() && rule.count.is_some() {
+        return Some("A recurrence can end on a date or after a count, not both".to_string());
+    }
+    if let Some(count) = rule.count {
+        if !(1..=MAX_RECURRENCE_COUNT).contains(&count) {
+            return Some(format!("Recurrence count must be between 1 and {}", MAX_RECURRENCE_COUNT));
+        }
+    }
+    if rule.until.is_some_and(|until| until < start) {
+        return Some("Recurrence must not end before the event starts".to_string());
+    }
+    if rule.exceptions.len() > MAX_RECURRENCE_COUNT as usize {
+        return Some("Too many removed occurrences".to_string());
+    }
+    None
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CalendarEvent {
     pub id: Option<i64>,
     pub group_id: i64,
@@ -18,22 +295,70 @@ pub struct CalendarEvent {
     pub created_by: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set for recurring events
+    #[serde(default)]
+    pub recurrence: Option<RecurrenceRule>,
 }
 
-// Get all events for a group
-pub async fn get_events(
-    pool: web::Data<Pool>,
-    path: web::Path<(i64,)>,
-) -> Result<HttpResponse, Box<dyn Error>> {
-    let client = pool.get().await?;
-    let (group_id,) = path.into_inner();
-    
-    let rows = client.query(
-        "SELECT id, group_id, title, description, event_type, start_time, end_time, all_day, location, created_by, created_at, updated_at FROM calendar_events WHERE group_id = $1 ORDER BY start_time",
-        &[&group_id]
-    ).await?;
-    
-    let events: Vec<CalendarEvent> = rows.iter().map(|row| CalendarEvent {
+/// Changes to a single occurrence of a recurring event. Unset fields keep the series' values.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct OccurrenceOverride {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub all_day: Option<bool>,
+    pub location: Option<String>,
+}
+
+impl OccurrenceOverride {
+    /// Apply the changes to a copy of the series placed at the occurrence
+    pub fn apply(&self, event: &mut CalendarEvent) {
+        if let Some(title) = &self.title {
+            event.title = title.clone();
+        }
+        if self.description.is_some() {
+            event.description = self.description.clone();
+        }
+        if let Some(start_time) = self.start_time {
+            event.start_time = start_time;
+        }
+        if self.end_time.is_some() {
+            event.end_time = self.end_time;
+        }
+        if let Some(all_day) = self.all_day {
+            event.all_day = all_day;
+        }
+        if self.location.is_some() {
+            event.location = self.location.clone();
+        }
+    }
+}
+
+/// An event, or one occurrence of a recurring event, within a date window
+#[derive(Debug, Serialize)]
+pub struct EventOccurrence {
+    #[serde(flatten)]
+    pub event: CalendarEvent,
+    /// Original start of this occurrence, which identifies it when editing or removing it
+    pub occurrence_start: Option<DateTime<Utc>>,
+    /// Whether this occurrence was edited separately from its series
+    pub overridden: bool,
+    pub headcount: Headcount,
+}
+
+/// Date window for listing events
+#[derive(Deserialize)]
+pub struct EventWindow {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+pub const EVENT_COLUMNS: &str = "id, group_id, title, description, event_type, start_time, end_time, all_day, location, created_by, created_at, updated_at, recurrence_frequency, recurrence_interval, recurrence_until, recurrence_count, recurrence_exceptions";
+
+pub fn event_from_row(row: &Row) -> CalendarEvent {
+    let frequency: Option<&str> = row.get(12);
+    CalendarEvent {
         id: row.get(0),
         group_id: row.get(1),
         title: row.get(2),
@@ -46,110 +371,338 @@ pub async fn get_events(
         created_by: row.get(9),
         created_at: row.get(10),
         updated_at: row.get(11),
-    }).collect();
-    
-    Ok(HttpResponse::Ok().json(events))
+        recurrence: frequency.and_then(|frequency| frequency.parse().ok()).map(|frequency| RecurrenceRule {
+            frequency,
+            interval: row.get(13),
+            until: row.get(14),
+            count: row.get(15),
+            exceptions: row.get(16),
+        }),
+    }
+}
+
+/// Overrides for the occurrences of the given events, keyed by event and original occurrence start
+pub async fn load_overrides(
+    client: &Client,
+    event_ids: &[i64],
+) -> Result<HashMap<(i64, DateTime<Utc>), OccurrenceOverride>, tokio_postgres::Error> {
+    let rows = client.query(
+        "SELECT event_id, occurrence_start, title, description, start_time, end_time, all_day, location FROM calendar_event_overrides WHERE event_id = ANY($1)",
+        &[&event_ids]
+    ).await?;
+
+    Ok(rows.iter().map(|row| {
+        let key: (i64, DateTime<Utc>) = (row.get(0), row.get(1));
+        let changes = OccurrenceOverride {
+            title: row.get(2),
+            description: row.get(3),
+            start_time: row.get(4),
+            end_time: row.get(5),
+            all_day: row.get(6),
+            location: row.get(7),
+        };
+        (key, changes)
+    }).collect())
+}
+
+/// The event's occurrences within the window, with any overrides applied.
+/// An event that doesn't recur is a single occurrence.
+pub fn expand_event(
+    event: &CalendarEvent,
+    overrides: &HashMap<(i64, DateTime<Utc>), OccurrenceOverride>,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+    tz: Tz,
+) -> Vec<EventOccurrence> {
+    let rule = match &event.recurrence {
+        Some(rule) => rule,
+        None => {
+            let end = event.end_time.unwrap_or(event.start_time);
+            if event.start_time < window_end && end >= window_start {
+                return vec![EventOccurrence {
+                    event: event.clone(),
+                    occurrence_start: None,
+                    overridden: false,
+                    headcount: Headcount::default(),
+                }];
+            }
+            return Vec::new();
+        }
+    };
+
+    let duration = event
+        .end_time
+        .map_or(Duration::zero(), |end| (end - event.start_time).max(Duration::zero()));
+    rule.occurrences(event.start_time, duration, window_start, window_end, tz)
+        .into_iter()
+        .map(|occurrence_start| {
+            let mut occurrence = event.clone();
+            occurrence.start_time = occurrence_start;
+            occurrence.end_time = event.end_time.map(|_| occurrence_start + duration);
+
+            let changes = event.id.and_then(|id| overrides.get(&(id, occurrence_start)));
+            if let Some(changes) = changes {
+                changes.apply(&mut occurrence);
+            }
+            EventOccurrence {
+                event: occurrence,
+                occurrence_start: Some(occurrence_start),
+                overridden: changes.is_some(),
+                headcount: Headcount::default(),
+            }
+        })
+        .collect()
 }
 
-// Get a single event
+// Get a group's events within a date window, with recurring events expanded into their occurrences
+pub async fn get_events(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    window: web::Query<EventWindow>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+
+    let window_start = window.start.unwrap_or_else(|| Utc::now() - Duration::days(30));
+    let window_end = window.end.unwrap_or(window_start + Duration::days(120));
+    if window_end <= window_start {
+        return Ok(HttpResponse::BadRequest().body("end must be after start"));
+    }
+    if window_end - window_start > Duration::days(MAX_EVENT_WINDOW_DAYS) {
+        return Ok(HttpResponse::BadRequest().body(format!("The window can be at most {} days", MAX_EVENT_WINDOW_DAYS)));
+    }
+
+    let rows = client.query(
+        &format!(
+            "SELECT {} FROM calendar_events
+             WHERE group_id = $1 AND start_time < $3
+             AND (recurrence_frequency IS NOT NULL OR COALESCE(end_time, start_time) >= $2)
+             ORDER BY start_time",
+            EVENT_COLUMNS
+        ),
+        &[&auth.group_id, &window_start, &window_end]
+    ).await?;
+    let events: Vec<CalendarEvent> = rows.iter().map(event_from_row).collect();
+
+    let recurring_ids: Vec<i64> = events.iter().filter(|event| event.recurrence.is_some()).filter_map(|event| event.id).collect();
+    let overrides = load_overrides(&client, &recurring_ids).await?;
+    let event_ids: Vec<i64> = events.iter().filter_map(|event| event.id).collect();
+    let headcounts = load_headcounts(&client, &event_ids).await?;
+    let tz = group_time_zone(&client, auth.group_id).await?;
+
+    let mut occurrences: Vec<EventOccurrence> = events
+        .iter()
+        .flat_map(|event| expand_event(event, &overrides, window_start, window_end, tz))
+        .collect();
+    for occurrence in &mut occurrences {
+        if let Some(headcount) = occurrence.event.id.and_then(|id| headcounts.get(&(id, occurrence.occurrence_start))) {
+            occurrence.headcount = headcount.clone();
+        }
+    }
+    occurrences.sort_by_key(|occurrence| occurrence.event.start_time);
+
+    Ok(HttpResponse::Ok().json(occurrences))
+}
+
+// Get a single event; recurring events are returned as the series
 pub async fn get_event(
     pool: web::Data<Pool>,
-    path: web::Path<(i64, i64)>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+) -> Result<HttpResponse, ApiError> {
     let client = pool.get().await?;
-    let (group_id, event_id) = path.into_inner();
-    
+    let (_group_name, event_id) = path.into_inner();
+
     let row = client.query_one(
-        "SELECT id, group_id, title, description, event_type, start_time, end_time, all_day, location, created_by, created_at, updated_at FROM calendar_events WHERE id = $1 AND group_id = $2",
-        &[&event_id, &group_id]
+        &format!("SELECT {} FROM calendar_events WHERE id = $1 AND group_id = $2", EVENT_COLUMNS),
+        &[&event_id, &auth.group_id]
     ).await?;
-    
-    let event = CalendarEvent {
-        id: row.get(0),
-        group_id: row.get(1),
-        title: row.get(2),
-        description: row.get(3),
-        event_type: row.get(4),
-        start_time: row.get(5),
-        end_time: row.get(6),
-        all_day: row.get(7),
-        location: row.get(8),
-        created_by: row.get(9),
-        created_at: row.get(10),
-        updated_at: row.get(11),
-    };
-    
+
+    let event = event_from_row(&row);
+
     Ok(HttpResponse::Ok().json(event))
 }
 
 // Create a new event
 pub async fn create_event(
     pool: web::Data<Pool>,
-    path: web::Path<(i64,)>,
+    auth: AuthedGroupId,
     event: web::Json<CalendarEvent>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+) -> Result<HttpResponse, ApiError> {
     let client = pool.get().await?;
-    let (group_id,) = path.into_inner();
-    
+
+    if let Some(reason) = event.recurrence.as_ref().and_then(|rule| validate_recurrence(rule, event.start_time)) {
+        return Ok(HttpResponse::BadRequest().body(reason));
+    }
+    let rule = event.recurrence.as_ref();
+
     let row = client.query_one(
-        "INSERT INTO calendar_events (group_id, title, description, event_type, start_time, end_time, all_day, location, created_by) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+        "INSERT INTO calendar_events (group_id, title, description, event_type, start_time, end_time, all_day, location, created_by, recurrence_frequency, recurrence_interval, recurrence_until, recurrence_count, recurrence_exceptions) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14) RETURNING id",
         &[
-            &group_id, 
-            &event.title, 
-            &event.description, 
-            &event.event_type, 
-            &event.start_time, 
-            &event.end_time, 
-            &event.all_day, 
-            &event.location, 
-            &event.created_by
+            &auth.group_id,
+            &event.title,
+            &event.description,
+            &event.event_type,
+            &event.start_time,
+            &event.end_time,
+            &event.all_day,
+            &event.location,
+            &event.created_by,
+            &rule.map(|rule| rule.frequency.as_str()),
+            &rule.map_or(1, |rule| rule.interval),
+            &rule.and_then(|rule| rule.until),
+            &rule.and_then(|rule| rule.count),
+            &rule.map_or_else(Vec::new, |rule| rule.exceptions.clone())
         ]
     ).await?;
-    
+
     let event_id: i64 = row.get(0);
-    
+
     Ok(HttpResponse::Created().json(serde_json::json!({"id": event_id})))
 }
 
-// Update an event
+// Update an event, or a whole recurring series. Occurrences edited separately keep their changes.
 pub async fn update_event(
     pool: web::Data<Pool>,
-    path: web::Path<(i64, i64)>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
     event: web::Json<CalendarEvent>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+) -> Result<HttpResponse, ApiError> {
     let client = pool.get().await?;
-    let (group_id, event_id) = path.into_inner();
-    
+    let (_group_name, event_id) = path.into_inner();
+
+    if let Some(reason) = event.recurrence.as_ref().and_then(|rule| validate_recurrence(rule, event.start_time)) {
+        return Ok(HttpResponse::BadRequest().body(reason));
+    }
+    let rule = event.recurrence.as_ref();
+
     client.execute(
-        "UPDATE calendar_events SET title = $1, description = $2, event_type = $3, start_time = $4, end_time = $5, all_day = $6, location = $7, updated_at = NOW() WHERE id = $8 AND group_id = $9",
+        "UPDATE calendar_events SET title = $1, description = $2, event_type = $3, start_time = $4, end_time = $5, all_day = $6, location = $7, recurrence_frequency = $10, recurrence_interval = $11, recurrence_until = $12, recurrence_count = $13, recurrence_exceptions = $14, updated_at = NOW() WHERE id = $8 AND group_id = $9",
         &[
-            &event.title, 
-            &event.description, 
-            &event.event_type, 
-            &event.start_time, 
-            &event.end_time, 
-            &event.all_day, 
-            &event.location, 
-            &event_id, 
-            &group_id
+            &event.title,
+            &event.description,
+            &event.event_type,
+            &event.start_time,
+            &event.end_time,
+            &event.all_day,
+            &event.location,
+            &event_id,
+            &auth.group_id,
+            &rule.map(|rule| rule.frequency.as_str()),
+            &rule.map_or(1, |rule| rule.interval),
+            &rule.and_then(|rule| rule.until),
+            &rule.and_then(|rule| rule.count),
+            &rule.map_or_else(Vec::new, |rule| rule.exceptions.clone())
         ]
     ).await?;
-    
+
     Ok(HttpResponse::Ok().finish())
 }
 
-// Delete an event
+// Delete an event, including every occurrence of a recurring series
 pub async fn delete_event(
     pool: web::Data<Pool>,
-    path: web::Path<(i64, i64)>,
-) -> Result<HttpResponse, Box<dyn Error>> {
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+) -> Result<HttpResponse, ApiError> {
     let client = pool.get().await?;
-    let (group_id, event_id) = path.into_inner();
-    
+    let (_group_name, event_id) = path.into_inner();
+
     client.execute(
         "DELETE FROM calendar_events WHERE id = $1 AND group_id = $2",
+        &[&event_id, &auth.group_id]
+    ).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Load a recurring series and check the occurrence belongs to it
+async fn find_occurrence(
+    client: &Client,
+    group_id: i64,
+    event_id: i64,
+    occurrence_start: DateTime<Utc>,
+) -> Result<Result<CalendarEvent, HttpResponse>, tokio_postgres::Error> {
+    let row = client.query_opt(
+        &format!("SELECT {} FROM calendar_events WHERE id = $1 AND group_id = $2", EVENT_COLUMNS),
         &[&event_id, &group_id]
     ).await?;
-    
+    let event = match row {
+        Some(row) => event_from_row(&row),
+        None => return Ok(Err(HttpResponse::NotFound().finish())),
+    };
+
+    let tz = group_time_zone(client, group_id).await?;
+    match &event.recurrence {
+        None => Ok(Err(HttpResponse::BadRequest().body("Only occurrences of recurring events can be changed on their own"))),
+        Some(rule) if !rule.has_occurrence(event.start_time, occurrence_start, tz) => {
+            Ok(Err(HttpResponse::NotFound().body("The event has no occurrence at that time")))
+        }
+        Some(_) => Ok(Ok(event)),
+    }
+}
+
+// Edit one occurrence of a recurring event without changing the rest of the series
+pub async fn update_occurrence(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64, DateTime<Utc>)>,
+    changes: web::Json<OccurrenceOverride>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let (_group_name, event_id, occurrence_start) = path.into_inner();
+
+    if let (Some(start), Some(end)) = (changes.start_time, changes.end_time) {
+        if end < start {
+            return Ok(HttpResponse::BadRequest().body("end_time must not be before start_time"));
+        }
+    }
+    if let Err(response) = find_occurrence(&client, auth.group_id, event_id, occurrence_start).await? {
+        return Ok(response);
+    }
+
+    client.execute(
+        "INSERT INTO calendar_event_overrides (event_id, occurrence_start, title, description, start_time, end_time, all_day, location)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         ON CONFLICT (event_id, occurrence_start) DO UPDATE SET
+            title = EXCLUDED.title, description = EXCLUDED.description, start_time = EXCLUDED.start_time,
+            end_time = EXCLUDED.end_time, all_day = EXCLUDED.all_day, location = EXCLUDED.location, updated_at = NOW()",
+        &[
+            &event_id,
+            &occurrence_start,
+            &changes.title,
+            &changes.description,
+            &changes.start_time,
+            &changes.end_time,
+            &changes.all_day,
+            &changes.location
+        ]
+    ).await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+// Remove one occurrence from a recurring event
+pub async fn delete_occurrence(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64, DateTime<Utc>)>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let (_group_name, event_id, occurrence_start) = path.into_inner();
+
+    if let Err(response) = find_occurrence(&client, auth.group_id, event_id, occurrence_start).await? {
+        return Ok(response);
+    }
+
+    client.execute(
+        "UPDATE calendar_events SET recurrence_exceptions = array_append(recurrence_exceptions, $3), updated_at = NOW()
+         WHERE id = $1 AND group_id = $2 AND NOT ($3 = ANY(recurrence_exceptions))",
+        &[&event_id, &auth.group_id, &occurrence_start]
+    ).await?;
+    client.execute(
+        "DELETE FROM calendar_event_overrides WHERE event_id = $1 AND occurrence_start = $2",
+        &[&event_id, &occurrence_start]
+    ).await?;
+
     Ok(HttpResponse::NoContent().finish())
 }
@@ -0,0 +1,570 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::db::get_member_id;
+use crate::error::ApiError;
+use crate::scoring_rules::stored_skill_xp;
+use actix_web::{web, HttpResponse};
+use deadpool_postgres::Pool;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[cfg(test)]
+mod slayer_assignment_tests {
+    use super::*;
+
+    fn master() -> SlayerMaster {
+        serde_json::from_value(serde_json::json!({
+            "name": "Test",
+            "combat_level": 50,
+            "slayer_level": 1,
+            "tasks": [
+                {"monster": "Goblins", "weight": 6, "quantity": [10, 20]},
+                {"monster": "Gargoyles", "weight": 2, "quantity": [10, 20], "slayer_level": 75},
+                {"monster": "Mithril dragons", "weight": 2, "quantity": [5, 10], "unlock": "I hope you mith me!"},
+                {"monster": "Elves", "weight": 2, "quantity": [10, 20], "quest": "Regicide",
+                 "extend": {"unlock": "Elves extended", "quantity": [20, 30]}}
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn profile() -> SlayerProfile {
+        SlayerProfile {
+            slayer_level: 80,
+            combat_level: 90,
+            blocked_tasks: HashSet::new(),
+            unlocks: HashSet::new(),
+            completed_quests: HashSet::from(["regicide".to_string()]),
+            current_task: None,
+        }
+    }
+
+    #[test]
+    fn bundled_masters_parse() {
+        assert!(find_master("Duradel").is_some());
+    }
+
+    #[test]
+    fn xp_to_level() {
+        assert_eq!(level_for_xp(0), 1);
+        assert_eq!(level_for_xp(83), 2);
+        assert_eq!(level_for_xp(13_034_430), 98);
+        assert_eq!(level_for_xp(13_034_431), 99);
+        assert_eq!(level_for_xp(200_000_000), 99);
+    }
+
+    #[test]
+    fn levels_from_stored_skills() {
+        // Skills are stored alphabetically: attack, hitpoints and slayer are 2nd, 12th and 19th
+        let mut skills = vec![0; 23];
+        skills[1] = 13_034_431;
+        skills[11] = 1_154;
+        skills[18] = 101_333;
+        assert_eq!(skill_level(&skills, "attack"), 99);
+        assert_eq!(skill_level(&skills, "hitpoints"), 10);
+        assert_eq!(skill_level(&skills, "slayer"), 50);
+        assert_eq!(skill_level(&skills, "strength"), 1);
+
+        // Older arrays include overall xp between mining and prayer
+        skills.insert(15, 13_137_000);
+        assert_eq!(skill_level(&skills, "attack"), 99);
+        assert_eq!(skill_level(&skills, "hitpoints"), 10);
+        assert_eq!(skill_level(&skills, "slayer"), 50);
+        assert_eq!(skill_level(&skills, "prayer"), 1);
+        assert_eq!(skill_level(&skills, "woodcutting"), 1);
+    }
+
+    #[test]
+    fn combat_level_from_levels() {
+        assert_eq!(combat_level(&[1, 1, 1, 10, 1, 1, 1]), 3);
+        assert_eq!(combat_level(&[99, 99, 99, 99, 99, 99, 99]), 126);
+    }
+
+    #[test]
+    fn probabilities_follow_requirements_and_blocks() {
+        let mut profile = profile();
+        let assignments = assignment_probabilities(&master(), &profile).unwrap();
+        let monsters: Vec<&str> = assignments.tasks.iter().map(|task| task.monster.as_str()).collect();
+        assert_eq!(monsters, vec!["Goblins", "Gargoyles", "Elves"]);
+        assert!((assignments.tasks[0].probability - 0.6).abs() < 1e-9);
+        assert_eq!(assignments.excluded.len(), 1);
+
+        profile.unlocks.insert("i hope you mith me!".to_string());
+        profile.unlocks.insert("elves extended".to_string());
+        profile.blocked_tasks.insert("goblins".to_string());
+        let assignments = assignment_probabilities(&master(), &profile).unwrap();
+        let monsters: Vec<&str> = assignments.tasks.iter().map(|task| task.monster.as_str()).collect();
+        assert_eq!(monsters, vec!["Gargoyles", "Mithril dragons", "Elves"]);
+        assert_eq!(assignments.tasks[2].quantity, [20, 30]);
+    }
+
+    #[test]
+    fn master_requirements_are_checked() {
+        let mut profile = profile();
+        profile.combat_level = 20;
+        assert!(assignment_probabilities(&master(), &profile).is_err());
+    }
+}
+
+/// Most tasks a member can have blocked at once
+pub const MAX_BLOCKED_TASKS: usize = 6;
+
+lazy_static! {
+    /// Built into the binary so the server doesn't depend on where it is started from
+    pub static ref SLAYER_MASTERS: Vec<SlayerMaster> = {
+        serde_json::from_str(include_str!("../slayer_masters.json")).expect("slayer_masters.json is not valid")
+    };
+}
+
+/// A slayer master and their task weight table
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SlayerMaster {
+    pub name: String,
+
+    /// Combat level needed to get tasks from this master
+    #[serde(default)]
+    pub combat_level: i32,
+
+    /// Slayer level needed to get tasks from this master
+    #[serde(default)]
+    pub slayer_level: i32,
+
+    pub tasks: Vec<SlayerAssignment>,
+}
+
+/// A task a slayer master can assign
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SlayerAssignment {
+    pub monster: String,
+
+    /// Relative chance of this task among the tasks the member can get
+    pub weight: i32,
+
+    /// Minimum and maximum number of kills
+    pub quantity: [i32; 2],
+
+    /// Slayer level needed to be assigned the task
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slayer_level: Option<i32>,
+
+    /// Quest that must be completed to be assigned the task
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quest: Option<String>,
+
+    /// Slayer reward unlock needed to be assigned the task
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unlock: Option<String>,
+
+    /// Unlock that extends the task and the extended quantity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extend: Option<TaskExtension>,
+
+    #[serde(default)]
+    pub is_boss: bool,
+}
+
+/// Slayer reward unlock that extends a task
+#[derive(Deserialize, Serialize, Clone)]
+pub struct TaskExtension {
+    pub unlock: String,
+    pub quantity: [i32; 2],
+}
+
+/// What a member has that affects which tasks they can be assigned.
+/// Names are stored lowercase.
+pub struct SlayerProfile {
+    pub slayer_level: i32,
+    pub combat_level: i32,
+    pub blocked_tasks: HashSet<String>,
+    pub unlocks: HashSet<String>,
+    pub completed_quests: HashSet<String>,
+
+    /// Current task; masters never assign the same task twice in a row
+    pub current_task: Option<String>,
+}
+
+/// Chance of being assigned a task
+#[derive(Serialize)]
+pub struct TaskProbability {
+    pub monster: String,
+    pub weight: i32,
+    pub probability: f64,
+    pub quantity: [i32; 2],
+    pub is_boss: bool,
+}
+
+/// A task the member can't currently be assigned
+#[derive(Serialize)]
+pub struct ExcludedTask {
+    pub monster: String,
+    pub reason: String,
+}
+
+/// Assignment probabilities for a member at one master
+#[derive(Serialize)]
+pub struct MasterAssignments {
+    pub slayer_master: String,
+    pub total_weight: i32,
+    pub tasks: Vec<TaskProbability>,
+    pub excluded: Vec<ExcludedTask>,
+}
+
+/// Stored block list, unlocks and slayer quests for a member
+#[derive(Deserialize, Serialize)]
+pub struct SlayerPreferences {
+    #[serde(default)]
+    pub blocked_tasks: Vec<String>,
+    #[serde(default)]
+    pub unlocks: Vec<String>,
+    #[serde(default)]
+    pub completed_quests: Vec<String>,
+}
+
+/// Query parameters for assignment probabilities
+#[derive(Deserialize)]
+pub struct ProbabilityParams {
+    /// Slayer master to use
+    pub master: String,
+
+    /// Comma-separated tasks to block on top of the member's block list
+    pub what_if_block: Option<String>,
+}
+
+/// Probabilities with the member's block list, and with the candidate blocks when given
+#[derive(Serialize)]
+pub struct ProbabilityResponse {
+    pub member_name: String,
+    pub slayer_level: i32,
+    pub combat_level: i32,
+    pub current: MasterAssignments,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub what_if: Option<MasterAssignments>,
+}
+
+/// Level for an amount of experience, capped at 99
+pub fn level_for_xp(xp: i32) -> i32 {
+    let mut points = 0.0;
+    for level in 1..99 {
+        points += (level as f64 + 300.0 * 2f64.powf(level as f64 / 7.0)).floor();
+        if (points / 4.0).floor() as i32 > xp {
+            return level;
+        }
+    }
+    99
+}
+
+/// Level in a skill from a member's stored skills array
+pub fn skill_level(skills: &[i32], skill: &str) -> i32 {
+    stored_skill_xp(skills, skill).map_or(1, level_for_xp)
+}
+
+/// Combat level from attack, defence, strength, hitpoints, ranged, prayer and magic levels
+pub fn combat_level(levels: &[i32; 7]) -> i32 {
+    let [attack, defence, strength, hitpoints, ranged, prayer, magic] = *levels;
+    let base = 0.25 * (defence + hitpoints + prayer / 2) as f64;
+    let melee = 0.325 * (attack + strength) as f64;
+    let range = 0.325 * (ranged * 3 / 2) as f64;
+    let mage = 0.325 * (magic * 3 / 2) as f64;
+    (base + melee.max(range).max(mage)).floor() as i32
+}
+
+/// Work out which tasks a master can assign the member and the chance of each.
+/// Fails when the member doesn't meet the master's own requirements.
+pub fn assignment_probabilities(
+    master: &SlayerMaster,
+    profile: &SlayerProfile,
+) -> Result<MasterAssignments, String> {
+    if profile.combat_level < master.combat_level {
+        return Err(format!("{} requires combat level {}", master.name, master.combat_level));
+    }
+    if profile.slayer_level < master.slayer_level {
+        return Err(format!("{} requires slayer level {}", master.name, master.slayer_level));
+    }
+
+    let mut eligible = Vec::new();
+    let mut excluded = Vec::new();
+    for task in &master.tasks {
+        let monster = task.monster.to_lowercase();
+        let reason = if profile.blocked_tasks.contains(&monster) {
+            Some("Blocked".to_string())
+        } else if profile.current_task.as_deref().map(str::to_lowercase).as_deref() == Some(monster.as_str()) {
+            Some("Current task".to_string())
+        } else if task.slayer_level.is_some_and(|level| profile.slayer_level < level) {
+            Some(format!("Requires slayer level {}", task.slayer_level.unwrap_or_default()))
+        } else if task.quest.as_ref().is_some_and(|quest| !profile.completed_quests.contains(&quest.to_lowercase())) {
+            Some(format!("Requires {}", task.quest.as_deref().unwrap_or_default()))
+        } else if task.unlock.as_ref().is_some_and(|unlock| !profile.unlocks.contains(&unlock.to_lowercase())) {
+            Some(format!("Requires the {} unlock", task.unlock.as_deref().unwrap_or_default()))
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => excluded.push(ExcludedTask {
+                monster: task.monster.clone(),
+                reason,
+            }),
+            None => eligible.push(task),
+        }
+    }
+
+    let total_weight: i32 = eligible.iter().map(|task| task.weight).sum();
+    let tasks = eligible
+        .into_iter()
+        .map(|task| {
+            let quantity = match &task.extend {
+                Some(extend) if profile.unlocks.contains(&extend.unlock.to_lowercase()) => extend.quantity,
+                _ => task.quantity,
+            };
+            TaskProbability {
+                monster: task.monster.clone(),
+                weight: task.weight,
+                probability: if total_weight > 0 { task.weight as f64 / total_weight as f64 } else { 0.0 },
+                quantity,
+                is_boss: task.is_boss,
+            }
+        })
+        .collect();
+
+    Ok(MasterAssignments {
+        slayer_master: master.name.clone(),
+        total_weight,
+        tasks,
+        excluded,
+    })
+}
+
+fn find_master(name: &str) -> Option<&'static SlayerMaster> {
+    SLAYER_MASTERS.iter().find(|master| master.name.eq_ignore_ascii_case(name))
+}
+
+/// Check stored preferences against the masters' tables and return them with canonical names
+pub fn validate_preferences(preferences: SlayerPreferences) -> Result<SlayerPreferences, String> {
+    let tasks = SLAYER_MASTERS.iter().flat_map(|master| master.tasks.iter());
+    let mut monsters = Vec::new();
+    let mut unlocks = Vec::new();
+    let mut quests = Vec::new();
+    for task in tasks {
+        monsters.push(task.monster.as_str());
+        unlocks.extend(task.unlock.as_deref());
+        unlocks.extend(task.extend.as_ref().map(|extend| extend.unlock.as_str()));
+        quests.extend(task.quest.as_deref());
+    }
+
+    let canonical = |kind: &str, known: &[&str], names: Vec<String>| -> Result<Vec<String>, String> {
+        let mut result: Vec<String> = Vec::new();
+        for name in names {
+            match known.iter().find(|known| known.eq_ignore_ascii_case(name.trim())) {
+                Some(known) if !result.iter().any(|existing| existing == known) => result.push(known.to_string()),
+                Some(_) => (),
+                None => return Err(format!("Unknown {} '{}'", kind, name)),
+            }
+        }
+        Ok(result)
+    };
+
+    let preferences = SlayerPreferences {
+        blocked_tasks: canonical("task", &monsters, preferences.blocked_tasks)?,
+        unlocks: canonical("unlock", &unlocks, preferences.unlocks)?,
+        completed_quests: canonical("quest", &quests, preferences.completed_quests)?,
+    };
+
+    if preferences.blocked_tasks.len() > MAX_BLOCKED_TASKS {
+        return Err(format!("At most {} tasks can be blocked", MAX_BLOCKED_TASKS));
+    }
+
+    Ok(preferences)
+}
+
+/// Get every slayer master's task weight table
+pub async fn get_slayer_masters(_auth: AuthedGroupId) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(&*SLAYER_MASTERS))
+}
+
+/// Get a member's block list, unlocks and slayer quests
+pub async fn get_slayer_preferences(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
+    let client = pool.get().await?;
+    let member_id = get_member_id(&client, auth.group_id, &member_name).await?;
+
+    let preferences = load_preferences(&client, member_id).await?;
+
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+/// Replace a member's block list, unlocks and slayer quests
+pub async fn update_slayer_preferences(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, String)>,
+    preferences: web::Json<SlayerPreferences>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
+
+    let preferences = match validate_preferences(preferences.into_inner()) {
+        Ok(preferences) => preferences,
+        Err(message) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": message
+            })));
+        }
+    };
+
+    let client = pool.get().await?;
+    let member_id = get_member_id(&client, auth.group_id, &member_name).await?;
+
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO groupironman.slayer_preferences
+             (member_id, blocked_tasks, unlocks, completed_quests, updated_at)
+             VALUES ($1, $2, $3, $4, NOW())
+             ON CONFLICT (member_id)
+             DO UPDATE SET blocked_tasks = $2, unlocks = $3, completed_quests = $4, updated_at = NOW()"
+        )
+        .await?;
+
+    client
+        .execute(
+            &stmt,
+            &[
+                &member_id,
+                &preferences.blocked_tasks,
+                &preferences.unlocks,
+                &preferences.completed_quests,
+            ],
+        )
+        .await?;
+
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+/// Get the probability of each task a master can assign a member
+///
+/// Uses the member's slayer and combat levels from their skills, their stored
+/// block list, unlocks and quests, and their current task. With `what_if_block`
+/// the response also includes the probabilities after blocking those tasks.
+pub async fn get_task_probabilities(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, String)>,
+    params: web::Query<ProbabilityParams>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
+
+    let master = match find_master(&params.master) {
+        Some(master) => master,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "status": "error",
+                "message": format!("Unknown slayer master '{}'", params.master)
+            })));
+        }
+    };
+
+    let client = pool.get().await?;
+    let member_id = get_member_id(&client, auth.group_id, &member_name).await?;
+    let preferences = load_preferences(&client, member_id).await?;
+
+    // Levels from the member's skills
+    let skills_stmt = client
+        .prepare_cached("SELECT skills FROM groupironman.members WHERE member_id = $1")
+        .await?;
+    let skills: Option<Vec<i32>> = client.query_one(&skills_stmt, &[&member_id]).await?.try_get(0)?;
+    let skills = skills.unwrap_or_default();
+    let level = |skill: &str| skill_level(&skills, skill);
+    let slayer_level = level("slayer");
+    let combat_level = combat_level(&[
+        level("attack"),
+        level("defence"),
+        level("strength"),
+        level("hitpoints").max(10),
+        level("ranged"),
+        level("prayer"),
+        level("magic"),
+    ]);
+
+    // Current task, which the master won't assign again straight away
+    let current_task_stmt = client
+        .prepare_cached(
+            "SELECT monster_name FROM groupironman.slayer_tasks
+             WHERE member_id = $1 AND is_complete = false AND skipped_at IS NULL
+             ORDER BY assigned_at DESC LIMIT 1"
+        )
+        .await?;
+    let current_task: Option<String> = client
+        .query_opt(&current_task_stmt, &[&member_id])
+        .await?
+        .map(|row| row.try_get(0))
+        .transpose()?;
+
+    let lowercase = |names: &[String]| names.iter().map(|name| name.to_lowercase()).collect::<HashSet<_>>();
+    let mut profile = SlayerProfile {
+        slayer_level,
+        combat_level,
+        blocked_tasks: lowercase(&preferences.blocked_tasks),
+        unlocks: lowercase(&preferences.unlocks),
+        completed_quests: lowercase(&preferences.completed_quests),
+        current_task,
+    };
+
+    let current = match assignment_probabilities(master, &profile) {
+        Ok(assignments) => assignments,
+        Err(message) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": message
+            })));
+        }
+    };
+
+    let what_if = match &params.what_if_block {
+        Some(blocks) => {
+            for block in blocks.split(',').map(str::trim).filter(|block| !block.is_empty()) {
+                profile.blocked_tasks.insert(block.to_lowercase());
+            }
+            assignment_probabilities(master, &profile).ok()
+        }
+        None => None,
+    };
+
+    Ok(HttpResponse::Ok().json(ProbabilityResponse {
+        member_name,
+        slayer_level,
+        combat_level,
+        current,
+        what_if,
+    }))
+}
+
+/// A member's stored preferences, or empty lists if they haven't saved any
+pub async fn load_preferences<C: deadpool_postgres::GenericClient>(
+    client: &C,
+    member_id: i64,
+) -> Result<SlayerPreferences, ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "SELECT blocked_tasks, unlocks, completed_quests
+             FROM groupironman.slayer_preferences WHERE member_id = $1"
+        )
+        .await?;
+
+    let preferences = match client.query_opt(&stmt, &[&member_id]).await? {
+        Some(row) => SlayerPreferences {
+            blocked_tasks: row.try_get(0)?,
+            unlocks: row.try_get(1)?,
+            completed_quests: row.try_get(2)?,
+        },
+        None => SlayerPreferences {
+            blocked_tasks: Vec::new(),
+            unlocks: Vec::new(),
+            completed_quests: Vec::new(),
+        },
+    };
+
+    Ok(preferences)
+}
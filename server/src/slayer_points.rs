@@ -0,0 +1,391 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::db::get_member_id;
+use crate::error::ApiError;
+use crate::slayer_assignments::{load_preferences, validate_preferences};
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{GenericClient, Pool};
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod slayer_points_tests {
+    use super::*;
+
+    #[test]
+    fn streak_milestones_multiply_points() {
+        assert_eq!(task_completion_points("Duradel", 1), Some(15));
+        assert_eq!(task_completion_points("duradel", 10), Some(75));
+        assert_eq!(task_completion_points("Duradel", 50), Some(225));
+        assert_eq!(task_completion_points("Duradel", 100), Some(375));
+        assert_eq!(task_completion_points("Duradel", 250), Some(525));
+        assert_eq!(task_completion_points("Duradel", 1000), Some(750));
+        assert_eq!(task_completion_points("Duradel", 2000), Some(750));
+        assert_eq!(task_completion_points("Turael", 10), Some(0));
+        assert_eq!(task_completion_points("Nobody", 10), None);
+    }
+
+    #[test]
+    fn default_costs() {
+        assert_eq!(SlayerSpendKind::Skip.default_cost(), Some(SKIP_COST));
+        assert_eq!(SlayerSpendKind::Block.default_cost(), Some(BLOCK_COST));
+        assert_eq!(SlayerSpendKind::Unlock.default_cost(), None);
+    }
+}
+
+/// Points to cancel a task at a slayer master
+pub const SKIP_COST: i32 = 30;
+
+/// Points to permanently block a task
+pub const BLOCK_COST: i32 = 100;
+
+/// Masters whose task resets are free and end the streak instead of costing points
+pub const RESET_MASTERS: [&str; 2] = ["Turael", "Spria"];
+
+/// Points for a completed task at each master: the base, then the 10th, 50th, 100th,
+/// 250th and 1000th task of a streak
+const MASTER_TASK_POINTS: [(&str, [i32; 6]); 10] = [
+    ("Turael", [0, 0, 0, 0, 0, 0]),
+    ("Spria", [0, 0, 0, 0, 0, 0]),
+    ("Mazchna", [6, 30, 90, 150, 210, 300]),
+    ("Vannaka", [8, 40, 120, 200, 280, 400]),
+    ("Chaeldar", [10, 50, 150, 250, 350, 500]),
+    ("Nieve", [12, 60, 180, 300, 420, 600]),
+    ("Steve", [12, 60, 180, 300, 420, 600]),
+    ("Duradel", [15, 75, 225, 375, 525, 750]),
+    ("Konar quo Maten", [18, 90, 270, 450, 630, 900]),
+    ("Krystilia", [25, 125, 375, 625, 875, 1250]),
+];
+
+/// What slayer points were spent on
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SlayerSpendKind {
+    Unlock,
+    Extend,
+    Block,
+    Skip,
+}
+
+impl SlayerSpendKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SlayerSpendKind::Unlock => "unlock",
+            SlayerSpendKind::Extend => "extend",
+            SlayerSpendKind::Block => "block",
+            SlayerSpendKind::Skip => "skip",
+        }
+    }
+
+    /// Fixed cost, for the kinds that have one
+    pub fn default_cost(&self) -> Option<i32> {
+        match self {
+            SlayerSpendKind::Block => Some(BLOCK_COST),
+            SlayerSpendKind::Skip => Some(SKIP_COST),
+            SlayerSpendKind::Unlock | SlayerSpendKind::Extend => None,
+        }
+    }
+}
+
+/// Request to record points spent at a slayer master
+#[derive(Deserialize)]
+pub struct SlayerSpendRequest {
+    pub kind: SlayerSpendKind,
+
+    /// Unlock or extension name, or the monster being blocked
+    pub reward: Option<String>,
+
+    /// Points spent; required for unlocks and extensions
+    pub points: Option<i32>,
+}
+
+/// An entry in a member's slayer points ledger
+#[derive(Serialize)]
+pub struct SlayerLedgerEntry {
+    pub entry_id: i64,
+    pub kind: String,
+
+    /// Positive when earned, negative when spent
+    pub points: i32,
+    pub task_id: Option<i64>,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A member's slayer points ledger and how it compares with the reported balance
+#[derive(Serialize)]
+pub struct SlayerPointsSummary {
+    pub member_name: String,
+    pub earned: i64,
+    pub spent: i64,
+    pub computed_balance: i64,
+
+    /// Balance last reported by the member's client
+    pub reported_balance: Option<i32>,
+
+    /// Reported minus computed balance
+    pub divergence: Option<i64>,
+    pub is_consistent: bool,
+    pub entries: Vec<SlayerLedgerEntry>,
+}
+
+/// Points earned for completing a task at a master with the given streak,
+/// counting the completed task. Unknown masters give `None`.
+pub fn task_completion_points(slayer_master: &str, streak: i32) -> Option<i32> {
+    let (_, points) = MASTER_TASK_POINTS
+        .iter()
+        .find(|(master, _)| master.eq_ignore_ascii_case(slayer_master))?;
+    let tier = match streak {
+        streak if streak > 0 && streak % 1000 == 0 => 5,
+        streak if streak > 0 && streak % 250 == 0 => 4,
+        streak if streak > 0 && streak % 100 == 0 => 3,
+        streak if streak > 0 && streak % 50 == 0 => 2,
+        streak if streak > 0 && streak % 10 == 0 => 1,
+        _ => 0,
+    };
+    Some(points[tier])
+}
+
+/// Whether replacing an unfinished task with one from this master is a free reset
+pub fn is_reset_master(slayer_master: &str) -> bool {
+    RESET_MASTERS.iter().any(|master| master.eq_ignore_ascii_case(slayer_master))
+}
+
+/// Start the member's ledger from their last reported balance, so points
+/// held before tracking began don't show up as a divergence
+pub async fn ensure_opening_balance<C: GenericClient>(client: &C, member_id: i64) -> Result<(), ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO groupironman.slayer_point_ledger (member_id, kind, points, description)
+             SELECT s.member_id, 'opening_balance', s.slayer_points, 'Balance when tracking began'
+             FROM groupironman.slayer_stats s
+             WHERE s.member_id = $1
+             AND s.slayer_points > 0
+             AND NOT EXISTS (SELECT 1 FROM groupironman.slayer_point_ledger l WHERE l.member_id = $1)"
+        )
+        .await?;
+    client.execute(&stmt, &[&member_id]).await?;
+    Ok(())
+}
+
+/// Record the points earned for a completed task. Each task only earns once.
+pub async fn record_task_completion<C: GenericClient>(
+    client: &C,
+    member_id: i64,
+    task_id: i64,
+    slayer_master: &str,
+    streak: i32,
+) -> Result<Option<i32>, ApiError> {
+    let points = match task_completion_points(slayer_master, streak) {
+        Some(points) => points,
+        None => return Ok(None),
+    };
+
+    ensure_opening_balance(client, member_id).await?;
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO groupironman.slayer_point_ledger (member_id, task_id, kind, points, description)
+             VALUES ($1, $2, 'task', $3, $4)
+             ON CONFLICT DO NOTHING"
+        )
+        .await?;
+    let description = format!("{} task, streak {}", slayer_master, streak);
+    client.execute(&stmt, &[&member_id, &task_id, &points, &description]).await?;
+
+    Ok(Some(points))
+}
+
+/// Record a task being skipped for points
+pub async fn record_task_skip<C: GenericClient>(client: &C, member_id: i64, task_id: i64) -> Result<(), ApiError> {
+    ensure_opening_balance(client, member_id).await?;
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO groupironman.slayer_point_ledger (member_id, task_id, kind, points, description)
+             VALUES ($1, $2, 'skip', $3, 'Task skipped')
+             ON CONFLICT DO NOTHING"
+        )
+        .await?;
+    client.execute(&stmt, &[&member_id, &task_id, &-SKIP_COST]).await?;
+    Ok(())
+}
+
+/// Reported minus computed balance, if the member has reported a balance
+pub async fn balance_divergence<C: GenericClient>(client: &C, member_id: i64) -> Result<Option<i64>, ApiError> {
+    let stmt = client
+        .prepare_cached(
+            "SELECT s.slayer_points::BIGINT - COALESCE(
+                (SELECT SUM(points) FROM groupironman.slayer_point_ledger WHERE member_id = $1), 0
+             )::BIGINT
+             FROM groupironman.slayer_stats s
+             WHERE s.member_id = $1"
+        )
+        .await?;
+    let divergence = client
+        .query_opt(&stmt, &[&member_id])
+        .await?
+        .map(|row| row.try_get(0))
+        .transpose()?;
+    Ok(divergence)
+}
+
+/// Get a member's slayer points ledger, computed balance and how it compares with the reported one
+pub async fn get_slayer_points(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
+    let client = pool.get().await?;
+    let member_id = get_member_id(&client, auth.group_id, &member_name).await?;
+
+    let entries_stmt = client
+        .prepare_cached(
+            "SELECT entry_id, kind, points, task_id, description, created_at
+             FROM groupironman.slayer_point_ledger
+             WHERE member_id = $1
+             ORDER BY created_at DESC, entry_id DESC"
+        )
+        .await?;
+
+    let entries = client
+        .query(&entries_stmt, &[&member_id])
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(SlayerLedgerEntry {
+                entry_id: row.try_get(0)?,
+                kind: row.try_get(1)?,
+                points: row.try_get(2)?,
+                task_id: row.try_get(3)?,
+                description: row.try_get(4)?,
+                created_at: row.try_get(5)?,
+            })
+        })
+        .collect::<Result<Vec<_>, tokio_postgres::Error>>()?;
+
+    let earned: i64 = entries.iter().filter(|entry| entry.points > 0).map(|entry| entry.points as i64).sum();
+    let spent: i64 = entries.iter().filter(|entry| entry.points < 0).map(|entry| -entry.points as i64).sum();
+    let computed_balance = earned - spent;
+
+    let stats_stmt = client
+        .prepare_cached("SELECT slayer_points FROM groupironman.slayer_stats WHERE member_id = $1")
+        .await?;
+    let reported_balance: Option<i32> = client
+        .query_opt(&stats_stmt, &[&member_id])
+        .await?
+        .map(|row| row.try_get(0))
+        .transpose()?;
+    let divergence = reported_balance.map(|reported| reported as i64 - computed_balance);
+
+    Ok(HttpResponse::Ok().json(SlayerPointsSummary {
+        member_name,
+        earned,
+        spent,
+        computed_balance,
+        reported_balance,
+        divergence,
+        is_consistent: divergence.is_none_or(|divergence| divergence == 0),
+        entries,
+    }))
+}
+
+/// Record slayer points spent on an unlock, extension, block or skip
+///
+/// Unlocks, extensions and blocks are also added to the member's slayer
+/// preferences, so assignment probabilities follow what they've bought.
+pub async fn spend_slayer_points(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, String)>,
+    request: web::Json<SlayerSpendRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
+
+    let points = match request.points.or_else(|| request.kind.default_cost()) {
+        Some(points) if points > 0 => points,
+        _ => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": "points must be a positive number"
+            })));
+        }
+    };
+    let reward = request.reward.as_deref().map(str::trim).filter(|reward| !reward.is_empty());
+    if reward.is_none() && request.kind != SlayerSpendKind::Skip {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "reward is required"
+        })));
+    }
+
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    let member_id = get_member_id(&transaction, auth.group_id, &member_name).await?;
+
+    // Keep the member's preferences in step with what they've bought
+    if let Some(reward) = reward.filter(|_| request.kind != SlayerSpendKind::Skip) {
+        let mut preferences = load_preferences(&transaction, member_id).await?;
+        match request.kind {
+            SlayerSpendKind::Block => preferences.blocked_tasks.push(reward.to_string()),
+            _ => preferences.unlocks.push(reward.to_string()),
+        }
+        let preferences = match validate_preferences(preferences) {
+            Ok(preferences) => preferences,
+            Err(message) => {
+                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "status": "error",
+                    "message": message
+                })));
+            }
+        };
+
+        let preferences_stmt = transaction
+            .prepare_cached(
+                "INSERT INTO groupironman.slayer_preferences
+                 (member_id, blocked_tasks, unlocks, completed_quests, updated_at)
+                 VALUES ($1, $2, $3, $4, NOW())
+                 ON CONFLICT (member_id)
+                 DO UPDATE SET blocked_tasks = $2, unlocks = $3, completed_quests = $4, updated_at = NOW()"
+            )
+            .await?;
+        transaction
+            .execute(
+                &preferences_stmt,
+                &[
+                    &member_id,
+                    &preferences.blocked_tasks,
+                    &preferences.unlocks,
+                    &preferences.completed_quests,
+                ],
+            )
+            .await?;
+    }
+
+    ensure_opening_balance(&transaction, member_id).await?;
+    let insert_stmt = transaction
+        .prepare_cached(
+            "INSERT INTO groupironman.slayer_point_ledger (member_id, kind, points, description)
+             VALUES ($1, $2, $3, $4)
+             RETURNING entry_id, created_at"
+        )
+        .await?;
+    let row = transaction
+        .query_one(&insert_stmt, &[&member_id, &request.kind.as_str(), &-points, &reward])
+        .await?;
+    let entry = SlayerLedgerEntry {
+        entry_id: row.try_get(0)?,
+        kind: request.kind.as_str().to_string(),
+        points: -points,
+        task_id: None,
+        description: reward.map(str::to_string),
+        created_at: row.try_get(1)?,
+    };
+
+    let divergence = balance_divergence(&transaction, member_id).await?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "entry": entry,
+        "balance_divergence": divergence,
+    })))
+}
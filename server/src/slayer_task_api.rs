@@ -1,10 +1,53 @@
 use crate::auth_middleware::AuthedGroupId;
-use crate::db::{get_member_id};
+use crate::db::{get_group_members, get_member_id};
 use crate::error::ApiError;
+use crate::exports::{stream_export, ExportParams};
+use crate::leaderboards::{period_windows, LeaderboardPeriod};
+use crate::models::SHARED_MEMBER;
+use crate::scoring_rules::stored_skill_xp;
+use crate::slayer_assignments::level_for_xp;
+use crate::slayer_points::{balance_divergence, is_reset_master, record_task_completion, record_task_skip};
+use crate::time_zones::group_time_zone;
 use actix_web::{web, HttpResponse};
 use deadpool_postgres::Pool;
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod slayer_tests {
+    use super::*;
+
+    #[test]
+    fn longest_streak_counts_consecutive_completions() {
+        assert_eq!(longest_streak(&[]), 0);
+        assert_eq!(longest_streak(&[true, true, false, true, true, true, false]), 3);
+        assert_eq!(longest_streak(&[false, false]), 0);
+    }
+
+    #[test]
+    fn kill_rate_from_progress_reports() {
+        let start = DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(kill_rate(&[]), None);
+        assert_eq!(kill_rate(&[(start, 100)]), None);
+
+        let reports = [
+            (start, 100),
+            (start + Duration::minutes(30), 80),
+            (start + Duration::minutes(60), 60),
+        ];
+        assert_eq!(kill_rate(&reports), Some(40.0));
+
+        // An extension raises the count, so only reports after it are used
+        let reports = [
+            (start, 10),
+            (start + Duration::minutes(30), 5),
+            (start + Duration::minutes(31), 105),
+            (start + Duration::minutes(61), 75),
+        ];
+        assert_eq!(kill_rate(&reports), Some(60.0));
+    }
+}
 
 /// Request to submit a new slayer task for a player
 #[derive(Deserialize)]
@@ -73,6 +116,9 @@ pub struct SlayerTask {
     
     /// Time when this task was completed (if completed)
     pub completed_at: Option<DateTime<Utc>>,
+    
+    /// Kills left at the last progress report, if the plugin has sent any
+    pub kills_remaining: Option<i32>,
 }
 
 /// Get the current slayer task and task history for a player
@@ -84,22 +130,23 @@ pub struct SlayerTask {
 pub async fn get_slayer_task(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    member_name: web::Path<String>,
+    path: web::Path<(String, String)>,
 ) -> Result<HttpResponse, ApiError> {
-    let mut client = pool.get().await?;
+    let (_group_name, member_name) = path.into_inner();
+    let client = pool.get().await?;
     
     // Get member ID
-    let member_id = get_member_id(&client, auth.0, &member_name).await?;
+    let member_id = get_member_id(&client, auth.group_id, &member_name).await?;
     
     // Get current task
     let current_task_stmt = client
         .prepare_cached(
             "SELECT 
                 task_id, monster_name, quantity, slayer_master, 
-                is_complete, is_boss_task, assigned_at, completed_at 
+                is_complete, is_boss_task, assigned_at, completed_at, kills_remaining 
              FROM groupironman.slayer_tasks 
              WHERE member_id = $1 
-             AND is_complete = false
+             AND is_complete = false AND skipped_at IS NULL
              ORDER BY assigned_at DESC 
              LIMIT 1"
         )
@@ -119,6 +166,7 @@ pub async fn get_slayer_task(
             is_boss_task: row.try_get(5)?,
             assigned_at: row.try_get(6)?,
             completed_at: row.try_get(7)?,
+            kills_remaining: row.try_get(8)?,
         })
     } else {
         None
@@ -129,7 +177,7 @@ pub async fn get_slayer_task(
         .prepare_cached(
             "SELECT 
                 task_id, monster_name, quantity, slayer_master, 
-                is_complete, is_boss_task, assigned_at, completed_at 
+                is_complete, is_boss_task, assigned_at, completed_at, kills_remaining 
              FROM groupironman.slayer_tasks 
              WHERE member_id = $1 
              AND is_complete = true
@@ -142,21 +190,22 @@ pub async fn get_slayer_task(
         .query(&history_stmt, &[&member_id])
         .await?;
     
-    let task_history: Vec<SlayerTask> = history_rows
+    let task_history = history_rows
         .iter()
         .map(|row| {
-            SlayerTask {
-                task_id: row.try_get(0).unwrap(),
-                monster_name: row.try_get(1).unwrap(),
-                quantity: row.try_get(2).unwrap(),
-                slayer_master: row.try_get(3).unwrap(),
-                is_complete: row.try_get(4).unwrap(),
-                is_boss_task: row.try_get(5).unwrap(),
-                assigned_at: row.try_get(6).unwrap(),
-                completed_at: row.try_get(7).unwrap(),
-            }
+            Ok(SlayerTask {
+                task_id: row.try_get(0)?,
+                monster_name: row.try_get(1)?,
+                quantity: row.try_get(2)?,
+                slayer_master: row.try_get(3)?,
+                is_complete: row.try_get(4)?,
+                is_boss_task: row.try_get(5)?,
+                assigned_at: row.try_get(6)?,
+                completed_at: row.try_get(7)?,
+                kills_remaining: row.try_get(8)?,
+            })
         })
-        .collect();
+        .collect::<Result<Vec<_>, tokio_postgres::Error>>()?;
     
     // Get slayer points and streak
     let stats_stmt = client
@@ -177,7 +226,7 @@ pub async fn get_slayer_task(
     
     // Return the full response
     Ok(HttpResponse::Ok().json(SlayerTaskResponse {
-        member_name: member_name.into_inner(),
+        member_name,
         current_task,
         task_history,
         slayer_points,
@@ -188,35 +237,69 @@ pub async fn get_slayer_task(
 /// Submit a new slayer task for a player
 /// 
 /// This endpoint handles:
-/// - Completing the current task if one exists
+/// - Closing the current task if one exists
 /// - Assigning a new task
 /// - Updating player slayer points and streak
+/// 
+/// A current task with kills left from progress reports was skipped. One without
+/// any progress reports is assumed complete, as older clients never sent progress.
+/// Completions earn slayer points and skips cost them, unless the new task comes
+/// from a master where resetting is free.
 pub async fn submit_slayer_task(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    member_name: web::Path<String>,
+    path: web::Path<(String, String)>,
     task: web::Json<SlayerTaskSubmission>,
 ) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
     let mut client = pool.get().await?;
     let transaction = client.transaction().await?;
     
     // Get member ID
-    let member_id = get_member_id(&transaction, auth.0, &member_name).await?;
+    let member_id = get_member_id(&transaction, auth.group_id, &member_name).await?;
     
-    // Check and complete any current task
+    // Close any current task, either as complete or as skipped
     let current_task_stmt = transaction
         .prepare_cached(
             "UPDATE groupironman.slayer_tasks 
-             SET is_complete = true, completed_at = NOW() 
-             WHERE member_id = $1 AND is_complete = false
-             RETURNING task_id"
+             SET is_complete = (kills_remaining IS NULL),
+                 completed_at = CASE WHEN kills_remaining IS NULL THEN NOW() END,
+                 skipped_at = CASE WHEN kills_remaining IS NOT NULL THEN NOW() END
+             WHERE member_id = $1 AND is_complete = false AND skipped_at IS NULL
+             RETURNING task_id, slayer_master, is_complete"
         )
         .await?;
     
-    let completed_task = transaction
+    let previous_task = transaction
         .query_opt(&current_task_stmt, &[&member_id])
         .await?;
     
+    // Record points for the previous task before the client's balance is stored.
+    // The streak is only changed when the client reports it or a task is closed.
+    let mut points_earned = None;
+    let mut task_streak = task.task_streak;
+    if let Some(previous_task) = &previous_task {
+        let previous_task_id: i64 = previous_task.try_get(0)?;
+        let previous_master: String = previous_task.try_get(1)?;
+        if previous_task.try_get::<_, bool>(2)? {
+            let streak = match task.task_streak {
+                Some(streak) => streak,
+                None => current_streak(&transaction, member_id).await? + 1,
+            };
+            points_earned = record_task_completion(&transaction, member_id, previous_task_id, &previous_master, streak).await?;
+            task_streak = Some(streak);
+        } else if !is_reset_master(&task.slayer_master) {
+            record_task_skip(&transaction, member_id, previous_task_id).await?;
+        } else if task_streak.is_none() {
+            // Cancelling a task at a reset master ends the streak
+            task_streak = Some(0);
+        }
+    }
+    let previous_completed = previous_task
+        .as_ref()
+        .map(|row| row.try_get::<_, bool>(2))
+        .transpose()?;
+    
     // Insert the new task
     let insert_task_stmt = transaction
         .prepare_cached(
@@ -243,24 +326,32 @@ pub async fn submit_slayer_task(
         .await?
         .try_get::<_, i64>(0)?;
     
-    // Update slayer stats
-    let slayer_points = task.slayer_points.unwrap_or(0);
-    let task_streak = task.task_streak.unwrap_or(0);
-    
+    // Update slayer stats, keeping the stored balance and streak when they didn't change
     let update_stats_stmt = transaction
         .prepare_cached(
             "INSERT INTO groupironman.slayer_stats 
              (member_id, slayer_points, task_streak) 
-             VALUES ($1, $2, $3)
+             VALUES ($1, COALESCE($2, 0), COALESCE($3, 0))
              ON CONFLICT (member_id) 
-             DO UPDATE SET slayer_points = $2, task_streak = $3"
+             DO UPDATE SET slayer_points = COALESCE($2, groupironman.slayer_stats.slayer_points),
+                           task_streak = COALESCE($3, groupironman.slayer_stats.task_streak)"
         )
         .await?;
     
     transaction
-        .execute(&update_stats_stmt, &[&member_id, &slayer_points, &task_streak])
+        .execute(&update_stats_stmt, &[&member_id, &task.slayer_points, &task_streak])
         .await?;
     
+    // Flag a reported balance that doesn't match the ledger
+    let divergence = balance_divergence(&transaction, member_id).await?;
+    if let Some(divergence) = divergence.filter(|divergence| *divergence != 0) {
+        log::warn!(
+            "Slayer points for member {} differ from the ledger by {}",
+            member_id,
+            divergence
+        );
+    }
+    
     // Commit transaction
     transaction.commit().await?;
     
@@ -269,6 +360,699 @@ pub async fn submit_slayer_task(
         "status": "success",
         "message": "Slayer task updated successfully",
         "task_id": new_task_id,
-        "completed_previous": completed_task.is_some(),
+        "completed_previous": previous_completed == Some(true),
+        "skipped_previous": previous_completed == Some(false),
+        "points_earned": points_earned,
+        "balance_divergence": divergence,
     })))
 }
+
+/// Kills-remaining update for the current task, sent by the plugin
+#[derive(Deserialize)]
+pub struct SlayerProgressReport {
+    /// Monster the client thinks the task is for; rejected if it doesn't match
+    pub monster_name: Option<String>,
+    
+    /// Kills left on the task
+    pub kills_remaining: i32,
+}
+
+/// Progress on a slayer task
+#[derive(Serialize)]
+pub struct SlayerTaskProgress {
+    pub task_id: i64,
+    pub monster_name: String,
+    pub quantity: i32,
+    pub kills_remaining: Option<i32>,
+    pub is_complete: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+    
+    /// Time of the most recent progress report
+    pub last_reported_at: Option<DateTime<Utc>>,
+    
+    /// Kills per hour since the task was last extended
+    pub kills_per_hour: Option<f64>,
+    
+    /// Expected completion time at the current kill rate
+    pub estimated_completion: Option<DateTime<Utc>>,
+}
+
+/// Kills per hour from time-ordered `(reported_at, kills_remaining)` reports.
+/// Only reports since the count last went up (a task extension) are used.
+pub fn kill_rate(reports: &[(DateTime<Utc>, i32)]) -> Option<f64> {
+    let start = reports
+        .windows(2)
+        .rposition(|pair| pair[1].1 > pair[0].1)
+        .map_or(0, |position| position + 1);
+    let (first, last) = (reports.get(start)?, reports.last()?);
+    
+    let hours = (last.0 - first.0).num_seconds() as f64 / 3600.0;
+    let kills = first.1 - last.1;
+    if hours <= 0.0 || kills <= 0 {
+        return None;
+    }
+    Some(kills as f64 / hours)
+}
+
+/// Streak last stored for the member, before any task being completed now
+async fn current_streak<C: deadpool_postgres::GenericClient>(client: &C, member_id: i64) -> Result<i32, ApiError> {
+    let stmt = client
+        .prepare_cached("SELECT task_streak FROM groupironman.slayer_stats WHERE member_id = $1")
+        .await?;
+    let streak: Option<i32> = client
+        .query_opt(&stmt, &[&member_id])
+        .await?
+        .map(|row| row.try_get(0))
+        .transpose()?;
+    Ok(streak.unwrap_or(0))
+}
+
+/// Build the progress for a task from its stored reports
+async fn load_task_progress<C: deadpool_postgres::GenericClient>(
+    client: &C,
+    row: &tokio_postgres::Row,
+) -> Result<SlayerTaskProgress, ApiError> {
+    let task_id: i64 = row.try_get(0)?;
+    let kills_remaining: Option<i32> = row.try_get(3)?;
+    let is_complete: bool = row.try_get(4)?;
+    
+    let reports_stmt = client
+        .prepare_cached(
+            "SELECT reported_at, kills_remaining FROM groupironman.slayer_task_progress
+             WHERE task_id = $1
+             ORDER BY reported_at, progress_id"
+        )
+        .await?;
+    
+    let reports = client
+        .query(&reports_stmt, &[&task_id])
+        .await?
+        .iter()
+        .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+        .collect::<Result<Vec<(DateTime<Utc>, i32)>, tokio_postgres::Error>>()?;
+    
+    let kills_per_hour = kill_rate(&reports);
+    let last_reported_at = reports.last().map(|report| report.0);
+    let estimated_completion = match (is_complete, kills_per_hour, kills_remaining, last_reported_at) {
+        (false, Some(rate), Some(remaining), Some(reported_at)) => {
+            Some(reported_at + Duration::seconds((remaining as f64 / rate * 3600.0).round() as i64))
+        }
+        _ => None,
+    };
+    
+    Ok(SlayerTaskProgress {
+        task_id,
+        monster_name: row.try_get(1)?,
+        quantity: row.try_get(2)?,
+        kills_remaining,
+        is_complete,
+        completed_at: row.try_get(5)?,
+        last_reported_at,
+        kills_per_hour,
+        estimated_completion,
+    })
+}
+
+/// Record a kills-remaining update for a player's current task
+/// 
+/// The task is completed when the count reaches zero.
+pub async fn report_task_progress(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, String)>,
+    report: web::Json<SlayerProgressReport>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
+    
+    if report.kills_remaining < 0 {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "kills_remaining cannot be negative"
+        })));
+    }
+    
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    let member_id = get_member_id(&transaction, auth.group_id, &member_name).await?;
+    
+    // Lock the current task so concurrent reports are applied in order
+    let current_task_stmt = transaction
+        .prepare_cached(
+            "SELECT task_id, monster_name, kills_remaining, slayer_master FROM groupironman.slayer_tasks
+             WHERE member_id = $1 AND is_complete = false AND skipped_at IS NULL
+             ORDER BY assigned_at DESC
+             LIMIT 1
+             FOR UPDATE"
+        )
+        .await?;
+    
+    let current_task = match transaction.query_opt(&current_task_stmt, &[&member_id]).await? {
+        Some(row) => row,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "status": "error",
+                "message": "No current slayer task"
+            })));
+        }
+    };
+    let task_id: i64 = current_task.try_get(0)?;
+    let monster_name: String = current_task.try_get(1)?;
+    let previous_remaining: Option<i32> = current_task.try_get(2)?;
+    let slayer_master: String = current_task.try_get(3)?;
+    
+    if let Some(reported_monster) = &report.monster_name {
+        if !reported_monster.eq_ignore_ascii_case(&monster_name) {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "status": "error",
+                "message": format!("Current slayer task is {}, not {}", monster_name, reported_monster)
+            })));
+        }
+    }
+    
+    // Repeated counts carry no new information for the kill rate
+    if previous_remaining != Some(report.kills_remaining) {
+        let insert_stmt = transaction
+            .prepare_cached(
+                "INSERT INTO groupironman.slayer_task_progress (task_id, kills_remaining, reported_at)
+                 VALUES ($1, $2, NOW())"
+            )
+            .await?;
+        transaction.execute(&insert_stmt, &[&task_id, &report.kills_remaining]).await?;
+    }
+    
+    let update_stmt = transaction
+        .prepare_cached(
+            "UPDATE groupironman.slayer_tasks
+             SET kills_remaining = $2,
+                 is_complete = ($2 = 0),
+                 completed_at = CASE WHEN $2 = 0 THEN NOW() END
+             WHERE task_id = $1
+             RETURNING task_id, monster_name, quantity, kills_remaining, is_complete, completed_at"
+        )
+        .await?;
+    let task = transaction.query_one(&update_stmt, &[&task_id, &report.kills_remaining]).await?;
+    
+    // A finished task extends the streak and earns points from its master
+    if report.kills_remaining == 0 {
+        let streak = current_streak(&transaction, member_id).await? + 1;
+        record_task_completion(&transaction, member_id, task_id, &slayer_master, streak).await?;
+        
+        let streak_stmt = transaction
+            .prepare_cached(
+                "INSERT INTO groupironman.slayer_stats (member_id, task_streak)
+                 VALUES ($1, $2)
+                 ON CONFLICT (member_id) DO UPDATE SET task_streak = $2"
+            )
+            .await?;
+        transaction.execute(&streak_stmt, &[&member_id, &streak]).await?;
+    }
+    
+    let progress = load_task_progress(&transaction, &task).await?;
+    transaction.commit().await?;
+    
+    Ok(HttpResponse::Ok().json(progress))
+}
+
+/// Get progress, kill rate and estimated completion for a player's current task
+pub async fn get_task_progress(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
+    let client = pool.get().await?;
+    let member_id = get_member_id(&client, auth.group_id, &member_name).await?;
+    
+    let current_task_stmt = client
+        .prepare_cached(
+            "SELECT task_id, monster_name, quantity, kills_remaining, is_complete, completed_at
+             FROM groupironman.slayer_tasks
+             WHERE member_id = $1 AND is_complete = false AND skipped_at IS NULL
+             ORDER BY assigned_at DESC
+             LIMIT 1"
+        )
+        .await?;
+    
+    match client.query_opt(&current_task_stmt, &[&member_id]).await? {
+        Some(row) => Ok(HttpResponse::Ok().json(load_task_progress(&client, &row).await?)),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "No current slayer task"
+        }))),
+    }
+}
+
+/// Slayer summary for one member of the group
+#[derive(Serialize)]
+pub struct MemberSlayerOverview {
+    pub member_name: String,
+    
+    /// Slayer level from the member's skills
+    pub slayer_level: i32,
+    pub slayer_points: i32,
+    pub task_streak: i32,
+    pub current_task: Option<SlayerTask>,
+    pub tasks_completed: i64,
+    
+    /// Tasks completed since the start of the week (Monday, in the group's time zone)
+    pub tasks_completed_this_week: i64,
+}
+
+/// Slayer summary for the whole group
+#[derive(Serialize)]
+pub struct GroupSlayerOverview {
+    pub members: Vec<MemberSlayerOverview>,
+    pub week_start: Option<DateTime<Utc>>,
+    pub total_points: i64,
+    pub total_tasks_completed: i64,
+    pub total_tasks_completed_this_week: i64,
+    pub highest_streak: i32,
+}
+
+/// Get every member's current task, points, streak, slayer level and weekly completions
+pub async fn get_group_slayer_overview(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let member_names = get_group_members(&client, auth.group_id).await?;
+    let tz = group_time_zone(&client, auth.group_id).await?;
+    let (week, _) = period_windows(LeaderboardPeriod::Week, None, None, Utc::now(), tz)
+        .expect("week windows never fail");
+    
+    // Stats, task counts and the current task for every member at once
+    let stmt = client
+        .prepare_cached(
+            "SELECT 
+                m.member_name,
+                m.skills,
+                COALESCE(s.slayer_points, 0),
+                COALESCE(s.task_streak, 0),
+                (SELECT COUNT(*) FROM groupironman.slayer_tasks t
+                 WHERE t.member_id = m.member_id AND t.is_complete),
+                (SELECT COUNT(*) FROM groupironman.slayer_tasks t
+                 WHERE t.member_id = m.member_id AND t.is_complete AND t.completed_at >= $2),
+                c.task_id, c.monster_name, c.quantity, c.slayer_master,
+                c.is_complete, c.is_boss_task, c.assigned_at, c.completed_at, c.kills_remaining
+             FROM groupironman.members m
+             LEFT JOIN groupironman.slayer_stats s ON s.member_id = m.member_id
+             LEFT JOIN LATERAL (
+                SELECT * FROM groupironman.slayer_tasks t
+                WHERE t.member_id = m.member_id AND t.is_complete = false AND t.skipped_at IS NULL
+                ORDER BY t.assigned_at DESC
+                LIMIT 1
+             ) c ON true
+             WHERE m.group_id = $1"
+        )
+        .await?;
+    
+    let mut rows_by_member = HashMap::new();
+    for row in client.query(&stmt, &[&auth.group_id, &week.start]).await? {
+        let member_name: String = row.try_get(0)?;
+        rows_by_member.insert(member_name, row);
+    }
+    
+    let mut members = Vec::new();
+    for member_name in member_names {
+        if member_name == SHARED_MEMBER {
+            continue;
+        }
+        let row = match rows_by_member.get(&member_name) {
+            Some(row) => row,
+            None => continue,
+        };
+        
+        let skills: Option<Vec<i32>> = row.try_get(1)?;
+        let slayer_xp = skills.and_then(|skills| stored_skill_xp(&skills, "slayer"));
+        let current_task = match row.try_get::<_, Option<i64>>(6)? {
+            Some(task_id) => Some(SlayerTask {
+                task_id,
+                monster_name: row.try_get(7)?,
+                quantity: row.try_get(8)?,
+                slayer_master: row.try_get(9)?,
+                is_complete: row.try_get(10)?,
+                is_boss_task: row.try_get(11)?,
+                assigned_at: row.try_get(12)?,
+                completed_at: row.try_get(13)?,
+                kills_remaining: row.try_get(14)?,
+            }),
+            None => None,
+        };
+        
+        members.push(MemberSlayerOverview {
+            member_name,
+            slayer_level: level_for_xp(slayer_xp.unwrap_or(0)),
+            slayer_points: row.try_get(2)?,
+            task_streak: row.try_get(3)?,
+            current_task,
+            tasks_completed: row.try_get(4)?,
+            tasks_completed_this_week: row.try_get(5)?,
+        });
+    }
+    members.sort_by(|a, b| b.slayer_points.cmp(&a.slayer_points).then_with(|| a.member_name.cmp(&b.member_name)));
+    
+    Ok(HttpResponse::Ok().json(GroupSlayerOverview {
+        week_start: week.start,
+        total_points: members.iter().map(|member| member.slayer_points as i64).sum(),
+        total_tasks_completed: members.iter().map(|member| member.tasks_completed).sum(),
+        total_tasks_completed_this_week: members.iter().map(|member| member.tasks_completed_this_week).sum(),
+        highest_streak: members.iter().map(|member| member.task_streak).max().unwrap_or(0),
+        members,
+    }))
+}
+
+/// Filters for slayer analytics
+#[derive(Deserialize)]
+pub struct SlayerAnalyticsParams {
+    /// Only include this member's tasks; the whole group when omitted
+    pub member_name: Option<String>,
+    
+    /// Only include tasks assigned at or after this time
+    pub start: Option<DateTime<Utc>>,
+    
+    /// Only include tasks assigned before this time
+    pub end: Option<DateTime<Utc>>,
+    
+    /// Number of monsters to include in the most frequent list
+    pub top: Option<i64>,
+}
+
+/// Task statistics for one slayer master
+#[derive(Serialize)]
+pub struct SlayerMasterStats {
+    pub slayer_master: String,
+    pub task_count: i64,
+    pub completed_count: i64,
+    pub average_task_size: Option<f64>,
+    pub boss_task_count: i64,
+}
+
+/// How often a monster has been assigned
+#[derive(Serialize)]
+pub struct SlayerMonsterStats {
+    pub monster_name: String,
+    pub task_count: i64,
+    pub total_quantity: i64,
+}
+
+/// Time taken to complete tasks, in seconds
+#[derive(Serialize)]
+pub struct SlayerDurationStats {
+    pub average_seconds: Option<f64>,
+    pub median_seconds: Option<f64>,
+    pub fastest_seconds: Option<f64>,
+    pub slowest_seconds: Option<f64>,
+}
+
+/// Streaks for one member
+#[derive(Serialize)]
+pub struct SlayerStreakStats {
+    pub member_name: String,
+    
+    /// Longest run of consecutively completed tasks in the range
+    pub longest_streak: i32,
+    
+    /// Streak last reported by the member's client
+    pub reported_streak: i32,
+}
+
+/// Slayer analytics for a member or the whole group
+#[derive(Serialize)]
+pub struct SlayerAnalytics {
+    pub member_name: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub total_tasks: i64,
+    pub completed_tasks: i64,
+    pub average_task_size: Option<f64>,
+    
+    /// Share of tasks that were boss tasks, from 0 to 1
+    pub boss_task_rate: Option<f64>,
+    pub durations: SlayerDurationStats,
+    pub masters: Vec<SlayerMasterStats>,
+    pub top_monsters: Vec<SlayerMonsterStats>,
+    pub streaks: Vec<SlayerStreakStats>,
+}
+
+/// Longest run of completed tasks in a member's assignment-ordered history
+pub fn longest_streak(completed: &[bool]) -> i32 {
+    let mut longest = 0;
+    let mut current = 0;
+    for is_complete in completed {
+        if *is_complete {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+// Shared filter for the analytics queries; expects the tasks aliased as t and members as m
+const SLAYER_ANALYTICS_FILTER: &str = "m.group_id = $1
+    AND ($2::TEXT IS NULL OR m.member_name = $2)
+    AND ($3::TIMESTAMPTZ IS NULL OR t.assigned_at >= $3)
+    AND ($4::TIMESTAMPTZ IS NULL OR t.assigned_at < $4)";
+
+/// Get slayer analytics for a member or the whole group
+/// 
+/// This endpoint covers:
+/// - Tasks per slayer master
+/// - Most frequently assigned monsters
+/// - Average task size and boss task rate
+/// - Completion durations
+/// - Longest task streaks
+pub async fn get_slayer_analytics(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    params: web::Query<SlayerAnalyticsParams>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let top = params.top.unwrap_or(10).clamp(1, 50);
+    let filter_params: [&(dyn tokio_postgres::types::ToSql + Sync); 4] = [
+        &auth.group_id,
+        &params.member_name,
+        &params.start,
+        &params.end,
+    ];
+    
+    // Overall counts, sizes and durations
+    let summary_stmt = client
+        .prepare_cached(&format!(
+            "WITH durations AS (
+                SELECT t.*, EXTRACT(EPOCH FROM t.completed_at - t.assigned_at)::FLOAT8 AS seconds
+                FROM groupironman.slayer_tasks t
+                JOIN groupironman.members m ON m.member_id = t.member_id
+                WHERE {}
+             )
+             SELECT 
+                COUNT(*),
+                COUNT(*) FILTER (WHERE is_complete),
+                AVG(quantity)::FLOAT8,
+                COUNT(*) FILTER (WHERE is_boss_task)::FLOAT8 / NULLIF(COUNT(*), 0),
+                AVG(seconds) FILTER (WHERE is_complete),
+                PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY seconds) FILTER (WHERE is_complete),
+                MIN(seconds) FILTER (WHERE is_complete),
+                MAX(seconds) FILTER (WHERE is_complete)
+             FROM durations",
+            SLAYER_ANALYTICS_FILTER
+        ))
+        .await?;
+    
+    let summary = client.query_one(&summary_stmt, &filter_params).await?;
+    
+    // Tasks per slayer master
+    let masters_stmt = client
+        .prepare_cached(&format!(
+            "SELECT 
+                t.slayer_master,
+                COUNT(*),
+                COUNT(*) FILTER (WHERE t.is_complete),
+                AVG(t.quantity)::FLOAT8,
+                COUNT(*) FILTER (WHERE t.is_boss_task)
+             FROM groupironman.slayer_tasks t
+             JOIN groupironman.members m ON m.member_id = t.member_id
+             WHERE {}
+             GROUP BY t.slayer_master
+             ORDER BY COUNT(*) DESC, t.slayer_master",
+            SLAYER_ANALYTICS_FILTER
+        ))
+        .await?;
+    
+    let masters = client
+        .query(&masters_stmt, &filter_params)
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(SlayerMasterStats {
+                slayer_master: row.try_get(0)?,
+                task_count: row.try_get(1)?,
+                completed_count: row.try_get(2)?,
+                average_task_size: row.try_get(3)?,
+                boss_task_count: row.try_get(4)?,
+            })
+        })
+        .collect::<Result<Vec<_>, tokio_postgres::Error>>()?;
+    
+    // Most frequently assigned monsters
+    let monsters_stmt = client
+        .prepare_cached(&format!(
+            "SELECT t.monster_name, COUNT(*), SUM(t.quantity)::BIGINT
+             FROM groupironman.slayer_tasks t
+             JOIN groupironman.members m ON m.member_id = t.member_id
+             WHERE {}
+             GROUP BY t.monster_name
+             ORDER BY COUNT(*) DESC, t.monster_name
+             LIMIT $5",
+            SLAYER_ANALYTICS_FILTER
+        ))
+        .await?;
+    
+    let top_monsters = client
+        .query(
+            &monsters_stmt,
+            &[filter_params[0], filter_params[1], filter_params[2], filter_params[3], &top],
+        )
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(SlayerMonsterStats {
+                monster_name: row.try_get(0)?,
+                task_count: row.try_get(1)?,
+                total_quantity: row.try_get(2)?,
+            })
+        })
+        .collect::<Result<Vec<_>, tokio_postgres::Error>>()?;
+    
+    // Task history in assignment order for the streaks
+    let history_stmt = client
+        .prepare_cached(&format!(
+            "SELECT m.member_name, COALESCE(t.is_complete, false), COALESCE(s.task_streak, 0)
+             FROM groupironman.slayer_tasks t
+             JOIN groupironman.members m ON m.member_id = t.member_id
+             LEFT JOIN groupironman.slayer_stats s ON s.member_id = t.member_id
+             WHERE {}
+             ORDER BY m.member_name, t.assigned_at",
+            SLAYER_ANALYTICS_FILTER
+        ))
+        .await?;
+    
+    let mut streaks: Vec<SlayerStreakStats> = Vec::new();
+    let mut completed: Vec<bool> = Vec::new();
+    for row in client.query(&history_stmt, &filter_params).await? {
+        let member_name: String = row.try_get(0)?;
+        let is_complete: bool = row.try_get(1)?;
+        let reported_streak: i32 = row.try_get(2)?;
+        
+        if streaks.last().is_none_or(|streak| streak.member_name != member_name) {
+            if let Some(streak) = streaks.last_mut() {
+                streak.longest_streak = longest_streak(&completed);
+            }
+            completed.clear();
+            streaks.push(SlayerStreakStats {
+                member_name,
+                longest_streak: 0,
+                reported_streak,
+            });
+        }
+        completed.push(is_complete);
+    }
+    if let Some(streak) = streaks.last_mut() {
+        streak.longest_streak = longest_streak(&completed);
+    }
+    streaks.sort_by_key(|streak| std::cmp::Reverse(streak.longest_streak));
+    
+    Ok(HttpResponse::Ok().json(SlayerAnalytics {
+        member_name: params.member_name.clone(),
+        start: params.start,
+        end: params.end,
+        total_tasks: summary.try_get(0)?,
+        completed_tasks: summary.try_get(1)?,
+        average_task_size: summary.try_get(2)?,
+        boss_task_rate: summary.try_get(3)?,
+        durations: SlayerDurationStats {
+            average_seconds: summary.try_get(4)?,
+            median_seconds: summary.try_get(5)?,
+            fastest_seconds: summary.try_get(6)?,
+            slowest_seconds: summary.try_get(7)?,
+        },
+        masters,
+        top_monsters,
+        streaks,
+    }))
+}
+
+const SLAYER_EXPORT_COLUMNS: &[&str] = &[
+    "task_id",
+    "member_name",
+    "monster_name",
+    "quantity",
+    "slayer_master",
+    "is_boss_task",
+    "is_complete",
+    "kills_remaining",
+    "assigned_at",
+    "completed_at",
+    "skipped_at",
+];
+
+fn slayer_export_values(row: &tokio_postgres::Row) -> Result<Vec<serde_json::Value>, tokio_postgres::Error> {
+    let timestamp = |index: usize| -> Result<serde_json::Value, tokio_postgres::Error> {
+        Ok(row
+            .try_get::<_, Option<DateTime<Utc>>>(index)?
+            .map(|timestamp| timestamp.to_rfc3339())
+            .into())
+    };
+    Ok(vec![
+        row.try_get::<_, i64>(0)?.into(),
+        row.try_get::<_, String>(1)?.into(),
+        row.try_get::<_, String>(2)?.into(),
+        row.try_get::<_, i32>(3)?.into(),
+        row.try_get::<_, String>(4)?.into(),
+        row.try_get::<_, bool>(5)?.into(),
+        row.try_get::<_, bool>(6)?.into(),
+        row.try_get::<_, Option<i32>>(7)?.into(),
+        timestamp(8)?,
+        timestamp(9)?,
+        timestamp(10)?,
+    ])
+}
+
+/// Export the group's slayer task history as CSV or newline-delimited JSON
+/// 
+/// Takes the same member and date filters as the analytics.
+pub async fn export_slayer_tasks(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    params: web::Query<SlayerAnalyticsParams>,
+    export: web::Query<ExportParams>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let filter_params: [&(dyn tokio_postgres::types::ToSql + Sync); 4] = [
+        &auth.group_id,
+        &params.member_name,
+        &params.start,
+        &params.end,
+    ];
+    
+    stream_export(
+        client,
+        &format!(
+            "SELECT t.task_id, m.member_name, t.monster_name, t.quantity, t.slayer_master,
+                COALESCE(t.is_boss_task, false), COALESCE(t.is_complete, false), t.kills_remaining,
+                t.assigned_at, t.completed_at, t.skipped_at
+             FROM groupironman.slayer_tasks t
+             JOIN groupironman.members m ON m.member_id = t.member_id
+             WHERE {}
+             ORDER BY t.assigned_at, t.task_id",
+            SLAYER_ANALYTICS_FILTER
+        ),
+        &filter_params,
+        export.format,
+        "slayer-tasks",
+        SLAYER_EXPORT_COLUMNS,
+        slayer_export_values,
+    )
+    .await
+}
@@ -0,0 +1,20 @@
+-- Peer review of activity completions that require proof.
+-- Completions needing proof start out pending; points are awarded when another member approves them.
+
+ALTER TABLE groupironman.player_activities ALTER COLUMN completion_date TYPE TIMESTAMPTZ;
+ALTER TABLE groupironman.player_activities ADD COLUMN IF NOT EXISTS review_status VARCHAR(20) NOT NULL DEFAULT 'approved';
+ALTER TABLE groupironman.player_activities ADD COLUMN IF NOT EXISTS reviewed_by_member_id BIGINT
+    REFERENCES groupironman.members(member_id) ON DELETE SET NULL;
+ALTER TABLE groupironman.player_activities ADD COLUMN IF NOT EXISTS reviewed_at TIMESTAMPTZ;
+ALTER TABLE groupironman.player_activities ADD COLUMN IF NOT EXISTS review_note TEXT;
+
+-- Unverified completions so far were waiting on proof that could never be checked
+UPDATE groupironman.player_activities SET review_status = 'pending' WHERE verified IS NOT TRUE;
+
+ALTER TABLE groupironman.player_activities DROP CONSTRAINT IF EXISTS chk_player_activities_review_status;
+ALTER TABLE groupironman.player_activities ADD CONSTRAINT chk_player_activities_review_status
+    CHECK (review_status IN ('pending', 'approved', 'rejected'));
+
+-- Index for the pending review queue
+CREATE INDEX IF NOT EXISTS idx_player_activities_pending ON groupironman.player_activities(completion_date)
+    WHERE review_status = 'pending';
@@ -0,0 +1,39 @@
+-- Files attached to valuable drops, activity proof and boss strategies. The file
+-- contents live in the blob store under storage_key.
+CREATE TABLE IF NOT EXISTS groupironman.attachments (
+    attachment_id BIGSERIAL PRIMARY KEY,
+    group_id BIGINT NOT NULL REFERENCES groupironman.groups(group_id) ON DELETE CASCADE,
+    drop_id BIGINT REFERENCES groupironman.valuable_drops(drop_id) ON DELETE CASCADE,
+    player_activity_id BIGINT REFERENCES groupironman.player_activities(player_activity_id) ON DELETE CASCADE,
+    -- boss_strategies lives outside this schema, so its attachments are removed by the strategy delete
+    boss_strategy_id BIGINT,
+    storage_key TEXT NOT NULL UNIQUE,
+    content_type VARCHAR(100) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT chk_attachments_one_parent CHECK (num_nonnulls(drop_id, player_activity_id, boss_strategy_id) = 1)
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_drop_id ON groupironman.attachments(drop_id) WHERE drop_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_attachments_player_activity_id ON groupironman.attachments(player_activity_id) WHERE player_activity_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_attachments_boss_strategy_id ON groupironman.attachments(boss_strategy_id) WHERE boss_strategy_id IS NOT NULL;
+
+-- Blobs waiting to be removed from the store. Rows are queued whenever an attachment
+-- is deleted, including through a cascade, and drained by the cleanup job.
+CREATE TABLE IF NOT EXISTS groupironman.attachment_blob_deletions (
+    deletion_id BIGSERIAL PRIMARY KEY,
+    storage_key TEXT NOT NULL,
+    queued_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE OR REPLACE FUNCTION groupironman.queue_attachment_blob_deletion() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO groupironman.attachment_blob_deletions (storage_key) VALUES (OLD.storage_key);
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_attachments_queue_blob_deletion ON groupironman.attachments;
+CREATE TRIGGER trg_attachments_queue_blob_deletion
+    AFTER DELETE ON groupironman.attachments
+    FOR EACH ROW EXECUTE FUNCTION groupironman.queue_attachment_blob_deletion();
@@ -0,0 +1,9 @@
+-- Token for a group's iCalendar subscription URL. Calendar apps can't send the
+-- group's Authorization header, so the feed is authorized by this token instead.
+-- Only the hash is stored; replacing or deleting the row revokes the old URL.
+CREATE TABLE IF NOT EXISTS groupironman.calendar_feed_tokens (
+    group_id BIGINT PRIMARY KEY REFERENCES groupironman.groups(group_id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ
+);
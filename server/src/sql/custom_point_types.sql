@@ -37,17 +37,7 @@ CREATE TABLE IF NOT EXISTS groupironman.milestone_contributions (
     UNIQUE(milestone_id, member_id)
 );
 
--- Table for point events (when points are earned)
-CREATE TABLE IF NOT EXISTS groupironman.point_events (
-    event_id SERIAL PRIMARY KEY,
-    member_id INT NOT NULL REFERENCES groupironman.members(member_id),
-    point_type_id INT NOT NULL REFERENCES groupironman.custom_point_types(point_type_id),
-    points INT NOT NULL,
-    event_description TEXT,
-    source_type VARCHAR(50) NOT NULL,
-    source_id VARCHAR(100),
-    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
-);
+-- Point events live in point_ledger.sql
 
 -- Insert default point types
 INSERT INTO groupironman.custom_point_types (type_name, description, color_hex)
@@ -62,5 +52,3 @@ ON CONFLICT (type_name) DO NOTHING;
 -- Index for performance
 CREATE INDEX IF NOT EXISTS idx_milestone_contributions_milestone ON groupironman.milestone_contributions(milestone_id);
 CREATE INDEX IF NOT EXISTS idx_milestone_contributions_member ON groupironman.milestone_contributions(member_id);
-CREATE INDEX IF NOT EXISTS idx_point_events_member ON groupironman.point_events(member_id);
-CREATE INDEX IF NOT EXISTS idx_point_events_point_type ON groupironman.point_events(point_type_id);
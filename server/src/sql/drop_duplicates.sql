@@ -0,0 +1,18 @@
+-- How a drop was recorded: by the plugin, entered by hand or imported from the loot tracker.
+-- Drops recorded before this was tracked have no entry source.
+ALTER TABLE groupironman.valuable_drops ADD COLUMN IF NOT EXISTS entry_source VARCHAR(20);
+ALTER TABLE groupironman.valuable_drops DROP CONSTRAINT IF EXISTS chk_valuable_drops_entry_source;
+ALTER TABLE groupironman.valuable_drops ADD CONSTRAINT chk_valuable_drops_entry_source
+    CHECK (entry_source IN ('plugin', 'manual', 'import'));
+
+-- Duplicate detection looks for drops of the same item by the same member close in time
+CREATE INDEX IF NOT EXISTS idx_valuable_drops_member_item_timestamp ON groupironman.valuable_drops(member_id, item_id, timestamp);
+
+-- Pairs of drops someone confirmed are not duplicates. drop_id is always the lower of the two.
+CREATE TABLE IF NOT EXISTS groupironman.valuable_drop_duplicate_dismissals (
+    drop_id BIGINT NOT NULL REFERENCES groupironman.valuable_drops(drop_id) ON DELETE CASCADE,
+    other_drop_id BIGINT NOT NULL REFERENCES groupironman.valuable_drops(drop_id) ON DELETE CASCADE,
+    dismissed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (drop_id, other_drop_id),
+    CHECK (drop_id < other_drop_id)
+);
@@ -0,0 +1,57 @@
+-- Group-scoped activity catalog layered on the global defaults.
+-- Rows in custom_activities with a NULL group_id are the global defaults every group sees;
+-- rows with a group_id belong to that group only. Groups customize or disable a default
+-- through group_activity_overrides, so default activity ids (and completions) stay stable.
+
+ALTER TABLE groupironman.custom_activities ADD COLUMN IF NOT EXISTS group_id BIGINT
+    REFERENCES groupironman.groups(group_id) ON DELETE CASCADE;
+ALTER TABLE groupironman.custom_activities ADD COLUMN IF NOT EXISTS is_disabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE groupironman.custom_activities ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+-- Index for faster catalog lookup by group
+CREATE INDEX IF NOT EXISTS idx_custom_activities_group_id ON groupironman.custom_activities(group_id);
+
+-- Per-group changes to a global default. NULL columns inherit the default's value.
+CREATE TABLE IF NOT EXISTS groupironman.group_activity_overrides (
+    group_id BIGINT NOT NULL REFERENCES groupironman.groups(group_id) ON DELETE CASCADE,
+    activity_id INT NOT NULL REFERENCES groupironman.custom_activities(activity_id) ON DELETE CASCADE,
+    activity_name VARCHAR(100),
+    activity_description TEXT,
+    point_value INT,
+    category VARCHAR(50),
+    icon_url VARCHAR(255),
+    required_proof BOOLEAN,
+    is_disabled BOOLEAN,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (group_id, activity_id)
+);
+
+-- The catalog a group sees: global defaults with the group's overrides applied, plus its own activities
+CREATE OR REPLACE FUNCTION groupironman.group_activity_catalog(p_group_id BIGINT)
+RETURNS TABLE (
+    activity_id INT,
+    activity_name VARCHAR,
+    activity_description TEXT,
+    point_value INT,
+    category VARCHAR,
+    icon_url VARCHAR,
+    required_proof BOOLEAN,
+    is_disabled BOOLEAN,
+    is_default BOOLEAN
+)
+LANGUAGE sql STABLE AS $$
+    SELECT a.activity_id,
+           COALESCE(o.activity_name, a.activity_name),
+           COALESCE(o.activity_description, a.activity_description),
+           COALESCE(o.point_value, a.point_value, 0),
+           COALESCE(o.category, a.category),
+           COALESCE(o.icon_url, a.icon_url),
+           COALESCE(o.required_proof, a.required_proof, FALSE),
+           COALESCE(o.is_disabled, a.is_disabled),
+           a.group_id IS NULL
+    FROM groupironman.custom_activities a
+    LEFT JOIN groupironman.group_activity_overrides o
+        ON o.activity_id = a.activity_id AND o.group_id = p_group_id
+    WHERE a.group_id IS NULL OR a.group_id = p_group_id
+$$;
@@ -0,0 +1,7 @@
+-- Per-group scoring rules document; groups without a row use the server defaults
+CREATE TABLE IF NOT EXISTS groupironman.group_scoring_rules (
+    group_id BIGINT PRIMARY KEY REFERENCES groupironman.groups(group_id) ON DELETE CASCADE,
+    rules JSONB NOT NULL,
+    updated_by TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,15 @@
+-- Plugin submissions already processed, keyed by the idempotency key the plugin sends.
+-- A retried submission returns the stored response instead of being processed again.
+CREATE TABLE IF NOT EXISTS groupironman.plugin_submissions (
+    group_id BIGINT NOT NULL REFERENCES groupironman.groups(group_id) ON DELETE CASCADE,
+    idempotency_key VARCHAR(100) NOT NULL,
+    member_id BIGINT NOT NULL REFERENCES groupironman.members(member_id) ON DELETE CASCADE,
+    -- PluginUpdateResponse returned for the original submission
+    response JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (group_id, idempotency_key)
+);
+
+-- Index for clearing out old keys
+CREATE INDEX IF NOT EXISTS idx_plugin_submissions_created ON groupironman.plugin_submissions(created_at);
@@ -0,0 +1,58 @@
+-- Append-only points ledger
+-- Every award and deduction is recorded as an event; totals are derived from the events.
+
+CREATE TABLE IF NOT EXISTS groupironman.point_events (
+    event_id BIGSERIAL PRIMARY KEY,
+    member_id BIGINT NOT NULL REFERENCES groupironman.members(member_id) ON DELETE CASCADE,
+    point_type VARCHAR(50) NOT NULL,
+    -- Positive for awards, negative for deductions
+    points INT NOT NULL,
+    reason TEXT,
+    -- What produced the event (manual, activity, boss_kill, reversal, ...)
+    source_type VARCHAR(50) NOT NULL,
+    -- Identifier of the row or submission that produced the event
+    source_id VARCHAR(100),
+    -- Set when this event reverses an earlier one
+    reverses_event_id BIGINT REFERENCES groupironman.point_events(event_id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Index for faster ledger retrieval by member
+CREATE INDEX IF NOT EXISTS idx_point_events_member ON groupironman.point_events(member_id, created_at);
+-- Index for faster retrieval by point type
+CREATE INDEX IF NOT EXISTS idx_point_events_type ON groupironman.point_events(point_type);
+-- Index for looking up the events produced by a source
+CREATE INDEX IF NOT EXISTS idx_point_events_source ON groupironman.point_events(source_type, source_id);
+-- An event can only be reversed once
+CREATE UNIQUE INDEX IF NOT EXISTS idx_point_events_reverses ON groupironman.point_events(reverses_event_id)
+    WHERE reverses_event_id IS NOT NULL;
+
+-- Events are never edited; corrections are made by appending a reversal
+CREATE OR REPLACE FUNCTION groupironman.point_events_immutable() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'point_events is append-only, append a reversal instead';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS point_events_no_update ON groupironman.point_events;
+CREATE TRIGGER point_events_no_update
+    BEFORE UPDATE ON groupironman.point_events
+    FOR EACH ROW EXECUTE FUNCTION groupironman.point_events_immutable();
+
+-- Carry existing totals over as opening balances
+INSERT INTO groupironman.point_events (member_id, point_type, points, reason, source_type, created_at)
+SELECT member_id, point_type, points, 'Opening balance', 'migration', COALESCE(last_updated, NOW())
+FROM groupironman.player_points
+WHERE points IS NOT NULL AND points <> 0;
+
+-- The mutable totals table is kept for reference and replaced by a derived view
+ALTER TABLE groupironman.player_points RENAME TO player_points_legacy;
+
+CREATE OR REPLACE VIEW groupironman.player_points AS
+SELECT
+    member_id,
+    point_type,
+    SUM(points)::INT AS points,
+    MAX(created_at) AS last_updated
+FROM groupironman.point_events
+GROUP BY member_id, point_type;
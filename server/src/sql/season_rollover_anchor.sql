@@ -0,0 +1,6 @@
+-- Boundary a rolling series of seasons is measured from, so monthly seasons keep
+-- ending on the same day of the month instead of drifting after a short month.
+ALTER TABLE groupironman.seasons ADD COLUMN IF NOT EXISTS rollover_anchor TIMESTAMPTZ;
+
+UPDATE groupironman.seasons SET rollover_anchor = ends_at
+WHERE rollover IS NOT NULL AND rollover_anchor IS NULL;
@@ -0,0 +1,38 @@
+-- Seasons: group competitions whose points reset at each boundary.
+-- Season points are derived from point_events inside [starts_at, ends_at); lifetime totals are unaffected.
+
+CREATE TABLE IF NOT EXISTS groupironman.seasons (
+    season_id BIGSERIAL PRIMARY KEY,
+    group_id BIGINT NOT NULL REFERENCES groupironman.groups(group_id) ON DELETE CASCADE,
+    season_number INT NOT NULL,
+    name TEXT NOT NULL,
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    -- 'weekly' or 'monthly' to create the next season automatically when this one ends
+    rollover VARCHAR(20),
+    -- Set once the final standings have been archived
+    archived_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    UNIQUE (group_id, season_number),
+    CONSTRAINT chk_seasons_window CHECK (ends_at > starts_at),
+    CONSTRAINT chk_seasons_rollover CHECK (rollover IS NULL OR rollover IN ('weekly', 'monthly'))
+);
+
+-- Index for finding a group's season at a point in time
+CREATE INDEX IF NOT EXISTS idx_seasons_group ON groupironman.seasons(group_id, starts_at);
+-- Index used by the rollover job
+CREATE INDEX IF NOT EXISTS idx_seasons_unarchived ON groupironman.seasons(ends_at) WHERE archived_at IS NULL;
+
+-- Final standings captured when a season ends. Member names are copied so
+-- the standings survive members being renamed or removed.
+CREATE TABLE IF NOT EXISTS groupironman.season_standings (
+    season_id BIGINT NOT NULL REFERENCES groupironman.seasons(season_id) ON DELETE CASCADE,
+    member_name TEXT NOT NULL,
+    rank BIGINT NOT NULL,
+    total_points BIGINT NOT NULL,
+    -- Points per point type, e.g. {"bossing": 120, "skilling": 40}
+    points_by_type JSONB NOT NULL DEFAULT '{}',
+
+    PRIMARY KEY (season_id, member_name)
+);
@@ -0,0 +1,6 @@
+-- Slayer task times are compared with timezone-aware filters and durations
+ALTER TABLE groupironman.slayer_tasks ALTER COLUMN assigned_at TYPE TIMESTAMPTZ;
+ALTER TABLE groupironman.slayer_tasks ALTER COLUMN completed_at TYPE TIMESTAMPTZ;
+
+-- Index for per-member task history in assignment order
+CREATE INDEX IF NOT EXISTS idx_slayer_tasks_member_assigned ON groupironman.slayer_tasks(member_id, assigned_at);
@@ -0,0 +1,15 @@
+-- Slayer points earned from tasks and spent at slayer masters
+CREATE TABLE IF NOT EXISTS groupironman.slayer_point_ledger (
+    entry_id BIGSERIAL PRIMARY KEY,
+    member_id BIGINT NOT NULL REFERENCES groupironman.members(member_id) ON DELETE CASCADE,
+    task_id BIGINT REFERENCES groupironman.slayer_tasks(task_id) ON DELETE SET NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('opening_balance', 'task', 'unlock', 'extend', 'block', 'skip')),
+    points INT NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_slayer_point_ledger_member ON groupironman.slayer_point_ledger(member_id, created_at);
+
+-- A task earns or costs points once
+CREATE UNIQUE INDEX IF NOT EXISTS idx_slayer_point_ledger_task ON groupironman.slayer_point_ledger(task_id, kind) WHERE task_id IS NOT NULL;
@@ -0,0 +1,8 @@
+-- Each member's slayer block list, reward unlocks and slayer-relevant quests
+CREATE TABLE IF NOT EXISTS groupironman.slayer_preferences (
+    member_id BIGINT PRIMARY KEY REFERENCES groupironman.members(member_id) ON DELETE CASCADE,
+    blocked_tasks TEXT[] NOT NULL DEFAULT '{}',
+    unlocks TEXT[] NOT NULL DEFAULT '{}',
+    completed_quests TEXT[] NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,13 @@
+-- Kills-remaining reports from the plugin while a task is in progress
+CREATE TABLE IF NOT EXISTS groupironman.slayer_task_progress (
+    progress_id BIGSERIAL PRIMARY KEY,
+    task_id BIGINT NOT NULL REFERENCES groupironman.slayer_tasks(task_id) ON DELETE CASCADE,
+    kills_remaining INT NOT NULL CHECK (kills_remaining >= 0),
+    reported_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_slayer_task_progress_task ON groupironman.slayer_task_progress(task_id, reported_at);
+
+-- Latest reported count, and when a task was replaced before it was finished
+ALTER TABLE groupironman.slayer_tasks ADD COLUMN IF NOT EXISTS kills_remaining INT;
+ALTER TABLE groupironman.slayer_tasks ADD COLUMN IF NOT EXISTS skipped_at TIMESTAMPTZ;
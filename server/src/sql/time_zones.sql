@@ -0,0 +1,4 @@
+-- IANA time zone names used for day, week and month boundaries. A member without
+-- their own time zone uses the group's.
+ALTER TABLE groupironman.groups ADD COLUMN IF NOT EXISTS time_zone TEXT NOT NULL DEFAULT 'UTC';
+ALTER TABLE groupironman.members ADD COLUMN IF NOT EXISTS time_zone TEXT;
@@ -0,0 +1,3 @@
+-- Keyset pagination orders drops by the sort column and then drop_id
+CREATE INDEX IF NOT EXISTS idx_valuable_drops_member_timestamp ON groupironman.valuable_drops(member_id, timestamp DESC, drop_id DESC);
+CREATE INDEX IF NOT EXISTS idx_valuable_drops_member_value ON groupironman.valuable_drops(member_id, item_value DESC, drop_id DESC);
@@ -0,0 +1,10 @@
+-- Drop times are compared with timezone-aware filters
+ALTER TABLE groupironman.valuable_drops ALTER COLUMN timestamp TYPE TIMESTAMPTZ;
+
+-- Values are computed by the server; keep what the client sent for comparison
+ALTER TABLE groupironman.valuable_drops ADD COLUMN IF NOT EXISTS reported_value BIGINT;
+ALTER TABLE groupironman.valuable_drops ADD COLUMN IF NOT EXISTS value_source TEXT NOT NULL DEFAULT 'reported'
+    CHECK (value_source IN ('ge', 'alch', 'reported', 'unknown'));
+ALTER TABLE groupironman.valuable_drops ADD COLUMN IF NOT EXISTS valued_at TIMESTAMPTZ;
+
+UPDATE groupironman.valuable_drops SET reported_value = item_value WHERE reported_value IS NULL;
@@ -0,0 +1,207 @@
+use crate::auth_middleware::AuthedGroupId;
+use crate::db::get_member_id;
+use crate::error::ApiError;
+use crate::models::SHARED_MEMBER;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use deadpool_postgres::{GenericClient, Pool};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod time_zone_tests {
+    use super::*;
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn time_zone_names() {
+        assert_eq!(parse_time_zone("Europe/London"), Ok(chrono_tz::Europe::London));
+        assert!(parse_time_zone("Mars/Olympus_Mons").is_err());
+        assert!(parse_time_zone("").is_err());
+    }
+
+    #[test]
+    fn local_times_across_dst_changes() {
+        let new_york = chrono_tz::America::New_York;
+        assert_eq!(local_to_utc(new_york, local(2024, 6, 5, 19, 0)), Utc.with_ymd_and_hms(2024, 6, 5, 23, 0, 0).unwrap());
+        // 02:30 doesn't exist on the day clocks go forward
+        assert_eq!(local_to_utc(new_york, local(2024, 3, 10, 2, 30)), Utc.with_ymd_and_hms(2024, 3, 10, 7, 30, 0).unwrap());
+        // 01:30 happens twice on the day clocks go back; the first one is used
+        assert_eq!(local_to_utc(new_york, local(2024, 11, 3, 1, 30)), Utc.with_ymd_and_hms(2024, 11, 3, 5, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn days_start_at_local_midnight() {
+        let sydney = chrono_tz::Australia::Sydney;
+        let date = NaiveDate::from_ymd_opt(2024, 6, 5).unwrap();
+        assert_eq!(start_of_local_day(sydney, date), Utc.with_ymd_and_hms(2024, 6, 4, 14, 0, 0).unwrap());
+        assert_eq!(local_date(sydney, Utc.with_ymd_and_hms(2024, 6, 4, 15, 0, 0).unwrap()), date);
+    }
+}
+
+/// Parse an IANA time zone name such as "Europe/London"
+pub fn parse_time_zone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>()
+        .map_err(|_| format!("Unknown time zone '{}'", name))
+}
+
+/// The instant a local date and time happens in the time zone. Times skipped when
+/// clocks go forward are moved an hour later; repeated times use the first one.
+pub fn local_to_utc(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map_or_else(|| Utc.from_utc_datetime(&local), |time| time.with_timezone(&Utc))
+}
+
+/// Local midnight at the start of the date
+pub fn start_of_local_day(tz: Tz, date: NaiveDate) -> DateTime<Utc> {
+    local_to_utc(tz, date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+/// The local date at an instant
+pub fn local_date(tz: Tz, time: DateTime<Utc>) -> NaiveDate {
+    time.with_timezone(&tz).date_naive()
+}
+
+/// The group's time zone. Stored names are validated, but anything unknown falls back to UTC.
+pub async fn group_time_zone<C: GenericClient>(client: &C, group_id: i64) -> Result<Tz, tokio_postgres::Error> {
+    let row = client
+        .query_opt("SELECT time_zone FROM groupironman.groups WHERE group_id = $1", &[&group_id])
+        .await?;
+    let name: Option<String> = match row {
+        Some(row) => row.try_get(0)?,
+        None => None,
+    };
+    Ok(name.and_then(|name| parse_time_zone(&name).ok()).unwrap_or(Tz::UTC))
+}
+
+/// The group's time zone and the members' own time zones, if they set one
+#[derive(Serialize)]
+pub struct TimeZoneSettings {
+    time_zone: String,
+    members: BTreeMap<String, Option<String>>,
+}
+
+/// Request to change a time zone. Members can clear theirs to use the group's.
+#[derive(Deserialize)]
+pub struct UpdateTimeZoneRequest {
+    time_zone: Option<String>,
+}
+
+/// Check the name is a zone both chrono-tz and the database know, since skill
+/// aggregation converts times with AT TIME ZONE and fails on zones Postgres lacks
+async fn validate_time_zone<C: GenericClient>(client: &C, name: &str) -> Result<Result<String, HttpResponse>, ApiError> {
+    let bad_request = |message: String| {
+        HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": message
+        }))
+    };
+
+    let time_zone = match parse_time_zone(name) {
+        Ok(tz) => tz.name().to_string(),
+        Err(message) => return Ok(Err(bad_request(message))),
+    };
+    let known: bool = client
+        .query_one(
+            "SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)",
+            &[&time_zone],
+        )
+        .await?
+        .try_get(0)?;
+    if !known {
+        return Ok(Err(bad_request(format!("Time zone '{}' is not supported by the database", time_zone))));
+    }
+
+    Ok(Ok(time_zone))
+}
+
+/// Get the group's and members' time zones
+pub async fn get_time_zones(pool: web::Data<Pool>, auth: AuthedGroupId) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let time_zone = group_time_zone(&client, auth.group_id).await?;
+
+    let rows = client
+        .query(
+            "SELECT member_name, time_zone FROM groupironman.members WHERE group_id = $1 AND member_name != $2",
+            &[&auth.group_id, &SHARED_MEMBER],
+        )
+        .await?;
+    let mut members = BTreeMap::new();
+    for row in &rows {
+        members.insert(row.try_get(0)?, row.try_get(1)?);
+    }
+
+    Ok(HttpResponse::Ok().json(TimeZoneSettings {
+        time_zone: time_zone.name().to_string(),
+        members,
+    }))
+}
+
+/// Set the group's time zone, used for calendar days and day, week and month boundaries.
+///
+/// Skill history already aggregated keeps its old boundaries, so the day, month or
+/// year in progress can end up with one bucket per time zone.
+pub async fn update_group_time_zone(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    request: web::Json<UpdateTimeZoneRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let time_zone = match request.time_zone.as_deref() {
+        Some(name) => match validate_time_zone(&client, name).await? {
+            Ok(time_zone) => time_zone,
+            Err(response) => return Ok(response),
+        },
+        None => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": "time_zone is required"
+            })));
+        }
+    };
+
+    client
+        .execute(
+            "UPDATE groupironman.groups SET time_zone = $1 WHERE group_id = $2",
+            &[&time_zone, &auth.group_id],
+        )
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "time_zone": time_zone })))
+}
+
+/// Set or clear a member's own time zone, used for their skill aggregation days.
+///
+/// As with the group's time zone, buckets already aggregated are not re-keyed.
+pub async fn update_member_time_zone(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, String)>,
+    request: web::Json<UpdateTimeZoneRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, member_name) = path.into_inner();
+    let client = pool.get().await?;
+    let time_zone = match request.time_zone.as_deref() {
+        Some(name) => match validate_time_zone(&client, name).await? {
+            Ok(time_zone) => Some(time_zone),
+            Err(response) => return Ok(response),
+        },
+        None => None,
+    };
+
+    let member_id = get_member_id(&client, auth.group_id, &member_name).await?;
+    client
+        .execute(
+            "UPDATE groupironman.members SET time_zone = $1 WHERE member_id = $2",
+            &[&time_zone, &member_id],
+        )
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "time_zone": time_zone })))
+}
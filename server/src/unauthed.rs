@@ -1,7 +1,7 @@
 use crate::config::Config;
 use crate::db;
 use crate::error::ApiError;
-use crate::models::{CaptchaVerifyResponse, CreateGroup, GEPrices, WikiGEPrices};
+use crate::models::{CaptchaVerifyResponse, CreateGroup, GEPrices, WikiGEPrices, WikiPriceTimeseries};
 use crate::collection_log::COLLECTION_LOG_DATA;
 use crate::validators::valid_name;
 use actix_web::{get, post, web, Error, HttpResponse, http::header::ContentType};
@@ -14,6 +14,7 @@ use tokio::{task, time};
 
 lazy_static! {
     static ref GE_PRICES: ArcSwapAny<Arc<String>> = ArcSwap::from(Arc::new(String::default()));
+    static ref GE_PRICE_MAP: ArcSwapAny<Arc<GEPrices>> = ArcSwap::from(Arc::new(GEPrices::new()));
     static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
 }
 
@@ -32,30 +33,50 @@ pub async fn fetch_latest_prices() -> Result<WikiGEPrices, ApiError> {
     Ok(wiki_ge_prices)
 }
 
+/// Daily average prices for an item over roughly the last year
+pub async fn fetch_price_history(item_id: i32) -> Result<WikiPriceTimeseries, ApiError> {
+    let item_id = item_id.to_string();
+    let res = HTTP_CLIENT
+        .get("https://prices.runescape.wiki/api/v1/osrs/timeseries")
+        .query(&[("timestep", "24h"), ("id", item_id.as_str())])
+        .header("User-Agent", "Group Ironmen - Dprk#8740")
+        .send()
+        .await
+        .map_err(ApiError::ReqwestError)?;
+    let timeseries = res
+        .json::<WikiPriceTimeseries>()
+        .await
+        .map_err(ApiError::ReqwestError)?;
+
+    Ok(timeseries)
+}
+
+/// The latest GE prices by item id, empty until the first fetch completes
+pub fn current_ge_prices() -> Arc<GEPrices> {
+    GE_PRICE_MAP.load_full()
+}
+
 pub async fn update_ge_prices() -> Result<(), ApiError> {
     let wiki_ge_prices = fetch_latest_prices().await?;
     let mut ge_prices: GEPrices = std::collections::HashMap::new();
     for (item_id, wiki_ge_price) in wiki_ge_prices.data {
         let mut avg_ge_price: i64 = 0;
-        match wiki_ge_price.high {
-            Some(high) => avg_ge_price = high,
-            None => (),
+        if let Some(high) = wiki_ge_price.high {
+            avg_ge_price = high
         }
-        match wiki_ge_price.low {
-            Some(low) => {
-                if avg_ge_price > 0 {
-                    avg_ge_price = (avg_ge_price + low) / 2
-                } else {
-                    avg_ge_price = low
-                }
+        if let Some(low) = wiki_ge_price.low {
+            if avg_ge_price > 0 {
+                avg_ge_price = (avg_ge_price + low) / 2
+            } else {
+                avg_ge_price = low
             }
-            None => (),
         }
 
         ge_prices.insert(item_id, avg_ge_price);
     }
 
     GE_PRICES.store(Arc::new(serde_json::to_string(&ge_prices)?));
+    GE_PRICE_MAP.store(Arc::new(ge_prices));
 
     Ok(())
 }
@@ -113,7 +134,7 @@ pub fn start_skills_aggregator(db_pool: Pool) {
 #[get("/ge-prices")]
 pub async fn get_ge_prices() -> Result<HttpResponse, Error> {
     let ge_prices_opt = GE_PRICES.load();
-    let res: String = (&**ge_prices_opt).clone();
+    let res: String = (**ge_prices_opt).clone();
 
     Ok(HttpResponse::Ok()
         .append_header(("Cache-Control", "public, max-age=86400"))
@@ -169,9 +190,9 @@ pub async fn create_group(
         return Ok(HttpResponse::BadRequest().body("Provided group name is not valid"));
     }
 
-    create_group_inner.member_names.retain(|member_name| member_name.trim().len() > 0);
+    create_group_inner.member_names.retain(|member_name| !member_name.trim().is_empty());
     for member_name in &create_group_inner.member_names {
-        if !valid_name(&member_name) {
+        if !valid_name(member_name) {
             return Ok(HttpResponse::BadRequest()
                       .body(format!("Member name {} is not valid", member_name)));
         }
@@ -4,6 +4,7 @@ use crate::error::ApiError;
 use crate::collection_log::{CollectionLogInfo, CollectionLog};
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod valid_name_tests {
     use super::*;
 
@@ -74,7 +75,7 @@ pub fn valid_name(name: &str) -> bool {
     }
 
     let len = name.len();
-    (1..=16).contains(&len) && name.is_ascii() && !NAME_RE.is_match(name) && name.trim().len() > 0
+    (1..=16).contains(&len) && name.is_ascii() && !NAME_RE.is_match(name) && !name.trim().is_empty()
 }
 
 pub fn validate_member_prop_length<T>(prop_name: &str, value: &Option<Vec<T>>, min: usize, max: usize) -> Result<(), ApiError> {
@@ -117,9 +118,7 @@ pub fn validate_collection_log(collection_log_info: &actix_web::web::Data<Collec
                     }
                 };
 
-                if result.is_err() {
-                    return result;
-                }
+                result?;
             }
 
             Ok(())
@@ -1,10 +1,33 @@
 use crate::auth_middleware::AuthedGroupId;
-use crate::db::{get_member_id, get_group_members};
+use crate::db::get_member_id;
+use crate::drop_duplicates::{find_likely_duplicate, DropEntrySource};
 use crate::error::ApiError;
+use crate::exports::{stream_export, ExportParams};
+use crate::item_values::{revalue_drops, value_item, RevaluationRequest};
 use actix_web::{web, HttpResponse};
 use deadpool_postgres::Pool;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use data_encoding::BASE64URL_NOPAD;
+
+#[cfg(test)]
+mod valuable_drops_tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = encode_cursor(DropSort::ItemValue, &serde_json::json!(125000), 42);
+        assert_eq!(decode_cursor(&cursor, DropSort::ItemValue), Ok((DropSortKey::Value(125000), 42)));
+        assert!(decode_cursor(&cursor, DropSort::Timestamp).is_err());
+        assert!(decode_cursor("not a cursor", DropSort::ItemValue).is_err());
+    }
+
+    #[test]
+    fn item_ids_are_parsed() {
+        assert_eq!(parse_item_ids("4151, 11832,"), Ok(vec![4151, 11832]));
+        assert!(parse_item_ids("4151,whip").is_err());
+    }
+}
 
 /// Request to manually add a valuable drop
 #[derive(Deserialize)]
@@ -22,8 +45,8 @@ pub struct AddValuableDropRequest {
     #[serde(default = "default_quantity")]
     pub item_quantity: i32,
     
-    /// GE value of the item
-    pub item_value: i64,
+    /// Value of one item as the client saw it; the server values drops from GE prices
+    pub item_value: Option<i64>,
     
     /// Source where the item was obtained (e.g., monster name, chest)
     pub source_name: String,
@@ -53,14 +76,15 @@ pub struct ValuableDropsResponse {
 /// Pagination information for valuable drops
 #[derive(Serialize)]
 pub struct PaginationInfo {
-    /// Total count of drops matching the filter
-    pub total_count: i64,
-    /// Offset from the start
-    pub offset: i64,
+    /// Total count of drops matching the filter, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<i64>,
     /// Limit per page
-    pub limit: i32,
+    pub limit: i64,
     /// Whether there are more drops to load
     pub has_more: bool,
+    /// Cursor for the next page
+    pub next_cursor: Option<String>,
 }
 
 /// Valuable drop data structure
@@ -87,6 +111,15 @@ pub struct ValuableDrop {
     /// Total value (quantity * value)
     pub total_value: i64,
     
+    /// Value of one item as reported by the client
+    pub reported_value: Option<i64>,
+    
+    /// Where item_value came from: ge, alch, reported or unknown
+    pub value_source: String,
+    
+    /// How the drop was recorded: plugin, manual or import. Unset for older drops.
+    pub entry_source: Option<String>,
+    
     /// Source where the item was obtained
     pub source_name: String,
     
@@ -120,46 +153,254 @@ pub struct GetValuableDropsParams {
     /// Optional end date filter
     pub end_date: Option<DateTime<Utc>>,
     
-    /// Pagination offset
+    /// Optional maximum value filter
+    pub max_value: Option<i64>,
+    
+    /// Optional comma-separated item IDs
+    pub item_ids: Option<String>,
+    
+    /// Optional bounding box for drop locations
+    pub min_x: Option<i32>,
+    pub max_x: Option<i32>,
+    pub min_y: Option<i32>,
+    pub max_y: Option<i32>,
+    pub plane: Option<i32>,
+    
+    /// Cursor from the previous page
+    pub cursor: Option<String>,
+    
+    /// Whether to count every drop matching the filter
     #[serde(default)]
-    pub offset: i64,
+    pub include_total: bool,
     
     /// Pagination limit
     #[serde(default = "default_limit")]
-    pub limit: i32,
+    pub limit: i64,
     
     /// Sort field
-    #[serde(default = "default_sort_field")]
-    pub sort: String,
+    #[serde(default)]
+    pub sort: DropSort,
     
     /// Sort direction (asc or desc)
     #[serde(default = "default_sort_direction")]
     pub direction: String,
 }
 
-fn default_limit() -> i32 {
+fn default_limit() -> i64 {
     25
 }
 
-fn default_sort_field() -> String {
-    "timestamp".to_string()
-}
-
 fn default_sort_direction() -> String {
     "desc".to_string()
 }
 
-/// Get valuable drops with optional filtering and pagination
+/// Column the drop list is sorted by
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DropSort {
+    #[default]
+    Timestamp,
+    ItemValue,
+    ItemName,
+    SourceName,
+}
+
+impl DropSort {
+    fn column(&self) -> &'static str {
+        match self {
+            DropSort::Timestamp => "d.timestamp",
+            DropSort::ItemValue => "d.item_value",
+            DropSort::ItemName => "d.item_name",
+            DropSort::SourceName => "COALESCE(d.source_name, '')",
+        }
+    }
+}
+
+/// Sort column value a page ended on
+#[derive(Debug, PartialEq)]
+pub enum DropSortKey {
+    Timestamp(DateTime<Utc>),
+    Value(i64),
+    Text(String),
+}
+
+/// Position after the last drop of a page
+#[derive(Serialize, Deserialize)]
+struct DropCursor {
+    sort: DropSort,
+    key: serde_json::Value,
+    drop_id: i64,
+}
+
+fn encode_cursor(sort: DropSort, key: &serde_json::Value, drop_id: i64) -> String {
+    let cursor = DropCursor { sort, key: key.clone(), drop_id };
+    BASE64URL_NOPAD.encode(serde_json::to_string(&cursor).unwrap_or_default().as_bytes())
+}
+
+/// Read a cursor, which is only valid for the sort it was made with
+fn decode_cursor(cursor: &str, sort: DropSort) -> Result<(DropSortKey, i64), String> {
+    let invalid = || "Invalid cursor".to_string();
+    let bytes = BASE64URL_NOPAD.decode(cursor.as_bytes()).map_err(|_| invalid())?;
+    let cursor: DropCursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    if cursor.sort != sort {
+        return Err("Cursor was made for a different sort".to_string());
+    }
+    
+    let key = match sort {
+        DropSort::Timestamp => DropSortKey::Timestamp(serde_json::from_value(cursor.key).map_err(|_| invalid())?),
+        DropSort::ItemValue => DropSortKey::Value(serde_json::from_value(cursor.key).map_err(|_| invalid())?),
+        DropSort::ItemName | DropSort::SourceName => {
+            DropSortKey::Text(serde_json::from_value(cursor.key).map_err(|_| invalid())?)
+        }
+    };
+    Ok((key, cursor.drop_id))
+}
+
+fn parse_item_ids(item_ids: &str) -> Result<Vec<i32>, String> {
+    item_ids
+        .split(',')
+        .map(str::trim)
+        .filter(|item_id| !item_id.is_empty())
+        .map(|item_id| item_id.parse().map_err(|_| format!("Invalid item ID '{}'", item_id)))
+        .collect()
+}
+
+/// Filters shared by the drop list and the drop statistics
+pub struct DropFilter<'a> {
+    member_name: Option<&'a String>,
+    min_value: Option<&'a i64>,
+    max_value: Option<&'a i64>,
+    item_ids: Option<Vec<i32>>,
+    source_pattern: Option<String>,
+    item_pattern: Option<String>,
+    start_date: Option<&'a DateTime<Utc>>,
+    end_date: Option<&'a DateTime<Utc>>,
+    min_x: Option<&'a i32>,
+    max_x: Option<&'a i32>,
+    min_y: Option<&'a i32>,
+    max_y: Option<&'a i32>,
+    plane: Option<&'a i32>,
+}
+
+impl<'a> DropFilter<'a> {
+    pub fn new(params: &'a GetValuableDropsParams) -> Result<Self, String> {
+        Ok(DropFilter {
+            member_name: params.member_name.as_ref(),
+            min_value: params.min_value.as_ref(),
+            max_value: params.max_value.as_ref(),
+            item_ids: params.item_ids.as_deref().map(parse_item_ids).transpose()?,
+            source_pattern: params.source_name.as_ref().map(|source_name| format!("%{}%", source_name)),
+            item_pattern: params.item_name.as_ref().map(|item_name| format!("%{}%", item_name)),
+            start_date: params.start_date.as_ref(),
+            end_date: params.end_date.as_ref(),
+            min_x: params.min_x.as_ref(),
+            max_x: params.max_x.as_ref(),
+            min_y: params.min_y.as_ref(),
+            max_y: params.max_y.as_ref(),
+            plane: params.plane.as_ref(),
+        })
+    }
+    
+    /// Add `AND` conditions on drops `d` and members `m`, numbering parameters after those already added
+    pub fn push_conditions<'b>(
+        &'b self,
+        query_parts: &mut Vec<String>,
+        params: &mut Vec<&'b (dyn tokio_postgres::types::ToSql + Sync)>,
+    ) {
+        let mut push = |condition: &str, param: &'b (dyn tokio_postgres::types::ToSql + Sync)| {
+            query_parts.push(format!("AND {}", condition.replace("{}", &format!("${}", params.len() + 1))));
+            params.push(param);
+        };
+        
+        if let Some(member_name) = self.member_name {
+            push("m.member_name = {}", member_name);
+        }
+        if let Some(min_value) = self.min_value {
+            push("d.item_value >= {}", min_value);
+        }
+        if let Some(max_value) = self.max_value {
+            push("d.item_value <= {}", max_value);
+        }
+        if let Some(item_ids) = &self.item_ids {
+            push("d.item_id = ANY({})", item_ids);
+        }
+        if let Some(pattern) = &self.source_pattern {
+            push("d.source_name ILIKE {}", pattern);
+        }
+        if let Some(pattern) = &self.item_pattern {
+            push("d.item_name ILIKE {}", pattern);
+        }
+        if let Some(start_date) = self.start_date {
+            push("d.timestamp >= {}", start_date);
+        }
+        if let Some(end_date) = self.end_date {
+            push("d.timestamp <= {}", end_date);
+        }
+        if let Some(min_x) = self.min_x {
+            push("d.x_coord >= {}", min_x);
+        }
+        if let Some(max_x) = self.max_x {
+            push("d.x_coord <= {}", max_x);
+        }
+        if let Some(min_y) = self.min_y {
+            push("d.y_coord >= {}", min_y);
+        }
+        if let Some(max_y) = self.max_y {
+            push("d.y_coord <= {}", max_y);
+        }
+        if let Some(plane) = self.plane {
+            push("d.z_coord = {}", plane);
+        }
+    }
+}
+
+fn drop_from_row(row: &tokio_postgres::Row) -> Result<ValuableDrop, tokio_postgres::Error> {
+    Ok(ValuableDrop {
+        drop_id: row.try_get("drop_id")?,
+        member_name: row.try_get("member_name")?,
+        item_id: row.try_get("item_id")?,
+        item_name: row.try_get("item_name")?,
+        item_quantity: row.try_get("item_quantity")?,
+        item_value: row.try_get("item_value")?,
+        total_value: row.try_get::<_, i32>("item_quantity")? as i64 * row.try_get::<_, i64>("item_value")?,
+        reported_value: row.try_get("reported_value")?,
+        value_source: row.try_get("value_source")?,
+        entry_source: row.try_get("entry_source")?,
+        source_name: row.try_get("source_name")?,
+        x_coord: row.try_get("x_coord")?,
+        y_coord: row.try_get("y_coord")?,
+        z_coord: row.try_get("z_coord")?,
+        timestamp: row.try_get("timestamp")?,
+    })
+}
+
+/// Get valuable drops with optional filtering and cursor pagination
+/// 
+/// Pages are keyed on the sort column plus `drop_id`; pass `next_cursor` from
+/// one page as `cursor` to get the next. The total count is only computed
+/// when `include_total` is set.
 pub async fn get_valuable_drops(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
     query: web::Query<GetValuableDropsParams>,
 ) -> Result<HttpResponse, ApiError> {
-    let mut client = pool.get().await?;
+    let bad_request = |message: String| {
+        HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": message
+        }))
+    };
     
-    // Get member IDs for the group
-    let members = get_group_members(&client, auth.0).await?;
-    let member_ids: Vec<i64> = members.iter().map(|m| m.member_id).collect();
+    let filter = match DropFilter::new(&query) {
+        Ok(filter) => filter,
+        Err(message) => return Ok(bad_request(message)),
+    };
+    let cursor = match query.cursor.as_deref().map(|cursor| decode_cursor(cursor, query.sort)).transpose() {
+        Ok(cursor) => cursor,
+        Err(message) => return Ok(bad_request(message)),
+    };
+    let limit = query.limit.clamp(1, 200);
+    let client = pool.get().await?;
     
     // Start building the query
     let mut query_parts = Vec::new();
@@ -167,158 +408,112 @@ pub async fn get_valuable_drops(
     
     // Base query
     query_parts.push(String::from(
-        "SELECT 
-            d.drop_id,
-            m.member_name,
-            d.item_id,
-            d.item_name,
-            d.item_quantity,
-            d.item_value,
-            d.source_name,
-            d.x_coord,
-            d.y_coord,
-            d.z_coord,
-            d.timestamp,
-            COUNT(*) OVER() AS total_count
-         FROM 
+        "FROM 
             groupironman.valuable_drops d
             JOIN groupironman.members m ON d.member_id = m.member_id
          WHERE 
             m.group_id = $1"
     ));
-    params.push(&auth.0);
+    params.push(&auth.group_id);
     
     // Add filters
-    let param_offset = params.len() + 1;
-    let mut param_index = param_offset;
+    filter.push_conditions(&mut query_parts, &mut params);
     
-    // Filter by member if provided
-    if let Some(member_name) = &query.member_name {
-        // Get member ID for the provided name
-        let member_id_opt = members.iter().find(|m| m.member_name == *member_name).map(|m| m.member_id);
-        
-        if let Some(member_id) = member_id_opt {
-            query_parts.push(format!("AND d.member_id = ${}", param_index));
-            params.push(&member_id);
-            param_index += 1;
-        } else {
-            // If member not found, return empty result
-            return Ok(HttpResponse::Ok().json(ValuableDropsResponse {
-                drops: Vec::new(),
-                pagination: PaginationInfo {
-                    total_count: 0,
-                    offset: query.offset,
-                    limit: query.limit,
-                    has_more: false,
-                },
-            }));
-        }
-    }
-    
-    // Filter by minimum value
-    if let Some(min_value) = query.min_value {
-        query_parts.push(format!("AND d.item_value >= ${}", param_index));
-        params.push(&min_value);
-        param_index += 1;
-    }
-    
-    // Filter by source name
-    if let Some(source_name) = &query.source_name {
-        let pattern = format!("%{}%", source_name);
-        query_parts.push(format!("AND d.source_name ILIKE ${}", param_index));
-        params.push(&pattern);
-        param_index += 1;
-    }
-    
-    // Filter by item name
-    if let Some(item_name) = &query.item_name {
-        let pattern = format!("%{}%", item_name);
-        query_parts.push(format!("AND d.item_name ILIKE ${}", param_index));
-        params.push(&pattern);
-        param_index += 1;
-    }
-    
-    // Filter by date range
-    if let Some(start_date) = query.start_date {
-        query_parts.push(format!("AND d.timestamp >= ${}", param_index));
-        params.push(&start_date);
-        param_index += 1;
-    }
-    
-    if let Some(end_date) = query.end_date {
-        query_parts.push(format!("AND d.timestamp <= ${}", param_index));
-        params.push(&end_date);
-        param_index += 1;
-    }
-    
-    // Add ordering
-    // Validate sort field to prevent SQL injection
-    let valid_sort_fields = vec!["timestamp", "item_value", "item_name", "source_name"];
-    let sort_field = if valid_sort_fields.contains(&query.sort.as_str()) {
-        &query.sort
+    // Count every matching drop, ignoring the cursor
+    let total_count = if query.include_total {
+        let count_stmt = client.prepare(&format!("SELECT COUNT(*) {}", query_parts.join(" "))).await?;
+        Some(client.query_one(&count_stmt, &params).await?.try_get::<_, i64>(0)?)
     } else {
-        "timestamp"
+        None
     };
     
-    // Validate sort direction to prevent SQL injection
-    let direction = if query.direction == "asc" { "ASC" } else { "DESC" };
-    query_parts.push(format!("ORDER BY {} {}", sort_field, direction));
-    
-    // Add pagination
-    query_parts.push(format!("LIMIT ${}", param_index));
-    params.push(&query.limit);
-    param_index += 1;
+    // Continue after the cursor, in the sort direction
+    let sort_column = query.sort.column();
+    let (direction, comparison) = if query.direction == "asc" { ("ASC", ">") } else { ("DESC", "<") };
+    if let Some((key, drop_id)) = &cursor {
+        let key: &(dyn tokio_postgres::types::ToSql + Sync) = match key {
+            DropSortKey::Timestamp(timestamp) => timestamp,
+            DropSortKey::Value(value) => value,
+            DropSortKey::Text(text) => text,
+        };
+        query_parts.push(format!(
+            "AND ({}, d.drop_id) {} (${}, ${})",
+            sort_column,
+            comparison,
+            params.len() + 1,
+            params.len() + 2
+        ));
+        params.push(key);
+        params.push(drop_id);
+    }
     
-    query_parts.push(format!("OFFSET ${}", param_index));
-    params.push(&query.offset);
+    // Fetch one extra row to know whether there is another page
+    let fetch_limit = limit + 1;
+    query_parts.push(format!(
+        "ORDER BY {} {}, d.drop_id {} LIMIT ${}",
+        sort_column,
+        direction,
+        direction,
+        params.len() + 1
+    ));
+    params.push(&fetch_limit);
     
     // Build the final query
-    let query_string = query_parts.join(" ");
+    let query_string = format!(
+        "SELECT 
+            d.drop_id,
+            m.member_name,
+            d.item_id,
+            d.item_name,
+            d.item_quantity,
+            d.item_value,
+            d.reported_value,
+            d.value_source,
+            d.entry_source,
+            d.source_name,
+            d.x_coord,
+            d.y_coord,
+            d.z_coord,
+            d.timestamp,
+            {} AS sort_key
+         {}",
+        sort_column,
+        query_parts.join(" ")
+    );
     
     // Prepare and execute the query
     let stmt = client.prepare(&query_string).await?;
-    let rows = client.query(&stmt, &params).await?;
-    
-    // Process results
-    let mut drops = Vec::new();
-    let mut total_count: i64 = 0;
-    
-    for row in rows {
-        // Get total count from first row
-        if total_count == 0 {
-            total_count = row.try_get::<_, i64>("total_count")?;
+    let mut rows = client.query(&stmt, &params).await?;
+    
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    
+    // Cursor from the last drop on the page
+    let next_cursor = match rows.last() {
+        Some(row) if has_more => {
+            let key = match query.sort {
+                DropSort::Timestamp => serde_json::to_value(row.try_get::<_, DateTime<Utc>>("sort_key")?)?,
+                DropSort::ItemValue => serde_json::to_value(row.try_get::<_, i64>("sort_key")?)?,
+                DropSort::ItemName | DropSort::SourceName => serde_json::to_value(row.try_get::<_, String>("sort_key")?)?,
+            };
+            Some(encode_cursor(query.sort, &key, row.try_get("drop_id")?))
         }
-        
-        // Convert row to ValuableDrop
-        let drop = ValuableDrop {
-            drop_id: row.try_get("drop_id")?,
-            member_name: row.try_get("member_name")?,
-            item_id: row.try_get("item_id")?,
-            item_name: row.try_get("item_name")?,
-            item_quantity: row.try_get("item_quantity")?,
-            item_value: row.try_get("item_value")?,
-            total_value: row.try_get::<_, i32>("item_quantity")? as i64 * row.try_get::<_, i64>("item_value")?,
-            source_name: row.try_get("source_name")?,
-            x_coord: row.try_get("x_coord")?,
-            y_coord: row.try_get("y_coord")?,
-            z_coord: row.try_get("z_coord")?,
-            timestamp: row.try_get("timestamp")?,
-        };
-        
-        drops.push(drop);
-    }
+        _ => None,
+    };
     
-    // Calculate if there are more results
-    let has_more = (query.offset + drops.len() as i64) < total_count;
+    let drops = rows
+        .iter()
+        .map(drop_from_row)
+        .collect::<Result<Vec<_>, tokio_postgres::Error>>()?;
     
     // Return response
     Ok(HttpResponse::Ok().json(ValuableDropsResponse {
         drops,
         pagination: PaginationInfo {
             total_count,
-            offset: query.offset,
-            limit: query.limit,
+            limit,
             has_more,
+            next_cursor,
         },
     }))
 }
@@ -329,20 +524,43 @@ pub async fn add_valuable_drop(
     auth: AuthedGroupId,
     drop_data: web::Json<AddValuableDropRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    let mut client = pool.get().await?;
+    let client = pool.get().await?;
     
     // Get member ID for the provided name
-    let member_id = get_member_id(&client, auth.0, &drop_data.member_name).await?;
+    let member_id = get_member_id(&client, auth.group_id, &drop_data.member_name).await?;
     
     // Use current timestamp if not provided
     let timestamp = drop_data.timestamp.unwrap_or_else(Utc::now);
     
+    // A drop the plugin already recorded isn't added again
+    let existing_drop_id = find_likely_duplicate(
+        &client,
+        member_id,
+        drop_data.item_id,
+        drop_data.item_quantity,
+        &drop_data.source_name,
+        timestamp,
+        DropEntrySource::Manual,
+    )
+    .await?;
+    if let Some(existing_drop_id) = existing_drop_id {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "status": "duplicate",
+            "message": "This drop has already been recorded",
+            "drop_id": existing_drop_id,
+        })));
+    }
+    
+    // Value the drop from GE prices, falling back to alch value and then the reported value
+    let (item_value, value_source) = value_item(drop_data.item_id, drop_data.item_value);
+    
     // Insert the valuable drop
     let stmt = client
         .prepare_cached(
             "INSERT INTO groupironman.valuable_drops 
-             (member_id, item_id, item_name, item_quantity, item_value, source_name, x_coord, y_coord, z_coord, timestamp) 
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             (member_id, item_id, item_name, item_quantity, item_value, source_name, x_coord, y_coord, z_coord, timestamp,
+              reported_value, value_source, valued_at, entry_source) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, NOW(), 'manual')
              RETURNING drop_id"
         )
         .await?;
@@ -355,12 +573,14 @@ pub async fn add_valuable_drop(
                 &drop_data.item_id,
                 &drop_data.item_name,
                 &drop_data.item_quantity,
-                &drop_data.item_value,
+                &item_value,
                 &drop_data.source_name,
                 &drop_data.x_coord,
                 &drop_data.y_coord,
                 &drop_data.z_coord,
                 &timestamp,
+                &drop_data.item_value,
+                &value_source.as_str(),
             ]
         )
         .await?
@@ -374,17 +594,60 @@ pub async fn add_valuable_drop(
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": "success",
         "message": "Valuable drop added successfully",
-        "drop_id": drop_id
+        "drop_id": drop_id,
+        "item_value": item_value,
+        "value_source": value_source,
     })))
 }
 
 /// Delete a valuable drop by ID
+pub async fn get_valuable_drop(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    path: web::Path<(String, i64)>,
+) -> Result<HttpResponse, ApiError> {
+    let (_group_name, drop_id) = path.into_inner();
+    let client = pool.get().await?;
+    
+    let stmt = client
+        .prepare_cached(
+            "SELECT
+                d.drop_id,
+                m.member_name,
+                d.item_id,
+                d.item_name,
+                d.item_quantity,
+                d.item_value,
+                d.reported_value,
+                d.value_source,
+                d.entry_source,
+                d.source_name,
+                d.x_coord,
+                d.y_coord,
+                d.z_coord,
+                d.timestamp
+             FROM groupironman.valuable_drops d
+             JOIN groupironman.members m ON d.member_id = m.member_id
+             WHERE d.drop_id = $1 AND m.group_id = $2"
+        )
+        .await?;
+    
+    match client.query_opt(&stmt, &[&drop_id, &auth.group_id]).await? {
+        Some(row) => Ok(HttpResponse::Ok().json(drop_from_row(&row)?)),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "Valuable drop not found or not in your group"
+        }))),
+    }
+}
+
 pub async fn delete_valuable_drop(
     pool: web::Data<Pool>,
     auth: AuthedGroupId,
-    drop_id: web::Path<i64>,
+    path: web::Path<(String, i64)>,
 ) -> Result<HttpResponse, ApiError> {
-    let mut client = pool.get().await?;
+    let (_group_name, drop_id) = path.into_inner();
+    let client = pool.get().await?;
     
     // Delete the drop, ensuring it belongs to the group
     let stmt = client
@@ -398,7 +661,7 @@ pub async fn delete_valuable_drop(
         .await?;
     
     let result = client
-        .query_opt(&stmt, &[&drop_id.into_inner(), &auth.0])
+        .query_opt(&stmt, &[&drop_id, &auth.group_id])
         .await?;
     
     // Check if the drop was found and deleted
@@ -415,3 +678,337 @@ pub async fn delete_valuable_drop(
         "message": "Valuable drop deleted successfully"
     })))
 }
+
+/// Recompute the group's drop values at current or at-the-time GE prices
+/// 
+/// Untradeable items are valued at their high alch value. Points already
+/// awarded for drops are not changed.
+pub async fn revalue_valuable_drops(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    request: web::Json<RevaluationRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let mut client = pool.get().await?;
+    
+    let summary = revalue_drops(&mut client, auth.group_id, &request).await?;
+    
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+/// Time bucket for drop value charts
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DropStatsBucket {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl DropStatsBucket {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DropStatsBucket::Day => "day",
+            DropStatsBucket::Week => "week",
+            DropStatsBucket::Month => "month",
+        }
+    }
+}
+
+/// Options for valuable drop statistics, alongside the usual drop filters
+#[derive(Deserialize)]
+pub struct ValuableDropStatsParams {
+    /// Number of top drops, sources and items to include
+    #[serde(default = "default_stats_top")]
+    pub top: i64,
+    
+    /// Size of the value buckets
+    #[serde(default)]
+    pub bucket: DropStatsBucket,
+}
+
+fn default_stats_top() -> i64 {
+    10
+}
+
+/// Drop totals for one member, source or item
+#[derive(Serialize)]
+pub struct DropGroupStats {
+    /// Member name, source name or item name
+    pub name: Option<String>,
+    
+    /// Item ID, for per-item stats
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_id: Option<i32>,
+    pub drop_count: i64,
+    pub total_quantity: i64,
+    pub total_value: i64,
+    
+    /// Median value of a single drop
+    pub median_value: Option<f64>,
+}
+
+/// Drop value received in one time bucket
+#[derive(Serialize)]
+pub struct DropValueBucket {
+    pub bucket_start: DateTime<Utc>,
+    pub drop_count: i64,
+    pub total_value: i64,
+}
+
+/// Aggregates over the group's valuable drops
+#[derive(Serialize)]
+pub struct ValuableDropStats {
+    pub drop_count: i64,
+    pub total_value: i64,
+    pub median_value: Option<f64>,
+    pub members: Vec<DropGroupStats>,
+    pub sources: Vec<DropGroupStats>,
+    pub items: Vec<DropGroupStats>,
+    pub top_drops: Vec<ValuableDrop>,
+    pub bucket: DropStatsBucket,
+    pub buckets: Vec<DropValueBucket>,
+}
+
+fn group_stats_from_row(row: &tokio_postgres::Row, has_item_id: bool) -> Result<DropGroupStats, tokio_postgres::Error> {
+    Ok(DropGroupStats {
+        name: row.try_get("name")?,
+        item_id: if has_item_id { row.try_get("item_id")? } else { None },
+        drop_count: row.try_get("drop_count")?,
+        total_quantity: row.try_get("total_quantity")?,
+        total_value: row.try_get("total_value")?,
+        median_value: row.try_get("median_value")?,
+    })
+}
+
+/// Get valuable drop statistics
+/// 
+/// Accepts the same filters as the drop list and returns:
+/// - Total and median value per member, source and item
+/// - The most valuable drops
+/// - Value received per day, week or month
+pub async fn get_valuable_drop_stats(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    query: web::Query<GetValuableDropsParams>,
+    options: web::Query<ValuableDropStatsParams>,
+) -> Result<HttpResponse, ApiError> {
+    let client = pool.get().await?;
+    let top = options.top.clamp(1, 100);
+    
+    // Filtered drops shared by every aggregate
+    let mut query_parts = vec![String::from(
+        "SELECT d.*, m.member_name, d.item_value * COALESCE(d.item_quantity, 1) AS drop_value
+         FROM groupironman.valuable_drops d
+         JOIN groupironman.members m ON d.member_id = m.member_id
+         WHERE m.group_id = $1"
+    )];
+    let filter = match DropFilter::new(&query) {
+        Ok(filter) => filter,
+        Err(message) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": message
+            })));
+        }
+    };
+    let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&auth.group_id];
+    filter.push_conditions(&mut query_parts, &mut params);
+    let drops = query_parts.join(" ");
+    let top_param = params.len() + 1;
+    let mut top_params = params.clone();
+    top_params.push(&top);
+    
+    let group_stats = |name: &str, group_by: &str, limit: bool| {
+        format!(
+            "WITH drops AS ({})
+             SELECT {} AS name, {}
+                COUNT(*) AS drop_count,
+                COALESCE(SUM(COALESCE(item_quantity, 1)), 0)::BIGINT AS total_quantity,
+                COALESCE(SUM(drop_value), 0)::BIGINT AS total_value,
+                PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY drop_value) AS median_value
+             FROM drops
+             GROUP BY {}
+             ORDER BY total_value DESC, drop_count DESC
+             {}",
+            drops,
+            name,
+            if group_by == "item_id" { "item_id," } else { "" },
+            group_by,
+            if limit { format!("LIMIT ${}", top_param) } else { String::new() },
+        )
+    };
+    
+    // Overall totals
+    let summary_stmt = client
+        .prepare(&format!(
+            "WITH drops AS ({})
+             SELECT COUNT(*), COALESCE(SUM(drop_value), 0)::BIGINT,
+                PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY drop_value)
+             FROM drops",
+            drops
+        ))
+        .await?;
+    let summary = client.query_one(&summary_stmt, &params).await?;
+    
+    // Per member, source and item
+    let members_stmt = client.prepare(&group_stats("member_name", "member_name", false)).await?;
+    let members = client
+        .query(&members_stmt, &params)
+        .await?
+        .iter()
+        .map(|row| group_stats_from_row(row, false))
+        .collect::<Result<Vec<_>, tokio_postgres::Error>>()?;
+    
+    let sources_stmt = client.prepare(&group_stats("source_name", "source_name", true)).await?;
+    let sources = client
+        .query(&sources_stmt, &top_params)
+        .await?
+        .iter()
+        .map(|row| group_stats_from_row(row, false))
+        .collect::<Result<Vec<_>, tokio_postgres::Error>>()?;
+    
+    let items_stmt = client.prepare(&group_stats("MAX(item_name)", "item_id", true)).await?;
+    let items = client
+        .query(&items_stmt, &top_params)
+        .await?
+        .iter()
+        .map(|row| group_stats_from_row(row, true))
+        .collect::<Result<Vec<_>, tokio_postgres::Error>>()?;
+    
+    // Most valuable single drops
+    let top_stmt = client
+        .prepare(&format!(
+            "WITH drops AS ({})
+             SELECT * FROM drops
+             ORDER BY drop_value DESC, timestamp DESC
+             LIMIT ${}",
+            drops, top_param
+        ))
+        .await?;
+    let top_drops = client
+        .query(&top_stmt, &top_params)
+        .await?
+        .iter()
+        .map(drop_from_row)
+        .collect::<Result<Vec<_>, tokio_postgres::Error>>()?;
+    
+    // Value over time, in buckets starting at midnight in the group's time zone
+    let buckets_stmt = client
+        .prepare(&format!(
+            "WITH drops AS ({}),
+             zone AS (SELECT time_zone FROM groupironman.groups WHERE group_id = $1)
+             SELECT date_trunc('{}', timestamp AT TIME ZONE zone.time_zone) AT TIME ZONE zone.time_zone AS bucket_start,
+                COUNT(*), COALESCE(SUM(drop_value), 0)::BIGINT
+             FROM drops, zone
+             GROUP BY bucket_start
+             ORDER BY bucket_start",
+            drops,
+            options.bucket.as_str()
+        ))
+        .await?;
+    let buckets = client
+        .query(&buckets_stmt, &params)
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(DropValueBucket {
+                bucket_start: row.try_get(0)?,
+                drop_count: row.try_get(1)?,
+                total_value: row.try_get(2)?,
+            })
+        })
+        .collect::<Result<Vec<_>, tokio_postgres::Error>>()?;
+    
+    Ok(HttpResponse::Ok().json(ValuableDropStats {
+        drop_count: summary.try_get(0)?,
+        total_value: summary.try_get(1)?,
+        median_value: summary.try_get(2)?,
+        members,
+        sources,
+        items,
+        top_drops,
+        bucket: options.bucket,
+        buckets,
+    }))
+}
+
+const DROP_EXPORT_COLUMNS: &[&str] = &[
+    "drop_id",
+    "member_name",
+    "item_id",
+    "item_name",
+    "item_quantity",
+    "item_value",
+    "total_value",
+    "value_source",
+    "entry_source",
+    "source_name",
+    "x_coord",
+    "y_coord",
+    "z_coord",
+    "timestamp",
+];
+
+fn drop_export_values(row: &tokio_postgres::Row) -> Result<Vec<serde_json::Value>, tokio_postgres::Error> {
+    let drop = drop_from_row(row)?;
+    Ok(vec![
+        drop.drop_id.into(),
+        drop.member_name.into(),
+        drop.item_id.into(),
+        drop.item_name.into(),
+        drop.item_quantity.into(),
+        drop.item_value.into(),
+        drop.total_value.into(),
+        drop.value_source.into(),
+        drop.entry_source.into(),
+        drop.source_name.into(),
+        drop.x_coord.into(),
+        drop.y_coord.into(),
+        drop.z_coord.into(),
+        drop.timestamp.to_rfc3339().into(),
+    ])
+}
+
+/// Export the group's valuable drops as CSV or newline-delimited JSON
+/// 
+/// Accepts the same filters as the drop list. Rows are streamed oldest first.
+pub async fn export_valuable_drops(
+    pool: web::Data<Pool>,
+    auth: AuthedGroupId,
+    query: web::Query<GetValuableDropsParams>,
+    export: web::Query<ExportParams>,
+) -> Result<HttpResponse, ApiError> {
+    let filter = match DropFilter::new(&query) {
+        Ok(filter) => filter,
+        Err(message) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": message
+            })));
+        }
+    };
+    
+    let mut query_parts = vec![String::from(
+        "SELECT d.drop_id, m.member_name, d.item_id, d.item_name, d.item_quantity, d.item_value,
+            d.reported_value, d.value_source, d.entry_source, d.source_name, d.x_coord, d.y_coord, d.z_coord, d.timestamp
+         FROM groupironman.valuable_drops d
+         JOIN groupironman.members m ON d.member_id = m.member_id
+         WHERE m.group_id = $1"
+    )];
+    let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&auth.group_id];
+    filter.push_conditions(&mut query_parts, &mut params);
+    query_parts.push(String::from("ORDER BY d.timestamp, d.drop_id"));
+    
+    let client = pool.get().await?;
+    stream_export(
+        client,
+        &query_parts.join(" "),
+        &params,
+        export.format,
+        "valuable-drops",
+        DROP_EXPORT_COLUMNS,
+        drop_export_values,
+    )
+    .await
+}